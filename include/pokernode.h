@@ -0,0 +1,164 @@
+#ifndef POKERNODE_H
+#define POKERNODE_H
+
+/* Generated by cbindgen from src/ffi.rs, don't edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * What every call returns, the reason for anything but PN_STATUS_OK is left for pn_last_error.
+ */
+typedef enum PnStatus {
+  PN_STATUS_OK = 0,
+  PN_STATUS_NULL_POINTER = 1,
+  PN_STATUS_INVALID_ARGUMENT = 2,
+  PN_STATUS_INVALID_CARDS = 3,
+  PN_STATUS_NOT_ENOUGH_PLAYERS = 4,
+  PN_STATUS_HAND_IN_PROGRESS = 5,
+  PN_STATUS_NO_HAND_IN_PROGRESS = 6,
+  PN_STATUS_NOT_YOUR_TURN = 7,
+  PN_STATUS_UNSUPPORTED = 8,
+  PN_STATUS_ILLEGAL_BET = 9,
+  PN_STATUS_SEAT = 10,
+  PN_STATUS_CHIPS = 11,
+  PN_STATUS_NO_EVENT = 12,
+  PN_STATUS_BUFFER_TOO_SMALL = 13,
+  PN_STATUS_PANIC = 14,
+} PnStatus;
+
+typedef enum PnBet {
+  PN_BET_CHECK = 0,
+  PN_BET_FOLD = 1,
+  PN_BET_CALL = 2,
+  PN_BET_RAISE = 3,
+} PnBet;
+
+/**
+ * A table with its own event queue, from pn_game_new until pn_game_free.
+ */
+typedef struct PnGame PnGame;
+
+/**
+ * What the seat to act may do: call is 0 when it can check, min_raise and max_raise are 0 when it can't raise.
+ */
+typedef struct PnRange {
+  uint64_t call;
+  uint64_t min_raise;
+  uint64_t max_raise;
+  uint64_t all_in;
+} PnRange;
+
+/**
+ * The best five cards, rank is 1 for high card up to 9 for a straight flush.
+ */
+typedef struct PnHand {
+  uint8_t rank;
+  uint8_t cards[5];
+} PnHand;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Copies the reason the last call on this thread failed.
+ */
+enum PnStatus pn_last_error(char *buffer, size_t length, size_t *written);
+
+/**
+ * A table for game ("texas", "omaha", "omaha8") and limit ("fixed_limit", "pot_limit", "no_limit"),
+ * a seed of 0 shuffles at random.
+ */
+enum PnStatus pn_game_new(const char *game,
+                          const char *limit,
+                          uint64_t big_blind,
+                          uint8_t table_size,
+                          uint64_t seed,
+                          struct PnGame **result);
+
+void pn_game_free(struct PnGame *game);
+
+enum PnStatus pn_game_join(struct PnGame *game, uint8_t position, const char *name, uint64_t stack);
+
+/**
+ * Folds first if the hand is still live, cash_out is what the seat takes away.
+ */
+enum PnStatus pn_game_leave(struct PnGame *game, uint8_t position, uint64_t *cash_out);
+
+enum PnStatus pn_game_start(struct PnGame *game);
+
+/**
+ * The seat to act, -1 when nobody is.
+ */
+enum PnStatus pn_game_to_act(struct PnGame *game, int16_t *position);
+
+enum PnStatus pn_game_range(struct PnGame *game, uint8_t position, struct PnRange *range);
+
+/**
+ * Bet is one of PnBet, anything else is PN_STATUS_INVALID_ARGUMENT.
+ * Raises are to a total for the street, amount is ignored for checks and folds.
+ */
+enum PnStatus pn_game_act(struct PnGame *game, uint8_t position, uint8_t bet, uint64_t amount);
+
+/**
+ * Takes the next event as json, with other seats' cards hidden from viewer (-1 for a spectator, -2 for an admin).
+ * Returns PN_STATUS_NO_EVENT when there is none, an event that doesn't fit stays queued.
+ */
+enum PnStatus pn_game_poll_event(struct PnGame *game,
+                                 int16_t viewer,
+                                 char *buffer,
+                                 size_t length,
+                                 size_t *written);
+
+/**
+ * The whole table as json the way viewer (-1 for a spectator, -2 for an admin) sees it.
+ */
+enum PnStatus pn_game_table(struct PnGame *game,
+                            int16_t viewer,
+                            char *buffer,
+                            size_t length,
+                            size_t *written);
+
+/**
+ * Parses cards like "Ah Kd", count is how many there were even when they don't fit.
+ */
+enum PnStatus pn_parse_cards(const char *s, uint8_t *cards, size_t length, size_t *count);
+
+enum PnStatus pn_format_cards(const uint8_t *cards,
+                              size_t count,
+                              char *buffer,
+                              size_t length,
+                              size_t *written);
+
+/**
+ * The best high hand from a pocket and a board, omaha plays exactly two pocket cards.
+ */
+enum PnStatus pn_evaluate(const uint8_t *pocket,
+                          size_t pocket_count,
+                          const uint8_t *board,
+                          size_t board_count,
+                          bool omaha,
+                          struct PnHand *result);
+
+/**
+ * order is 1 when a beats b, -1 when b wins and 0 for a split.
+ */
+enum PnStatus pn_hand_compare(const struct PnHand *a, const struct PnHand *b, int8_t *order);
+
+/**
+ * Like "two pairs, As and 7s".
+ */
+enum PnStatus pn_hand_describe(const struct PnHand *hand,
+                               char *buffer,
+                               size_t length,
+                               size_t *written);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* POKERNODE_H */
@@ -1,30 +1,51 @@
 use std::time;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use crate::player::Player;
+use crate::chips::Chips;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", content = "amount", rename_all = "snake_case")]
 pub enum Bet {
     Check,
     Fold,
-    Raise(u32),
-    Call(u32),
-    SmallBlind(u32),
-    BigBlind(u32),
-    Straddle(u32),
-    Ante(u32),
-    BringIn(u32),
-    GuestBlind(u32),
+    Raise(Chips),
+    Call(Chips),
+    SmallBlind(Chips),
+    BigBlind(Chips),
+    Straddle(Chips),
+    Ante(Chips),
+    BringIn(Chips),
+    GuestBlind(Chips),
 }
 
-#[derive(Debug)]
+impl Bet {
+    pub fn is_forced(&self) -> bool {
+        matches!(self,
+            Self::SmallBlind(_) | Self::BigBlind(_) | Self::Straddle(_) | Self::Ante(_) | Self::BringIn(_) | Self::GuestBlind(_))
+    }
+
+    pub fn amount(&self) -> Chips {
+        match *self {
+            Self::Check | Self::Fold => Chips::ZERO,
+            Self::Raise(amount) | Self::Call(amount) | Self::SmallBlind(amount) | Self::BigBlind(amount)
+            | Self::Straddle(amount) | Self::Ante(amount) | Self::BringIn(amount) | Self::GuestBlind(amount) => amount,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "amount", rename_all = "snake_case")]
 pub enum AutoPlay {
     Fold,
     Check,
+    Call(Chips),
     CallAny,
     CheckFold,
     FoldAnyBet,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Action<'a> {
     player: Player<'a>,
     bet: Bet,
@@ -32,4 +53,62 @@ pub struct Action<'a> {
     all_in: bool,
     forced_bet: bool,
     timed_out: bool,
+    time_used: time::Duration,
+}
+
+impl<'a> Action<'a> {
+    pub fn new(player: Player<'a>, bet: Bet, time: time::SystemTime) -> Self {
+        Action {
+            player,
+            bet,
+            time,
+            all_in: false,
+            forced_bet: bet.is_forced(),
+            timed_out: false,
+            time_used: time::Duration::ZERO,
+        }
+    }
+
+    pub fn with_all_in(mut self, all_in: bool) -> Self {
+        self.all_in = all_in;
+        self
+    }
+
+    pub fn with_timed_out(mut self, timed_out: bool) -> Self {
+        self.timed_out = timed_out;
+        self
+    }
+
+    pub fn with_time_used(mut self, time_used: time::Duration) -> Self {
+        self.time_used = time_used;
+        self
+    }
+
+    pub fn player(&self) -> &Player<'a> {
+        &self.player
+    }
+
+    pub fn bet(&self) -> Bet {
+        self.bet
+    }
+
+    pub fn time(&self) -> time::SystemTime {
+        self.time
+    }
+
+    pub fn is_all_in(&self) -> bool {
+        self.all_in
+    }
+
+    pub fn is_forced_bet(&self) -> bool {
+        self.forced_bet
+    }
+
+    pub fn is_timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    pub fn time_used(&self) -> time::Duration {
+        self.time_used
+    }
 }
@@ -0,0 +1,256 @@
+use std::fmt;
+use crate::bet::{AutoPlay, Bet};
+use crate::game::Limit;
+use crate::chips::Chips;
+
+// fixed limit caps a street at a bet and three raises
+pub const MAX_RAISES: u8 = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    CannotCheck,
+    CannotRaise,
+    WrongCallAmount { expected: Chips },
+    RaiseOutOfRange { min: Chips, max: Chips },
+    ForcedBet,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CannotCheck => write!(f, "can't check facing a bet"),
+            Self::CannotRaise => write!(f, "raising is not allowed"),
+            Self::WrongCallAmount { expected } => write!(f, "call amount must be {}", expected),
+            Self::RaiseOutOfRange { min, max } => write!(f, "raise must be between {} and {}", min, max),
+            Self::ForcedBet => write!(f, "forced bets are posted by the dealer"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub call: Chips,
+    pub min_raise: Option<Chips>,
+    pub max_raise: Option<Chips>,
+    pub all_in: Chips,
+}
+
+impl Range {
+    pub fn can_check(&self) -> bool {
+        self.call.is_zero()
+    }
+
+    pub fn can_raise(&self) -> bool {
+        self.min_raise.is_some()
+    }
+
+    pub fn validate(&self, bet: &Bet) -> Result<(), Error> {
+        match *bet {
+            Bet::Fold => Ok(()),
+            Bet::Check if self.can_check() => Ok(()),
+            Bet::Check => Err(Error::CannotCheck),
+            Bet::Call(amount) if !self.can_check() && amount == self.call => Ok(()),
+            Bet::Call(_) => Err(Error::WrongCallAmount { expected: self.call }),
+            Bet::Raise(amount) => match (self.min_raise, self.max_raise) {
+                (Some(min), Some(max)) if (amount >= min && amount <= max) || amount == self.all_in => Ok(()),
+                (Some(min), Some(max)) => Err(Error::RaiseOutOfRange { min, max }),
+                _ => Err(Error::CannotRaise),
+            },
+            _ => Err(Error::ForcedBet),
+        }
+    }
+}
+
+// a queued pre-action turns into a bet, or None once it no longer applies
+pub fn resolve(auto_play: &AutoPlay, range: &Range) -> Option<Bet> {
+    match *auto_play {
+        AutoPlay::Fold => Some(Bet::Fold),
+        AutoPlay::Check if range.can_check() => Some(Bet::Check),
+        AutoPlay::Check => None,
+        AutoPlay::Call(amount) if amount == range.call && !range.can_check() => Some(Bet::Call(amount)),
+        AutoPlay::Call(_) => None,
+        AutoPlay::CallAny if range.can_check() => Some(Bet::Check),
+        AutoPlay::CallAny => Some(Bet::Call(range.call)),
+        AutoPlay::CheckFold | AutoPlay::FoldAnyBet if range.can_check() => Some(Bet::Check),
+        AutoPlay::CheckFold | AutoPlay::FoldAnyBet => Some(Bet::Fold),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Round {
+    limit: Limit,
+    bet_size: Chips,
+    call: Chips,
+    min_raise: Chips,
+    raises: u8,
+    order: Vec<usize>,
+    pending: Vec<usize>,
+    // players who acted since the last full raise may not re-raise an incomplete all-in raise
+    acted: Vec<usize>,
+}
+
+impl Round {
+    pub fn new(limit: Limit, bet_size: Chips, call: Chips, order: Vec<usize>, pending: Vec<usize>) -> Self {
+        let pending = order.iter().copied().filter(|position| pending.contains(position)).collect();
+        Round {
+            limit,
+            bet_size,
+            call,
+            min_raise: bet_size,
+            raises: if call.is_zero() { 0 } else { 1 },
+            order,
+            pending,
+            acted: vec![],
+        }
+    }
+
+    pub fn call(&self) -> Chips {
+        self.call
+    }
+
+    pub fn next(&self) -> Option<usize> {
+        self.pending.first().copied()
+    }
+
+    pub fn is_pending(&self, position: usize) -> bool {
+        self.pending.contains(&position)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn range(&self, position: usize, put: Chips, stack: Chips, pot: Chips) -> Range {
+        let to_call = self.call.saturating_sub(put).min(stack);
+        let all_in = put + stack;
+        let capped = self.limit == Limit::FixedLimit && self.raises >= MAX_RAISES;
+        if stack <= to_call || capped || self.acted.contains(&position) {
+            return Range { call: to_call, min_raise: None, max_raise: None, all_in };
+        }
+
+        let min = match self.limit {
+            Limit::FixedLimit => self.call + self.bet_size,
+            _ => self.call + self.min_raise,
+        };
+        let max = match self.limit {
+            Limit::FixedLimit => min,
+            Limit::PotLimit => self.call + pot + to_call,
+            Limit::NoLimit => all_in,
+        };
+        Range { call: to_call, min_raise: Some(min.min(all_in)), max_raise: Some(max.min(all_in)), all_in }
+    }
+
+    pub fn called(&mut self, position: usize) {
+        self.pending.retain(|&pending| pending != position);
+        self.acted.push(position);
+    }
+
+    pub fn folded(&mut self, position: usize) {
+        self.pending.retain(|&pending| pending != position);
+    }
+
+    // everyone still able to bet has to act again, starting after the raiser
+    pub fn raised(&mut self, position: usize, to: Chips, able: &[usize]) {
+        let size = to.saturating_sub(self.call);
+        let full = match self.limit {
+            Limit::FixedLimit => size >= self.bet_size,
+            _ => size >= self.min_raise,
+        };
+        if full {
+            self.min_raise = size;
+            self.raises += 1;
+            self.acted = vec![position];
+        } else {
+            self.acted.push(position);
+        }
+        self.call = self.call.max(to);
+
+        let start = self.order.iter().position(|&seat| seat == position).map_or(0, |index| index + 1);
+        let mut order = self.order[start..].to_vec();
+        order.extend_from_slice(&self.order[..start]);
+        self.pending = order.into_iter()
+            .filter(|&seat| seat != position && able.contains(&seat))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn no_limit_ranges() {
+        let round = Round::new(Limit::NoLimit, Chips(10), Chips(10), vec![2, 0, 1], vec![0, 1, 2]);
+        assert_eq!(round.next(), Some(2));
+        let range = round.range(2, Chips(0), Chips(1000), Chips(15));
+        assert_eq!(range, Range { call: Chips(10), min_raise: Some(Chips(20)), max_raise: Some(Chips(1000)), all_in: Chips(1000) });
+        assert_eq!(range.validate(&Bet::Check), Err(Error::CannotCheck));
+        assert_eq!(range.validate(&Bet::Call(Chips(5))), Err(Error::WrongCallAmount { expected: Chips(10) }));
+        assert_eq!(range.validate(&Bet::Raise(Chips(15))), Err(Error::RaiseOutOfRange { min: Chips(20), max: Chips(1000) }));
+        assert_eq!(range.validate(&Bet::Raise(Chips(1000))), Ok(()));
+        assert_eq!(range.validate(&Bet::BigBlind(Chips(10))), Err(Error::ForcedBet));
+
+        // short stack can only call all-in
+        let range = round.range(2, Chips(0), Chips(8), Chips(15));
+        assert_eq!(range, Range { call: Chips(8), min_raise: None, max_raise: None, all_in: Chips(8) });
+    }
+
+    #[test]
+    pub fn pot_limit_ranges() {
+        let round = Round::new(Limit::PotLimit, Chips(10), Chips(10), vec![2, 0, 1], vec![0, 1, 2]);
+        // call 10 into 15, raise the pot of 25 to 35
+        let range = round.range(2, Chips(0), Chips(1000), Chips(15));
+        assert_eq!(range.max_raise, Some(Chips(35)));
+    }
+
+    #[test]
+    pub fn fixed_limit_cap() {
+        let mut round = Round::new(Limit::FixedLimit, Chips(10), Chips(10), vec![0, 1], vec![0, 1]);
+        round.raised(0, Chips(20), &[0, 1]);
+        round.raised(1, Chips(30), &[0, 1]);
+        assert_eq!(round.range(0, Chips(20), Chips(1000), Chips(50)).min_raise, Some(Chips(40)));
+        round.raised(0, Chips(40), &[0, 1]);
+        assert_eq!(round.range(1, Chips(30), Chips(1000), Chips(70)).min_raise, None);
+    }
+
+    #[test]
+    pub fn raise_reopens_action() {
+        let mut round = Round::new(Limit::NoLimit, Chips(10), Chips(0), vec![0, 1, 2], vec![0, 1, 2]);
+        round.called(0);
+        round.raised(1, Chips(30), &[0, 1, 2]);
+        assert_eq!(round.next(), Some(2));
+        round.called(2);
+        assert_eq!(round.next(), Some(0));
+        round.called(0);
+        assert!(round.is_complete());
+        assert_eq!(round.call(), Chips(30));
+    }
+
+    #[test]
+    pub fn incomplete_raise_does_not_reopen() {
+        let mut round = Round::new(Limit::NoLimit, Chips(10), Chips(0), vec![0, 1, 2], vec![0, 1, 2]);
+        round.raised(0, Chips(100), &[0, 1, 2]);
+        round.called(1);
+        // all-in for 20 more than the bet
+        round.raised(2, Chips(120), &[0, 1]);
+        assert_eq!(round.next(), Some(0));
+        assert!(!round.range(0, Chips(100), Chips(1000), Chips(320)).can_raise());
+        assert_eq!(round.range(0, Chips(100), Chips(1000), Chips(320)).call, Chips(20));
+    }
+
+    #[test]
+    pub fn resolve_pre_actions() {
+        let facing_bet = Range { call: Chips(10), min_raise: Some(Chips(20)), max_raise: Some(Chips(100)), all_in: Chips(100) };
+        let checked_to = Range { call: Chips(0), min_raise: Some(Chips(10)), max_raise: Some(Chips(100)), all_in: Chips(100) };
+
+        assert_eq!(resolve(&AutoPlay::Check, &checked_to), Some(Bet::Check));
+        assert_eq!(resolve(&AutoPlay::Check, &facing_bet), None);
+        assert_eq!(resolve(&AutoPlay::CheckFold, &facing_bet), Some(Bet::Fold));
+        assert_eq!(resolve(&AutoPlay::CallAny, &facing_bet), Some(Bet::Call(Chips(10))));
+        assert_eq!(resolve(&AutoPlay::Call(Chips(10)), &facing_bet), Some(Bet::Call(Chips(10))));
+        assert_eq!(resolve(&AutoPlay::Call(Chips(5)), &facing_bet), None);
+        assert_eq!(resolve(&AutoPlay::Fold, &checked_to), Some(Bet::Fold));
+    }
+}
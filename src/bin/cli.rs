@@ -0,0 +1,169 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use clap::{Parser, Subcommand};
+use serde::de::DeserializeOwned;
+use pokernode::chips::Chips;
+use pokernode::cli::{self, Options};
+use pokernode::equity::Calculator;
+use pokernode::game::{Limit, Name, Stake};
+use pokernode::poker::{card, Cards};
+use pokernode::{pokerstars, replay};
+
+// exit codes, clap itself exits with 2 on bad arguments
+const FAILED: u8 = 1;
+const BAD_INPUT: u8 = 3;
+const MISMATCH: u8 = 4;
+
+#[derive(Parser)]
+#[command(name = "pokernode-cli", version, about = "Plays, replays and analyses poker hands in the terminal")]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Play at a local table, asks for the setup when no players are given")]
+    Play {
+        #[arg(help = "Player names, bot for a bot")]
+        players: Vec<String>,
+        #[arg(short, long, value_parser = snake_case::<Name>, default_value = "texas")]
+        game: Name,
+        #[arg(short, long, value_parser = snake_case::<Limit>, default_value = "no_limit")]
+        limit: Limit,
+        #[arg(short, long, default_value_t = 10)]
+        big_blind: u64,
+        #[arg(long, help = "Starting stack, the maximum buy-in by default")]
+        stack: Option<u64>,
+        #[arg(long, help = "Stop after this many hands")]
+        hands: Option<u32>,
+        #[arg(long, help = "Fixes the shuffle and the bots")]
+        seed: Option<u64>,
+        #[arg(long)]
+        no_color: bool,
+    },
+    #[command(about = "Replay PokerStars hand histories through the engine and report mismatches")]
+    Replay {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    #[command(about = "Equity of two or more hands, like AhKd QsQc")]
+    Equity {
+        #[arg(required = true, num_args = 2..)]
+        hands: Vec<String>,
+        #[arg(short, long, default_value = "")]
+        board: String,
+        #[arg(short, long, default_value = "", help = "Cards that can't come on the board")]
+        dead: String,
+        #[arg(long)]
+        omaha: bool,
+        #[arg(long, default_value_t = 20_000, help = "Boards to sample when there are too many to deal them all")]
+        trials: u32,
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+// the snake_case names used everywhere else, "texas" or "no_limit"
+fn snake_case<T: DeserializeOwned>(s: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(s.to_lowercase())).map_err(|_| format!("unknown: {}", s))
+}
+
+fn cards(s: &str) -> Result<Cards, String> {
+    card::parse_joined(s).map_err(|error| error.to_string())
+}
+
+fn play(options: Options) -> ExitCode {
+    let stdin = io::stdin();
+    match cli::play(&options, &mut stdin.lock(), &mut io::stdout()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::from(FAILED)
+        }
+    }
+}
+
+fn replay(files: Vec<PathBuf>) -> ExitCode {
+    let (mut replayed, mut mismatched) = (0, 0);
+    for file in files {
+        let histories = match fs::read_to_string(&file).map_err(|error| error.to_string())
+            .and_then(|text| pokerstars::parse(&text).map_err(|error| error.to_string())) {
+            Ok(histories) => histories,
+            Err(error) => {
+                eprintln!("{}: {}", file.display(), error);
+                return ExitCode::from(BAD_INPUT);
+            }
+        };
+        for (id, result) in replay::replay_all(&histories) {
+            replayed += 1;
+            if let Err(error) = result {
+                mismatched += 1;
+                println!("{}: hand #{}: {}", file.display(), id, error);
+            }
+        }
+    }
+    println!("{} hands replayed, {} mismatched", replayed, mismatched);
+    if mismatched > 0 { ExitCode::from(MISMATCH) } else { ExitCode::SUCCESS }
+}
+
+fn main() -> ExitCode {
+    match Args::parse().command {
+        Command::Play { players, game, limit, big_blind, stack, hands, seed, no_color } => {
+            let mut options = if players.is_empty() {
+                let stdin = io::stdin();
+                match cli::setup(&mut stdin.lock(), &mut io::stdout()) {
+                    Ok(options) => options,
+                    Err(error) => {
+                        eprintln!("{}", error);
+                        return ExitCode::from(FAILED);
+                    }
+                }
+            } else {
+                if !cli::playable().contains(&game) {
+                    eprintln!("{} can't be dealt yet", game.human_readable_name());
+                    return ExitCode::from(BAD_INPUT);
+                }
+                let mut options = Options::new(cli::seats(players.iter().map(String::as_str), game));
+                options.game = game;
+                options.limit = limit;
+                options.stake = Stake::new(Chips(big_blind.max(2)));
+                options.stack = options.stake.buy_in().1;
+                options
+            };
+            options.stack = stack.map_or(options.stack, Chips);
+            options.hands = hands;
+            options.seed = seed;
+            options.color = !no_color;
+            play(options)
+        }
+        Command::Replay { files } => replay(files),
+        Command::Equity { hands, board, dead, omaha, trials, seed, json } => {
+            let query = (|| -> Result<Calculator, String> {
+                let pockets = hands.iter().map(|hand| cards(hand)).collect::<Result<Vec<_>, _>>()?;
+                let calculator = Calculator::new(pockets).with_board(cards(&board)?).with_dead(cards(&dead)?);
+                Ok(if omaha { calculator.with_omaha() } else { calculator }.with_trials(trials).with_seed(seed))
+            })();
+            let outcome = match query.and_then(|calculator| calculator.calculate().map_err(|error| error.to_string())) {
+                Ok(outcome) => outcome,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    return ExitCode::from(BAD_INPUT);
+                }
+            };
+            let mut stdout = io::stdout().lock();
+            let written = if json {
+                writeln!(stdout, "{}", serde_json::to_string(&outcome).expect("outcomes always serialize"))
+            } else {
+                hands.iter().zip(outcome.equities.iter())
+                    .try_for_each(|(hand, equity)| writeln!(stdout, "{:<12} {:>6.2}%  win {:>6.2}%  tie {:>6.2}%", hand, equity.equity * 100.0, equity.win * 100.0, equity.tie * 100.0))
+                    .and_then(|_| writeln!(stdout, "{} {} boards", if outcome.exact { "all" } else { "sampled" }, outcome.boards))
+            };
+            if written.is_err() { ExitCode::from(FAILED) } else { ExitCode::SUCCESS }
+        }
+    }
+}
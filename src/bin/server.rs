@@ -0,0 +1,120 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use clap::{Parser, Subcommand};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use pokernode::config::Config;
+use pokernode::gateway;
+use pokernode::server::Server;
+
+// exit codes, clap itself exits with 2 on bad arguments
+const FAILED: u8 = 1;
+const BAD_CONFIG: u8 = 3;
+
+#[derive(Parser)]
+#[command(name = "pokernode-server", version, about = "Hosts poker tables for tcp and websocket clients")]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Serve the tables in a config file until interrupted")]
+    Run {
+        #[arg(short, long)]
+        config: PathBuf,
+        #[arg(long, help = "Overrides the config's host")]
+        host: Option<String>,
+        #[arg(short, long, help = "Overrides the config's tcp port")]
+        port: Option<u16>,
+        #[arg(short, long, help = "Overrides the config's websocket port")]
+        websocket_port: Option<u16>,
+    },
+    #[command(about = "Check a config file and list its tables")]
+    Check {
+        #[arg(short, long)]
+        config: PathBuf,
+    },
+    #[command(about = "Print an example config")]
+    Example,
+}
+
+fn load(path: &PathBuf) -> Result<Config, ExitCode> {
+    Config::load(path).map_err(|error| {
+        eprintln!("{}: {}", path.display(), error);
+        ExitCode::from(BAD_CONFIG)
+    })
+}
+
+async fn run(config: Config, server: Server) -> std::io::Result<()> {
+    let server = Arc::new(server);
+    let (stop, stopped) = watch::channel(false);
+    let shutdown = |mut stopped: watch::Receiver<bool>| async move {
+        // a dropped sender stops the servers just the same
+        let _ = stopped.changed().await;
+    };
+
+    let listener = TcpListener::bind((config.host.as_str(), config.port)).await?;
+    println!("tcp on {}", listener.local_addr()?);
+    let mut serving = vec![tokio::spawn(server.clone().serve(listener, shutdown(stopped.clone())))];
+    if let Some(port) = config.websocket_port {
+        let listener = TcpListener::bind((config.host.as_str(), port)).await?;
+        println!("websockets on {}", listener.local_addr()?);
+        serving.push(tokio::spawn(gateway::serve(server, listener, shutdown(stopped))));
+    }
+
+    tokio::signal::ctrl_c().await?;
+    println!("shutting down");
+    let _ = stop.send(true);
+    for task in serving {
+        task.await.map_err(std::io::Error::other)??;
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match Args::parse().command {
+        Command::Run { config, host, port, websocket_port } => {
+            let mut config = match load(&config) {
+                Ok(config) => config,
+                Err(code) => return code,
+            };
+            config.host = host.unwrap_or(config.host);
+            config.port = port.unwrap_or(config.port);
+            config.websocket_port = websocket_port.or(config.websocket_port);
+            // tables come back from storage before anyone can connect
+            let server = match config.server() {
+                Ok(server) => server,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    return ExitCode::from(FAILED);
+                }
+            };
+            match run(config, server).await {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    ExitCode::from(FAILED)
+                }
+            }
+        }
+        Command::Check { config } => match load(&config) {
+            Ok(config) => {
+                for table in config.tables.iter() {
+                    println!("{}: {} {} {}/{}, {} seats", table.name, table.limit.full_name(), table.game.human_readable_name(), table.big_blind.split(2).0, table.big_blind, table.table_size);
+                }
+                ExitCode::SUCCESS
+            }
+            Err(code) => code,
+        },
+        Command::Example => {
+            let mut config = Config::from_json(r#"{"tables": [{"name": "Alpha", "game": "texas", "big_blind": 10}]}"#).expect("the example is valid");
+            config.websocket_port = Some(config.port + 1);
+            println!("{}", config.to_json());
+            ExitCode::SUCCESS
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use crate::chips::Chips;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Presence {
+    Empty,
+    SittingOut,
+    WaitBigBlind,
+    PostBigBlind(Chips),
+    Active,
+}
+
+impl Presence {
+    pub fn is_active(&self) -> bool {
+        matches!(self, Presence::Active | Presence::PostBigBlind(_))
+    }
+
+    pub fn can_take_big_blind(&self) -> bool {
+        self.is_active() || *self == Presence::WaitBigBlind
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blinds {
+    pub button: usize,
+    pub small_blind: usize,
+    pub big_blind: usize,
+    pub dead_button: bool,
+    pub dead_small_blind: bool,
+}
+
+impl Blinds {
+    pub fn is_heads_up(&self) -> bool {
+        self.button == self.small_blind
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rotation {
+    pub blinds: Blinds,
+    pub dealt: Vec<usize>,
+    pub missed_small_blind: Vec<usize>,
+    pub missed_big_blind: Vec<usize>,
+    pub posts: Vec<(usize, Chips)>,
+}
+
+impl Rotation {
+    // first to act is the seat after the big blind, so heads-up the button acts first
+    pub fn preflop_order(&self) -> Vec<usize> {
+        self.order_after(self.blinds.big_blind)
+    }
+
+    pub fn postflop_order(&self) -> Vec<usize> {
+        self.order_after(self.blinds.button)
+    }
+
+    fn order_after(&self, position: usize) -> Vec<usize> {
+        let start = self.dealt.iter().position(|&seat| seat > position).unwrap_or(0);
+        let mut order = self.dealt[start..].to_vec();
+        order.extend_from_slice(&self.dealt[..start]);
+        order
+    }
+}
+
+fn next(seats: &[Presence], from: usize, filter: fn(&Presence) -> bool) -> Option<usize> {
+    let size = seats.len();
+    (1..=size).map(|i| (from + i) % size).find(|&i| filter(&seats[i]))
+}
+
+fn previous(seats: &[Presence], from: usize, filter: fn(&Presence) -> bool) -> Option<usize> {
+    let size = seats.len();
+    (1..=size).map(|i| (from + size - i) % size).find(|&i| filter(&seats[i]))
+}
+
+fn between(seats: &[Presence], from: usize, to: usize) -> Vec<usize> {
+    let size = seats.len();
+    (1..size).map(|i| (from + i) % size).take_while(|&i| i != to).collect()
+}
+
+// moving big blind with a dead button: the big blind always advances to the next player,
+// the previous big blind posts the small blind and the previous small blind gets the button
+pub fn rotate(seats: &[Presence], previous_blinds: Option<&Blinds>, button: usize) -> Option<Rotation> {
+    // nobody owes a blind before the first hand, so waiting players are dealt in
+    let first_hand: Vec<Presence>;
+    let seats = match previous_blinds {
+        Some(_) => seats,
+        None => {
+            first_hand = seats.iter()
+                .map(|&seat| if seat == Presence::WaitBigBlind { Presence::Active } else { seat })
+                .collect();
+            &first_hand
+        },
+    };
+
+    let active = seats.iter().filter(|seat| seat.is_active()).count();
+    let waiting = seats.iter().filter(|&&seat| seat == Presence::WaitBigBlind).count();
+    if active == 0 || active + waiting < 2 {
+        return None;
+    }
+
+    let mut missed_small_blind = vec![];
+    let mut missed_big_blind = vec![];
+
+    let blinds = match previous_blinds {
+        None => {
+            let button = if seats[button % seats.len()].is_active() {
+                button % seats.len()
+            } else {
+                next(seats, button, Presence::is_active)?
+            };
+            if active == 2 {
+                let big_blind = next(seats, button, Presence::is_active)?;
+                Blinds { button, small_blind: button, big_blind, dead_button: false, dead_small_blind: false }
+            } else {
+                let small_blind = next(seats, button, Presence::is_active)?;
+                let big_blind = next(seats, small_blind, Presence::is_active)?;
+                Blinds { button, small_blind, big_blind, dead_button: false, dead_small_blind: false }
+            }
+        },
+
+        Some(previous_blinds) => {
+            let big_blind = next(seats, previous_blinds.big_blind, Presence::can_take_big_blind)?;
+            for position in between(seats, previous_blinds.big_blind, big_blind) {
+                if seats[position] == Presence::SittingOut {
+                    missed_big_blind.push(position);
+                }
+            }
+
+            let heads_up = active + if seats[big_blind].is_active() { 0 } else { 1 } == 2;
+            if heads_up {
+                let button = next(seats, big_blind, Presence::is_active)?;
+                Blinds { button, small_blind: button, big_blind, dead_button: false, dead_small_blind: false }
+            } else {
+                let small_blind = previous_blinds.big_blind;
+                let dead_small_blind = !seats[small_blind].is_active() || small_blind == big_blind;
+                if seats[small_blind] == Presence::SittingOut {
+                    missed_small_blind.push(small_blind);
+                }
+
+                let mut button = previous_blinds.small_blind;
+                if button == small_blind || button == big_blind {
+                    button = previous(seats, small_blind, Presence::is_active)?;
+                }
+                let dead_button = !seats[button].is_active();
+                Blinds { button, small_blind, big_blind, dead_button, dead_small_blind }
+            }
+        },
+    };
+
+    let dealt = seats.iter().enumerate()
+        .filter(|&(position, seat)| seat.is_active() || position == blinds.big_blind)
+        .map(|(position, _)| position)
+        .collect();
+
+    let posts = seats.iter().enumerate()
+        .filter_map(|(position, seat)| match seat {
+            Presence::PostBigBlind(amount) if position != blinds.big_blind => Some((position, *amount)),
+            _ => None,
+        })
+        .collect();
+
+    Some(Rotation { blinds, dealt, missed_small_blind, missed_big_blind, posts })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::Presence::*;
+
+    #[test]
+    pub fn first_hand() {
+        let seats = [Active, Empty, Active, Active, Empty, Active];
+        let rotation = rotate(&seats, None, 1).unwrap();
+        assert_eq!(rotation.blinds, Blinds { button: 2, small_blind: 3, big_blind: 5, dead_button: false, dead_small_blind: false });
+        assert_eq!(rotation.preflop_order(), vec![0, 2, 3, 5]);
+        assert_eq!(rotation.postflop_order(), vec![3, 5, 0, 2]);
+    }
+
+    #[test]
+    pub fn button_moves() {
+        let seats = [Active, Active, Active, Active];
+        let first = rotate(&seats, None, 0).unwrap();
+        let second = rotate(&seats, Some(&first.blinds), 0).unwrap();
+        assert_eq!(second.blinds, Blinds { button: 1, small_blind: 2, big_blind: 3, dead_button: false, dead_small_blind: false });
+        let third = rotate(&seats, Some(&second.blinds), 0).unwrap();
+        assert_eq!(third.blinds, Blinds { button: 2, small_blind: 3, big_blind: 0, dead_button: false, dead_small_blind: false });
+    }
+
+    #[test]
+    pub fn dead_button() {
+        let previous = Blinds { button: 0, small_blind: 1, big_blind: 2, dead_button: false, dead_small_blind: false };
+        // small blind left the table
+        let seats = [Active, Empty, Active, Active, Active];
+        let rotation = rotate(&seats, Some(&previous), 0).unwrap();
+        assert_eq!(rotation.blinds, Blinds { button: 1, small_blind: 2, big_blind: 3, dead_button: true, dead_small_blind: false });
+        assert_eq!(rotation.dealt, vec![0, 2, 3, 4]);
+    }
+
+    #[test]
+    pub fn dead_small_blind() {
+        let previous = Blinds { button: 0, small_blind: 1, big_blind: 2, dead_button: false, dead_small_blind: false };
+        // big blind left the table
+        let seats = [Active, Active, Empty, Active, Active];
+        let rotation = rotate(&seats, Some(&previous), 0).unwrap();
+        assert_eq!(rotation.blinds, Blinds { button: 1, small_blind: 2, big_blind: 3, dead_button: false, dead_small_blind: true });
+    }
+
+    #[test]
+    pub fn heads_up() {
+        let seats = [Active, Empty, Active];
+        let first = rotate(&seats, None, 0).unwrap();
+        assert!(first.blinds.is_heads_up());
+        assert_eq!(first.blinds.button, 0);
+        assert_eq!(first.blinds.big_blind, 2);
+        assert_eq!(first.preflop_order(), vec![0, 2]);
+        assert_eq!(first.postflop_order(), vec![2, 0]);
+
+        let second = rotate(&seats, Some(&first.blinds), 0).unwrap();
+        assert_eq!(second.blinds.button, 2);
+        assert_eq!(second.blinds.small_blind, 2);
+        assert_eq!(second.blinds.big_blind, 0);
+    }
+
+    #[test]
+    pub fn sitting_out_misses_blinds() {
+        let previous = Blinds { button: 0, small_blind: 1, big_blind: 2, dead_button: false, dead_small_blind: false };
+        let seats = [Active, Active, SittingOut, SittingOut, Active, Active];
+        let rotation = rotate(&seats, Some(&previous), 0).unwrap();
+        assert_eq!(rotation.blinds.big_blind, 4);
+        assert!(rotation.blinds.dead_small_blind);
+        assert_eq!(rotation.missed_small_blind, vec![2]);
+        assert_eq!(rotation.missed_big_blind, vec![3]);
+    }
+
+    #[test]
+    pub fn waiting_player_comes_in_on_big_blind() {
+        let previous = Blinds { button: 0, small_blind: 1, big_blind: 2, dead_button: false, dead_small_blind: false };
+        let seats = [Active, Active, Active, WaitBigBlind, WaitBigBlind];
+        let rotation = rotate(&seats, Some(&previous), 0).unwrap();
+        assert_eq!(rotation.blinds.big_blind, 3);
+        assert_eq!(rotation.dealt, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    pub fn returning_player_posts_out_of_position() {
+        let previous = Blinds { button: 0, small_blind: 1, big_blind: 2, dead_button: false, dead_small_blind: false };
+        let seats = [Active, Active, Active, Active, PostBigBlind(Chips(15))];
+        let rotation = rotate(&seats, Some(&previous), 0).unwrap();
+        assert_eq!(rotation.blinds.big_blind, 3);
+        assert_eq!(rotation.dealt, vec![0, 1, 2, 3, 4]);
+        assert_eq!(rotation.posts, vec![(4, Chips(15))]);
+    }
+
+    #[test]
+    pub fn first_hand_deals_waiting_players() {
+        let rotation = rotate(&[Active, WaitBigBlind, Active], None, 0).unwrap();
+        assert_eq!(rotation.blinds, Blinds { button: 0, small_blind: 1, big_blind: 2, dead_button: false, dead_small_blind: false });
+        assert_eq!(rotation.dealt, vec![0, 1, 2]);
+    }
+
+    #[test]
+    pub fn not_enough_players() {
+        assert_eq!(rotate(&[Active, SittingOut, Empty], None, 0), None);
+        assert_eq!(rotate(&[WaitBigBlind, SittingOut, Empty], None, 0), None);
+    }
+}
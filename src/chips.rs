@@ -0,0 +1,200 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use crate::locale::Currency;
+use crate::rake::BASIS_POINTS;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Error {
+    Overflow,
+    Underflow,
+    Invalid(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Overflow => write!(f, "chip amount overflow"),
+            Self::Underflow => write!(f, "chip amount underflow"),
+            Self::Invalid(reason) => write!(f, "invalid chip amount: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// an amount in the smallest unit: cents for most currencies, whole chips for play money
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct Chips(pub u64);
+
+impl Chips {
+    pub const ZERO: Chips = Chips(0);
+
+    pub fn amount(&self) -> u64 {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn checked_add(self, other: Chips) -> Result<Chips, Error> {
+        self.0.checked_add(other.0).map(Chips).ok_or(Error::Overflow)
+    }
+
+    pub fn checked_sub(self, other: Chips) -> Result<Chips, Error> {
+        self.0.checked_sub(other.0).map(Chips).ok_or(Error::Underflow)
+    }
+
+    pub fn checked_mul(self, times: u64) -> Result<Chips, Error> {
+        self.0.checked_mul(times).map(Chips).ok_or(Error::Overflow)
+    }
+
+    pub fn saturating_sub(self, other: Chips) -> Chips {
+        Chips(self.0.saturating_sub(other.0))
+    }
+
+    // equal shares and the odd chips left over
+    pub fn split(self, ways: u64) -> (Chips, Chips) {
+        match ways {
+            0 => (Chips::ZERO, self),
+            _ => (Chips(self.0 / ways), Chips(self.0 % ways)),
+        }
+    }
+
+    pub fn basis_points(self, basis_points: u32) -> Chips {
+        Chips((self.0 as u128 * basis_points as u128 / BASIS_POINTS as u128) as u64)
+    }
+
+    // "12.50" with a currency of two minor units is 1250
+    pub fn parse(s: &str, currency: Option<Currency>) -> Result<Chips, Error> {
+        let minor_units = currency.map_or(0, |currency| currency.minor_units()) as usize;
+        let s = s.trim();
+        let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+        if fraction.len() > minor_units {
+            return Err(Error::Invalid("too many decimal places"));
+        }
+        // "1,000,000" but not "1,,0" or "10,00"
+        let mut groups = whole.split(',');
+        let first = groups.next().unwrap_or_default();
+        if whole.contains(',') && (first.len() > 3 || groups.any(|group| group.len() != 3)) {
+            return Err(Error::Invalid("misplaced thousands separator"));
+        }
+        let digits = format!("{}{:0<width$}", whole.replace(',', ""), fraction, width = minor_units);
+        // a leading digit, and a digit after the point if there is one
+        if first.is_empty() || (s.contains('.') && fraction.is_empty()) || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(Error::Invalid("not a number"));
+        }
+        digits.parse::<u64>().map(Chips).map_err(|_| Error::Overflow)
+    }
+
+    pub fn format(&self, currency: Option<Currency>) -> String {
+        let minor_units = currency.map_or(0, |currency| currency.minor_units()) as u32;
+        if minor_units == 0 {
+            return self.0.to_string();
+        }
+        let scale = 10u64.pow(minor_units);
+        format!("{}.{:0width$}", self.0 / scale, self.0 % scale, width = minor_units as usize)
+    }
+}
+
+impl fmt::Display for Chips {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<u16> for Chips {
+    fn from(amount: u16) -> Self {
+        Chips(amount as u64)
+    }
+}
+
+impl From<u32> for Chips {
+    fn from(amount: u32) -> Self {
+        Chips(amount as u64)
+    }
+}
+
+impl From<u64> for Chips {
+    fn from(amount: u64) -> Self {
+        Chips(amount)
+    }
+}
+
+// operators panic instead of wrapping around, use the checked versions where input is untrusted
+impl Add for Chips {
+    type Output = Chips;
+
+    fn add(self, other: Chips) -> Chips {
+        self.checked_add(other).expect("chip amount overflow")
+    }
+}
+
+impl Sub for Chips {
+    type Output = Chips;
+
+    fn sub(self, other: Chips) -> Chips {
+        self.checked_sub(other).expect("chip amount underflow")
+    }
+}
+
+impl AddAssign for Chips {
+    fn add_assign(&mut self, other: Chips) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Chips {
+    fn sub_assign(&mut self, other: Chips) {
+        *self = *self - other;
+    }
+}
+
+impl Sum for Chips {
+    fn sum<I: Iterator<Item = Chips>>(iter: I) -> Chips {
+        iter.fold(Chips::ZERO, |sum, chips| sum + chips)
+    }
+}
+
+impl<'a> Sum<&'a Chips> for Chips {
+    fn sum<I: Iterator<Item = &'a Chips>>(iter: I) -> Chips {
+        iter.copied().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn checked_arithmetic() {
+        assert_eq!(Chips(u64::MAX).checked_add(Chips(1)), Err(Error::Overflow));
+        assert_eq!(Chips(1).checked_sub(Chips(2)), Err(Error::Underflow));
+        assert_eq!(Chips(1).saturating_sub(Chips(2)), Chips::ZERO);
+        assert_eq!(Chips(100).split(3), (Chips(33), Chips(1)));
+        assert_eq!(Chips(3000).basis_points(500), Chips(150));
+        // 100 big blinds at a 1000 big blind no longer overflows
+        assert_eq!(Chips(1000).checked_mul(100), Ok(Chips(100_000)));
+    }
+
+    #[test]
+    pub fn minor_units() {
+        assert_eq!(Chips::parse("12.5", Some(Currency::USD)), Ok(Chips(1250)));
+        assert_eq!(Chips::parse("1,000", Some(Currency::JPY)), Ok(Chips(1000)));
+        assert_eq!(Chips::parse("1.5", Some(Currency::JPY)), Err(Error::Invalid("too many decimal places")));
+        assert_eq!(Chips::parse("abc", None), Err(Error::Invalid("not a number")));
+        assert_eq!(Chips::parse("1,000,000", None), Ok(Chips(1_000_000)));
+        assert_eq!(Chips::parse("1,,0", None), Err(Error::Invalid("misplaced thousands separator")));
+        assert_eq!(Chips::parse("10,00", None), Err(Error::Invalid("misplaced thousands separator")));
+        assert_eq!(Chips::parse(",500", None), Err(Error::Invalid("not a number")));
+        assert_eq!(Chips::parse(".5", Some(Currency::USD)), Err(Error::Invalid("not a number")));
+        assert_eq!(Chips::parse("5.", Some(Currency::USD)), Err(Error::Invalid("not a number")));
+        assert_eq!(Chips(1250).format(Some(Currency::USD)), "12.50");
+        assert_eq!(Chips(1250).format(Some(Currency::KRW)), "1250");
+        assert_eq!(Chips(1250).format(None), "1250");
+    }
+}
@@ -0,0 +1,404 @@
+use std::io::{self, BufRead, Write};
+use ansi_term::{Colour, Style};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::de::DeserializeOwned;
+use crate::bet::Bet;
+use crate::betting::Range;
+use crate::chips::Chips;
+use crate::game::{self, Game, Limit, Name, Stake, Variant};
+use crate::locale::Currency;
+use crate::player::Player;
+use crate::poker::Card;
+use crate::pokerstars::money;
+use crate::protocol;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Seat {
+    Human(String),
+    Bot(String),
+}
+
+impl Seat {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Human(name) | Self::Bot(name) => name,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub game: Name,
+    pub limit: Limit,
+    pub stake: Stake,
+    pub stack: Chips,
+    pub seats: Vec<Seat>,
+    // play on until someone quits or only one player is left
+    pub hands: Option<u32>,
+    pub seed: Option<u64>,
+    pub color: bool,
+}
+
+impl Options {
+    pub fn new(seats: Vec<Seat>) -> Self {
+        let stake = Stake::new(Chips(10));
+        Options {
+            game: Name::Texas,
+            limit: Limit::NoLimit,
+            stack: stake.buy_in().1,
+            stake,
+            seats,
+            hands: None,
+            seed: None,
+            color: true,
+        }
+    }
+}
+
+// games the engine can deal today
+pub fn playable() -> Vec<Name> {
+    Name::ALL.into_iter().filter(|name| name.options().variant() == Variant::Holdem).collect()
+}
+
+// the snake_case names used everywhere else, "texas" or "no_limit"
+fn parse<T: DeserializeOwned>(s: &str) -> Option<T> {
+    serde_json::from_value(serde_json::Value::String(s.trim().to_lowercase())).ok()
+}
+
+fn id<T: serde::Serialize>(value: T) -> String {
+    serde_json::to_value(value).ok().and_then(|value| value.as_str().map(str::to_string)).unwrap_or_default()
+}
+
+// none once the input runs out
+fn prompt(input: &mut impl BufRead, output: &mut impl Write, question: &str) -> io::Result<Option<String>> {
+    write!(output, "{}", question)?;
+    output.flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+// asks for the game, stakes and players, an empty answer takes the default in brackets
+pub fn setup(input: &mut impl BufRead, output: &mut impl Write) -> io::Result<Options> {
+    let names = playable().into_iter().map(id).collect::<Vec<_>>().join(", ");
+    let mut options = Options::new(vec![]);
+    loop {
+        match prompt(input, output, &format!("Game ({}) [texas]: ", names))? {
+            Some(answer) if !answer.is_empty() => match parse::<Name>(&answer).filter(|name| playable().contains(name)) {
+                Some(name) => options.game = name,
+                None => {
+                    writeln!(output, "no such game: {}", answer)?;
+                    continue;
+                }
+            },
+            _ => {}
+        }
+        break;
+    }
+    loop {
+        match prompt(input, output, "Limit (fixed_limit, pot_limit, no_limit) [no_limit]: ")? {
+            Some(answer) if !answer.is_empty() => match parse::<Limit>(&answer) {
+                Some(limit) => options.limit = limit,
+                None => {
+                    writeln!(output, "no such limit: {}", answer)?;
+                    continue;
+                }
+            },
+            _ => {}
+        }
+        break;
+    }
+    loop {
+        match prompt(input, output, "Big blind [10]: ")? {
+            Some(answer) if !answer.is_empty() => match Chips::parse(&answer, None) {
+                Ok(big_blind) if !big_blind.is_zero() => options.stake = Stake::new(big_blind),
+                _ => {
+                    writeln!(output, "not an amount: {}", answer)?;
+                    continue;
+                }
+            },
+            _ => {}
+        }
+        break;
+    }
+    options.stack = options.stake.buy_in().1;
+
+    let answer = prompt(input, output, "Players, bot for a bot [you bot bot]: ")?.filter(|answer| !answer.is_empty());
+    options.seats = seats(answer.as_deref().unwrap_or("you bot bot").split_whitespace(), options.game);
+    Ok(options)
+}
+
+// every "bot" becomes a numbered bot, anyone past the table size is left out
+pub fn seats<'a>(names: impl IntoIterator<Item = &'a str>, game: Name) -> Vec<Seat> {
+    let mut bots = 0;
+    names.into_iter()
+        .take(game.options().max_table_size())
+        .map(|name| match name {
+            "bot" => {
+                bots += 1;
+                Seat::Bot(format!("bot{}", bots))
+            }
+            name => Seat::Human(name.to_string()),
+        })
+        .collect()
+}
+
+struct Paint {
+    color: bool,
+}
+
+impl Paint {
+    fn style(&self, style: Style, text: &str) -> String {
+        if self.color { style.paint(text).to_string() } else { text.to_string() }
+    }
+
+    fn cards(&self, cards: &[Option<Card>]) -> String {
+        cards.iter()
+            .map(|card| match card {
+                Some(card) if self.color => card.to_string(),
+                Some(card) => format!("{:?}", card),
+                None => "##".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+// the table as viewer sees it, everyone else's cards face down
+pub fn render(game: &Game, viewer: protocol::Viewer, color: bool) -> String {
+    let paint = Paint { color };
+    let view = protocol::Table::view(game, viewer);
+    let currency = view.game.stake.currency();
+    let board = view.board.iter().copied().map(Some).collect::<Vec<_>>();
+    let mut lines = vec![
+        paint.style(Style::new().bold(), &format!("{} {} {}/{} - {}",
+            view.game.name.human_readable_name(), view.game.limit.full_name(),
+            money(view.game.stake.small_blind(), currency), money(view.game.stake.big_blind(), currency), view.game.table)),
+        format!("board: {}  pot: {}", if board.is_empty() { "-".to_string() } else { paint.cards(&board) }, money(view.pot, currency)),
+    ];
+    for seat in view.seats.iter() {
+        let Some(player) = seat.player.as_ref() else { continue };
+        let marker = if view.to_act == Some(seat.position) { paint.style(Colour::Yellow.bold(), ">") } else { " ".to_string() };
+        let button = if view.button == seat.position { " (button)" } else { "" };
+        let put = if seat.put.is_zero() { String::new() } else { format!("  in: {}", money(seat.put, currency)) };
+        lines.push(format!("{} {} {:<12} {:>8}{}  {}{}", marker, seat.position + 1, player.name,
+            money(seat.stack.unwrap_or_default(), currency), put, paint.cards(&seat.cards), button));
+    }
+    lines.join("\n")
+}
+
+// what the player may do, like "fold, call 10, raise 20-1000"
+pub fn hint(range: &Range, currency: Option<Currency>) -> String {
+    let mut hints = vec![];
+    if range.can_check() {
+        hints.push("check".to_string());
+    } else {
+        hints.push("fold".to_string());
+        hints.push(format!("call {}", money(range.call, currency)));
+    }
+    match (range.min_raise, range.max_raise) {
+        (Some(min), Some(max)) if min == max => hints.push(format!("raise {}", money(min, currency))),
+        (Some(min), Some(max)) => hints.push(format!("raise {}-{}", money(min, currency), money(max, currency))),
+        _ => {}
+    }
+    hints.join(", ")
+}
+
+// f, fold, k, check, c, call, r 40, raise 40, a, all in
+pub fn parse_bet(line: &str, range: &Range, currency: Option<Currency>) -> Result<Bet, String> {
+    let words = line.split_whitespace().map(str::to_lowercase).collect::<Vec<_>>();
+    let words = words.iter().map(String::as_str).collect::<Vec<_>>();
+    match words.as_slice() {
+        ["f" | "fold"] => Ok(Bet::Fold),
+        ["k" | "check"] => Ok(Bet::Check),
+        ["c" | "call"] if range.can_check() => Ok(Bet::Check),
+        ["c" | "call"] => Ok(Bet::Call(range.call)),
+        ["r" | "raise" | "b" | "bet", amount] => {
+            Chips::parse(amount, currency).map(Bet::Raise).map_err(|error| format!("{}: {}", amount, error))
+        }
+        ["a" | "allin"] | ["all", "in"] => Ok(match range.max_raise {
+            Some(max) => Bet::Raise(max),
+            None if range.can_check() => Bet::Check,
+            None => Bet::Call(range.call),
+        }),
+        _ => Err(format!("can't read {:?}", line.trim())),
+    }
+}
+
+// calls a lot, raises now and then, folds to big bets
+fn bot(range: &Range, big_blind: Chips, rng: &mut StdRng) -> Bet {
+    let roll = rng.gen_range(0..10);
+    match range.min_raise {
+        Some(min) if roll == 0 => return Bet::Raise(min),
+        _ => {}
+    }
+    if range.can_check() {
+        Bet::Check
+    } else if range.call.amount() <= big_blind.amount() * 4 || roll < 3 {
+        Bet::Call(range.call)
+    } else {
+        Bet::Fold
+    }
+}
+
+fn describe(bet: &Bet, currency: Option<Currency>) -> String {
+    match bet {
+        Bet::Check => "checks".to_string(),
+        Bet::Fold => "folds".to_string(),
+        Bet::Raise(amount) => format!("raises to {}", money(*amount, currency)),
+        Bet::Call(amount) => format!("calls {}", money(*amount, currency)),
+        bet => format!("posts {}", money(bet.amount(), currency)),
+    }
+}
+
+fn results(game: &Game, color: bool) -> String {
+    let paint = Paint { color };
+    let Some(history) = game.history() else { return String::new() };
+    let currency = history.stake.currency();
+    let name = |position: u8| history.player(position).unwrap_or_default().to_string();
+    let mut lines = vec![];
+    if !history.board.is_empty() {
+        lines.push(format!("board: {}", paint.cards(&history.board.iter().copied().map(Some).collect::<Vec<_>>())));
+    }
+    for shown in history.shown.iter() {
+        let cards = shown.cards.iter().copied().map(Some).collect::<Vec<_>>();
+        lines.push(format!("{} shows {} ({})", name(shown.position), paint.cards(&cards), shown.description));
+    }
+    if let Some((position, amount)) = history.uncalled {
+        lines.push(format!("{} takes back {}", name(position), money(amount, currency)));
+    }
+    for seat in history.seats.iter() {
+        let won = history.won(seat.position);
+        if !won.is_zero() {
+            lines.push(paint.style(Colour::Green.bold(), &format!("{} wins {}", seat.player, money(won, currency))));
+        }
+    }
+    lines.join("\n")
+}
+
+// plays hands until the hand limit, a quit or a single player left, bots act on their own
+pub fn play(options: &Options, input: &mut impl BufRead, output: &mut impl Write) -> io::Result<()> {
+    let table_size = options.seats.len().max(2) as u8;
+    let mut game = Game::new(options.game, options.limit, options.stake.clone(), table_size).with_name("cli");
+    if let Some(seed) = options.seed {
+        game = game.with_seed(seed);
+    }
+    for (position, seat) in options.seats.iter().enumerate() {
+        if let Err(error) = game.join(Player::blank_player(seat.name()), position as u8, options.stack) {
+            writeln!(output, "{} can't sit down: {}", seat.name(), error)?;
+        }
+    }
+    let mut rng = options.seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
+    let currency = options.stake.currency();
+    let humans = options.seats.iter().filter(|seat| matches!(seat, Seat::Human(_))).count();
+
+    let mut played = 0;
+    while options.hands.is_none_or(|hands| played < hands) {
+        match game.start() {
+            Ok(()) => {}
+            Err(game::Error::NotEnoughPlayers) => {
+                writeln!(output, "not enough players left")?;
+                break;
+            }
+            Err(error) => {
+                writeln!(output, "can't deal: {}", error)?;
+                break;
+            }
+        }
+        played += 1;
+        writeln!(output, "\n*** hand {} ***", played)?;
+
+        while let Some(position) = game.to_act() {
+            let Some(range) = game.range(position) else { break };
+            let seat = &options.seats[position as usize];
+            let bet = match seat {
+                Seat::Bot(_) => bot(&range, options.stake.big_blind(), &mut rng),
+                Seat::Human(name) => {
+                    // hot seat: the next player takes the keyboard before their cards show
+                    if humans > 1 && prompt(input, output, &format!("\n{}, press enter", name))?.is_none() {
+                        return Ok(());
+                    }
+                    writeln!(output, "\n{}", render(&game, protocol::Viewer::Seat(position), options.color))?;
+                    loop {
+                        let Some(line) = prompt(input, output, &format!("{} ({}, q to quit): ", name, hint(&range, currency)))? else {
+                            return Ok(());
+                        };
+                        if line == "q" || line == "quit" {
+                            return Ok(());
+                        }
+                        match parse_bet(&line, &range, currency).and_then(|bet| range.validate(&bet).map(|_| bet).map_err(|error| error.to_string())) {
+                            Ok(bet) => break bet,
+                            Err(error) => writeln!(output, "{}", error)?,
+                        }
+                    }
+                }
+            };
+            // bots only pick from the range, so this can't fail
+            if let Err(error) = game.act(position, bet) {
+                writeln!(output, "{}", error)?;
+                continue;
+            }
+            writeln!(output, "{} {}", seat.name(), describe(&bet, currency))?;
+        }
+
+        writeln!(output, "{}", results(&game, options.color))?;
+        if humans > 0 && options.hands.is_none_or(|hands| played < hands) {
+            match prompt(input, output, "\nenter for the next hand, q to quit: ")? {
+                Some(line) if line != "q" && line != "quit" => {}
+                _ => break,
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn setup_prompts() {
+        let mut output = vec![];
+        let options = setup(&mut "omaha\nbad\npot_limit\n20\nann bot bot\n".as_bytes(), &mut output).unwrap();
+        assert_eq!((options.game, options.limit, options.stake.big_blind()), (Name::Omaha, Limit::PotLimit, Chips(20)));
+        assert_eq!(options.seats, vec![Seat::Human("ann".to_string()), Seat::Bot("bot1".to_string()), Seat::Bot("bot2".to_string())]);
+        assert!(String::from_utf8(output).unwrap().contains("no such limit: bad"));
+
+        let options = setup(&mut "\n\n\n\n".as_bytes(), &mut vec![]).unwrap();
+        assert_eq!((options.game, options.limit, options.seats.len()), (Name::Texas, Limit::NoLimit, 3));
+    }
+
+    #[test]
+    pub fn read_bets() {
+        let range = Range { call: Chips(10), min_raise: Some(Chips(20)), max_raise: Some(Chips(1000)), all_in: Chips(1000) };
+        assert_eq!(hint(&range, None), "fold, call 10, raise 20-1000");
+        assert_eq!(parse_bet("c", &range, None), Ok(Bet::Call(Chips(10))));
+        assert_eq!(parse_bet("R 40", &range, None), Ok(Bet::Raise(Chips(40))));
+        assert_eq!(parse_bet("all in", &range, None), Ok(Bet::Raise(Chips(1000))));
+        assert!(parse_bet("raise lots", &range, None).is_err());
+
+        let range = Range { call: Chips::ZERO, min_raise: None, max_raise: None, all_in: Chips(50) };
+        assert_eq!(hint(&range, None), "check");
+        assert_eq!(parse_bet("call", &range, None), Ok(Bet::Check));
+    }
+
+    #[test]
+    pub fn play_against_bots() {
+        let mut options = Options::new(vec![Seat::Human("ann".to_string()), Seat::Bot("bot1".to_string()), Seat::Bot("bot2".to_string())]);
+        options.seed = Some(11);
+        options.color = false;
+        options.hands = Some(2);
+        // a bad command, then calls and checks through both hands
+        let input = "raise lots\n".to_string() + &"c\n".repeat(20);
+        let mut output = vec![];
+        play(&options, &mut input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("*** hand 2 ***"));
+        assert!(output.contains("lots: "));
+        assert!(output.contains(" wins "));
+        assert!(output.contains("ann ("));
+    }
+}
@@ -0,0 +1,245 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time;
+
+pub const ACTION_TIME: time::Duration = time::Duration::from_secs(30);
+pub const TIME_BANK: time::Duration = time::Duration::from_secs(60);
+pub const WARNING: time::Duration = time::Duration::from_secs(10);
+
+// time elapsed since an arbitrary epoch, so tests can drive the clock by hand
+pub trait TimeSource: fmt::Debug + Send + Sync {
+    fn now(&self) -> time::Duration;
+
+    // when things happened for the hand history, targets without a system clock bring their own
+    fn wall(&self) -> time::SystemTime {
+        time::SystemTime::now()
+    }
+}
+
+#[derive(Debug)]
+pub struct SystemClock {
+    epoch: time::Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock { epoch: time::Instant::now() }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeSource for SystemClock {
+    fn now(&self) -> time::Duration {
+        self.epoch.elapsed()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FakeClock {
+    now: Arc<Mutex<time::Duration>>,
+}
+
+impl FakeClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: time::Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+impl TimeSource for FakeClock {
+    fn now(&self) -> time::Duration {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Settings {
+    pub action_time: time::Duration,
+    pub time_bank: time::Duration,
+    pub max_time_bank: time::Duration,
+    // the bank is topped up by refill every refill_hands hands
+    pub refill: time::Duration,
+    pub refill_hands: u32,
+    pub warning: time::Duration,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            action_time: ACTION_TIME,
+            time_bank: TIME_BANK,
+            max_time_bank: TIME_BANK,
+            refill: time::Duration::from_secs(10),
+            refill_hands: 10,
+            warning: WARNING,
+        }
+    }
+}
+
+impl Settings {
+    pub fn new(action_time: time::Duration) -> Self {
+        Settings { action_time, ..Self::default() }
+    }
+
+    pub fn with_time_bank(mut self, time_bank: time::Duration, max_time_bank: time::Duration) -> Self {
+        self.time_bank = time_bank;
+        self.max_time_bank = max_time_bank;
+        self
+    }
+
+    pub fn with_refill(mut self, refill: time::Duration, hands: u32) -> Self {
+        self.refill = refill;
+        self.refill_hands = hands;
+        self
+    }
+
+    pub fn with_warning(mut self, warning: time::Duration) -> Self {
+        self.warning = warning;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alert {
+    Warning(time::Duration),
+    TimeBank(time::Duration),
+    Expired,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Clock {
+    started: Option<time::Duration>,
+    action_time: time::Duration,
+    time_bank: time::Duration,
+    hands: u32,
+    warned: bool,
+    banking: bool,
+}
+
+impl Clock {
+    pub fn new(settings: &Settings) -> Self {
+        Clock {
+            time_bank: settings.time_bank,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    pub fn time_bank(&self) -> time::Duration {
+        self.time_bank
+    }
+
+    pub fn start(&mut self, now: time::Duration, settings: &Settings) {
+        self.started = Some(now);
+        self.action_time = settings.action_time;
+        self.warned = false;
+        self.banking = false;
+    }
+
+    pub fn extend(&mut self, extra: time::Duration) {
+        self.action_time += extra;
+    }
+
+    pub fn elapsed(&self, now: time::Duration) -> time::Duration {
+        self.started.map_or(time::Duration::ZERO, |started| now.saturating_sub(started))
+    }
+
+    // action time first, then the time bank
+    pub fn remaining(&self, now: time::Duration) -> time::Duration {
+        (self.action_time + self.time_bank).saturating_sub(self.elapsed(now))
+    }
+
+    // every alert fires once per decision
+    pub fn poll(&mut self, now: time::Duration, settings: &Settings) -> Option<Alert> {
+        if !self.is_running() {
+            return None;
+        }
+        let remaining = self.remaining(now);
+        if remaining.is_zero() {
+            return Some(Alert::Expired);
+        }
+        if !self.banking && self.elapsed(now) >= self.action_time {
+            self.banking = true;
+            return Some(Alert::TimeBank(remaining));
+        }
+        if !self.warned && remaining <= settings.warning {
+            self.warned = true;
+            return Some(Alert::Warning(remaining));
+        }
+        None
+    }
+
+    // time used on the decision, anything past the action time comes out of the bank
+    pub fn stop(&mut self, now: time::Duration) -> time::Duration {
+        let used = self.elapsed(now).min(self.action_time + self.time_bank);
+        self.time_bank = self.time_bank.saturating_sub(used.saturating_sub(self.action_time));
+        self.started = None;
+        used
+    }
+
+    pub fn finish_hand(&mut self, settings: &Settings) {
+        self.hands += 1;
+        if settings.refill_hands > 0 && self.hands % settings.refill_hands == 0 {
+            self.time_bank = (self.time_bank + settings.refill).min(settings.max_time_bank);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> time::Duration {
+        time::Duration::from_secs(secs)
+    }
+
+    #[test]
+    pub fn alerts() {
+        let settings = Settings::new(secs(15)).with_time_bank(secs(30), secs(60)).with_warning(secs(10));
+        let time = FakeClock::new();
+        let mut clock = Clock::new(&settings);
+        clock.start(time.now(), &settings);
+
+        time.advance(secs(10));
+        assert_eq!(clock.poll(time.now(), &settings), None);
+        time.advance(secs(5));
+        assert_eq!(clock.poll(time.now(), &settings), Some(Alert::TimeBank(secs(30))));
+        time.advance(secs(25));
+        assert_eq!(clock.poll(time.now(), &settings), Some(Alert::Warning(secs(5))));
+        assert_eq!(clock.poll(time.now(), &settings), None);
+        time.advance(secs(5));
+        assert_eq!(clock.poll(time.now(), &settings), Some(Alert::Expired));
+    }
+
+    #[test]
+    pub fn time_bank_usage_and_refill() {
+        let settings = Settings::new(secs(15)).with_time_bank(secs(30), secs(40)).with_refill(secs(20), 2);
+        let time = FakeClock::new();
+        let mut clock = Clock::new(&settings);
+
+        clock.start(time.now(), &settings);
+        time.advance(secs(25));
+        assert_eq!(clock.stop(time.now()), secs(25));
+        assert_eq!(clock.time_bank(), secs(20));
+        assert!(!clock.is_running());
+
+        clock.finish_hand(&settings);
+        assert_eq!(clock.time_bank(), secs(20));
+        clock.finish_hand(&settings);
+        assert_eq!(clock.time_bank(), secs(40));
+        clock.finish_hand(&settings);
+        clock.finish_hand(&settings);
+        assert_eq!(clock.time_bank(), secs(40));
+    }
+}
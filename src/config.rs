@@ -0,0 +1,298 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time;
+use serde::{Deserialize, Serialize};
+use crate::chips::Chips;
+use crate::cli;
+use crate::game::{Game, Limit, Name, Stake};
+use crate::insurance;
+use crate::locale::Currency;
+use crate::server::Server;
+use crate::storage::{self, Journal, Recovery, Storage};
+
+// the server's json config file, everything but the tables can be left out
+//
+// {"port": 7070, "websocket_port": 7071, "tables": [{"name": "Alpha", "game": "texas", "big_blind": 10}]}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Invalid(String),
+    Storage(storage::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error)
+    }
+}
+
+impl From<storage::Error> for Error {
+    fn from(error: storage::Error) -> Self {
+        Error::Storage(error)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "can't read config: {}", error),
+            Self::Json(error) => write!(f, "bad config: {}", error),
+            Self::Invalid(reason) => write!(f, "bad config: {}", reason),
+            Self::Storage(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TableConfig {
+    pub name: String,
+    pub game: Name,
+    #[serde(default = "default_limit")]
+    pub limit: Limit,
+    pub big_blind: Chips,
+    #[serde(default)]
+    pub ante: Option<Chips>,
+    #[serde(default)]
+    pub currency: Option<Currency>,
+    #[serde(default = "default_table_size")]
+    pub table_size: u8,
+    // the most times players may agree to run the board after an all-in
+    #[serde(default = "default_run_it")]
+    pub run_it: u8,
+    // the house margin in percent, no all-in insurance without it
+    #[serde(default)]
+    pub insurance: Option<f32>,
+    // fixes the shuffle, for testing only
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+// where running tables are kept, {"journal": "tables.jsonl"} or {"sqlite": "tables.db"}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum StorageConfig {
+    Journal(PathBuf),
+    #[cfg(feature = "sqlite")]
+    Sqlite(PathBuf),
+}
+
+impl StorageConfig {
+    pub fn open(&self) -> Result<Box<dyn Storage>, storage::Error> {
+        Ok(match self {
+            Self::Journal(path) => Box::new(Journal::open(path)?),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(path) => Box::new(storage::Sqlite::open(path)?),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default = "default_host")]
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    // no websocket gateway without one
+    #[serde(default)]
+    pub websocket_port: Option<u16>,
+    #[serde(default = "default_heartbeat")]
+    pub heartbeat_secs: u64,
+    #[serde(default = "default_resume_window")]
+    pub resume_window_secs: u64,
+    // login tokens by player name, anyone gets in without them
+    #[serde(default)]
+    pub tokens: Option<BTreeMap<String, String>>,
+    // tables are lost on restart without it
+    #[serde(default)]
+    pub storage: Option<StorageConfig>,
+    // what happens to a hand cut off by a restart
+    #[serde(default)]
+    pub recovery: Recovery,
+    pub tables: Vec<TableConfig>,
+}
+
+fn default_limit() -> Limit { Limit::NoLimit }
+fn default_table_size() -> u8 { 6 }
+fn default_run_it() -> u8 { 1 }
+fn default_host() -> String { "127.0.0.1".to_string() }
+fn default_port() -> u16 { 7070 }
+fn default_heartbeat() -> u64 { 30 }
+fn default_resume_window() -> u64 { 120 }
+
+impl TableConfig {
+    pub fn game(&self) -> Game<'static> {
+        let mut stake = Stake::new(self.big_blind);
+        if let Some(ante) = self.ante {
+            stake = stake.with_ante_of(ante);
+        }
+        if let Some(currency) = self.currency {
+            stake = stake.with_currency(currency);
+        }
+        let mut game = Game::new(self.game, self.limit, stake, self.table_size).with_name(&self.name).with_run_it(self.run_it);
+        if let Some(margin) = self.insurance {
+            game = game.with_insurance(insurance::Policy::new(margin));
+        }
+        match self.seed {
+            Some(seed) => game.with_seed(seed),
+            None => game,
+        }
+    }
+}
+
+impl Config {
+    pub fn new(tables: Vec<TableConfig>) -> Self {
+        Config {
+            host: default_host(),
+            port: default_port(),
+            websocket_port: None,
+            heartbeat_secs: default_heartbeat(),
+            resume_window_secs: default_resume_window(),
+            tokens: None,
+            storage: None,
+            recovery: Recovery::default(),
+            tables,
+        }
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, Error> {
+        let config: Config = serde_json::from_str(s)?;
+        config.check()?;
+        Ok(config)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("configs always serialize")
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        let invalid = |reason: String| Err(Error::Invalid(reason));
+        if self.tables.is_empty() {
+            return invalid("no tables".to_string());
+        }
+        if self.heartbeat_secs == 0 {
+            return invalid("heartbeat_secs must be at least 1".to_string());
+        }
+        for (index, table) in self.tables.iter().enumerate() {
+            if self.tables[..index].iter().any(|other| other.name == table.name) {
+                return invalid(format!("two tables named {}", table.name));
+            }
+            if !cli::playable().contains(&table.game) {
+                return invalid(format!("table {}: {} can't be dealt yet", table.name, table.game.human_readable_name()));
+            }
+            if table.big_blind.is_zero() {
+                return invalid(format!("table {}: no big blind", table.name));
+            }
+            if table.table_size < 2 {
+                return invalid(format!("table {}: needs at least two seats", table.name));
+            }
+        }
+        Ok(())
+    }
+
+    // tables in storage come back as they were, even the ones since dropped from the config
+    pub fn server(&self) -> Result<Server, Error> {
+        let mut server = Server::new()
+            .with_heartbeat(time::Duration::from_secs(self.heartbeat_secs))
+            .with_resume_window(time::Duration::from_secs(self.resume_window_secs));
+        if let Some(tokens) = self.tokens.clone() {
+            server = server.with_authenticator(move |name, token| tokens.get(name).is_some_and(|expected| expected == token));
+        }
+        let mut saved = BTreeMap::new();
+        if let Some(storage) = self.storage.as_ref() {
+            let mut storage = storage.open()?;
+            saved = storage.load()?.into_iter().map(|saved| (saved.table.clone(), saved)).collect();
+            server = server.with_storage(storage)?;
+        }
+        for table in self.tables.iter() {
+            let game = match saved.remove(&table.name) {
+                Some(saved) => storage::restore(|| table.game(), &saved, self.recovery)?,
+                None => table.game(),
+            };
+            server = server.with_table(game);
+        }
+        for saved in saved.into_values() {
+            server = server.with_table(storage::restore(|| saved.setup.game(&saved.table), &saved, self.recovery)?);
+        }
+        Ok(server)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn read_config() {
+        let config = Config::from_json(r#"{"port": 9000, "tables": [{"name": "Alpha", "game": "omaha", "limit": "pot_limit", "big_blind": 20}]}"#).unwrap();
+        assert_eq!((config.host.as_str(), config.port, config.websocket_port), ("127.0.0.1", 9000, None));
+        assert_eq!(config.tables[0].table_size, 6);
+        assert_eq!(Config::from_json(&config.to_json()).unwrap(), config);
+
+        let server = config.server().unwrap();
+        assert_eq!(server.with_game("Alpha", |game| (game.game(), game.limit(), game.stake().big_blind())), Some((Name::Omaha, Limit::PotLimit, Chips(20))));
+    }
+
+    #[test]
+    pub fn bad_configs() {
+        assert!(matches!(Config::from_json(r#"{"tables": []}"#), Err(Error::Invalid(_))));
+        assert!(matches!(Config::from_json(r#"{"tables": [{"name": "A", "game": "texas"}]}"#), Err(Error::Json(_))));
+        assert!(matches!(Config::from_json(r#"{"prot": 1, "tables": [{"name": "A", "game": "texas", "big_blind": 10}]}"#), Err(Error::Json(_))));
+        let error = Config::from_json(r#"{"tables": [{"name": "A", "game": "razz", "big_blind": 10}]}"#).unwrap_err();
+        assert_eq!(error.to_string(), "bad config: table A: Razz can't be dealt yet");
+    }
+
+    #[test]
+    pub fn restart_with_storage() {
+        use std::sync::Arc;
+        use crate::server::{Request, Session};
+
+        let path = std::env::temp_dir().join(format!("pokernode-config-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let json = format!(r#"{{"storage": {{"journal": {:?}}}, "tables": [{{"name": "Alpha", "game": "texas", "big_blind": 10, "seed": 3}}]}}"#, path);
+        let config = Config::from_json(&json).unwrap();
+        let server = Arc::new(config.server().unwrap());
+        let (sender, _messages) = tokio::sync::mpsc::unbounded_channel();
+        let mut sessions = vec![];
+        for name in ["alice", "bob"] {
+            let mut session = Session::new(server.clone(), sender.clone());
+            session.handle(Request::Login { name: name.to_string(), token: String::new() });
+            session.handle(Request::Join { table: "Alpha".to_string(), position: sessions.len() as u8, amount: Chips(1000) });
+            sessions.push(session);
+        }
+        let before = server.with_game("Alpha", |game| (game.to_act(), game.pocket_cards(0).cloned(), game.table().pot().total())).unwrap();
+        assert!(before.0.is_some());
+        drop(sessions);
+        drop(server);
+
+        // the hand the restart cut off goes on
+        let server = config.server().unwrap();
+        assert_eq!(server.with_game("Alpha", |game| (game.to_act(), game.pocket_cards(0).cloned(), game.table().pot().total())), Some(before));
+        drop(server);
+        let mut config = config;
+        config.recovery = Recovery::Void;
+        config.tables.clear();
+        // or is called off, and the table stays even without a config
+        let server = config.server().unwrap();
+        assert_eq!(server.with_game("Alpha", |game| (game.is_hand_in_progress(), game.table().seats()[1].stack())), Some((false, Some(Chips(1000)))));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,58 @@
+use std::time;
+use crate::bet::AutoPlay;
+
+pub const GRACE: time::Duration = time::Duration::from_secs(15);
+pub const AWAY_AFTER_HANDS: u8 = 3;
+
+// how the dealer treats players who lost their connection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Protection {
+    // extra time on the clock while offline
+    pub grace: time::Duration,
+    // played instead of the seat's own auto play when an offline player runs out of time
+    pub auto_play: Option<AutoPlay>,
+    pub away_after_hands: Option<u8>,
+    // number of times an offline player facing a bet is kept in the pot instead of folding
+    pub all_in_protections: u8,
+}
+
+impl Default for Protection {
+    fn default() -> Self {
+        Protection {
+            grace: GRACE,
+            auto_play: Some(AutoPlay::CheckFold),
+            away_after_hands: Some(AWAY_AFTER_HANDS),
+            all_in_protections: 0,
+        }
+    }
+}
+
+impl Protection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_grace(mut self, grace: time::Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    pub fn with_auto_play(mut self, auto_play: Option<AutoPlay>) -> Self {
+        self.auto_play = auto_play;
+        self
+    }
+
+    pub fn with_away_after_hands(mut self, hands: Option<u8>) -> Self {
+        self.away_after_hands = hands;
+        self
+    }
+
+    pub fn with_all_in_protections(mut self, times: u8) -> Self {
+        self.all_in_protections = times;
+        self
+    }
+
+    pub fn is_away(&self, offline_hands: u8) -> bool {
+        self.away_after_hands.is_some_and(|hands| offline_hands >= hands)
+    }
+}
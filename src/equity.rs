@@ -0,0 +1,242 @@
+use std::fmt;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use crate::poker::{Card, Cards, Deck, hand};
+
+const BOARD_SIZE: usize = 5;
+// boards left to deal up to this many are all dealt, more than that are sampled
+const EXACT_LIMIT: u64 = 50_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    NotEnoughHands,
+    PocketSize { hand: usize, expected: usize, actual: usize },
+    BoardSize(usize),
+    Duplicate(Card),
+    NoTrials,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotEnoughHands => write!(f, "need at least two hands"),
+            Self::PocketSize { hand, expected, actual } => write!(f, "hand {} has {} cards, expected {}", hand + 1, actual, expected),
+            Self::BoardSize(size) => write!(f, "a board has at most {} cards, got {}", BOARD_SIZE, size),
+            Self::Duplicate(card) => write!(f, "duplicate card: {:?}", card),
+            Self::NoTrials => write!(f, "too many boards to deal them all and no trials to sample"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// shares of the boards dealt, a two way split counts half towards equity
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Equity {
+    pub win: f64,
+    pub tie: f64,
+    pub equity: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Outcome {
+    pub equities: Vec<Equity>,
+    pub boards: u64,
+    // every board dealt rather than a sample
+    pub exact: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Calculator {
+    pockets: Vec<Cards>,
+    board: Cards,
+    dead: Cards,
+    omaha: bool,
+    trials: u32,
+    seed: u64,
+}
+
+impl Calculator {
+    pub fn new(pockets: Vec<Cards>) -> Self {
+        Calculator {
+            pockets,
+            board: vec![],
+            dead: vec![],
+            omaha: false,
+            trials: 20_000,
+            seed: 0,
+        }
+    }
+
+    pub fn with_board(mut self, board: Cards) -> Self {
+        self.board = board;
+        self
+    }
+
+    // mucked or exposed cards that can't come on the board
+    pub fn with_dead(mut self, dead: Cards) -> Self {
+        self.dead = dead;
+        self
+    }
+
+    // four card hands playing exactly two
+    pub fn with_omaha(mut self) -> Self {
+        self.omaha = true;
+        self
+    }
+
+    pub fn with_trials(mut self, trials: u32) -> Self {
+        self.trials = trials;
+        self
+    }
+
+    // sampling is seeded so the same query always gives the same answer
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    fn check(&self) -> Result<Cards, Error> {
+        if self.pockets.len() < 2 {
+            return Err(Error::NotEnoughHands);
+        }
+        let expected = if self.omaha { 4 } else { 2 };
+        for (index, pocket) in self.pockets.iter().enumerate() {
+            if pocket.len() != expected {
+                return Err(Error::PocketSize { hand: index, expected, actual: pocket.len() });
+            }
+        }
+        if self.board.len() > BOARD_SIZE {
+            return Err(Error::BoardSize(self.board.len()));
+        }
+        let mut known: Cards = vec![];
+        for &card in self.pockets.iter().flatten().chain(self.board.iter()).chain(self.dead.iter()) {
+            if known.contains(&card) {
+                return Err(Error::Duplicate(card));
+            }
+            known.push(card);
+        }
+        Ok(Deck::all().into_iter().filter(|card| !known.contains(card)).collect())
+    }
+
+    pub fn calculate(&self) -> Result<Outcome, Error> {
+        let rest = self.check()?;
+        let missing = BOARD_SIZE - self.board.len();
+        let mut totals = vec![(0u64, 0u64, 0f64); self.pockets.len()];
+
+        let boards = combinations(rest.len() as u64, missing as u64);
+        if boards <= EXACT_LIMIT {
+            let mut indices: Vec<usize> = (0..missing).collect();
+            loop {
+                self.deal(indices.iter().map(|&index| rest[index]), &mut totals);
+                if !next_combination(&mut indices, rest.len()) {
+                    break;
+                }
+            }
+            return Ok(outcome(totals, boards, true));
+        }
+
+        if self.trials == 0 {
+            return Err(Error::NoTrials);
+        }
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut rest = rest;
+        for _ in 0..self.trials {
+            let (runout, _) = rest.partial_shuffle(&mut rng, missing);
+            let runout = runout.to_vec();
+            self.deal(runout.into_iter(), &mut totals);
+        }
+        Ok(outcome(totals, self.trials as u64, false))
+    }
+
+    fn deal(&self, runout: impl Iterator<Item = Card>, totals: &mut [(u64, u64, f64)]) {
+        let mut board = self.board.clone();
+        board.extend(runout);
+        let hands: Vec<_> = self.pockets.iter().map(|pocket| hand::best_high(pocket, &board, self.omaha)).collect();
+        let best = hands.iter().flatten().reduce(|best, hand| if hand > best { hand } else { best });
+        let winners: Vec<usize> = (0..hands.len()).filter(|&index| hands[index].as_ref() == best).collect();
+        for &index in winners.iter() {
+            if winners.len() == 1 {
+                totals[index].0 += 1;
+            } else {
+                totals[index].1 += 1;
+            }
+            totals[index].2 += 1.0 / winners.len() as f64;
+        }
+    }
+}
+
+fn outcome(totals: Vec<(u64, u64, f64)>, boards: u64, exact: bool) -> Outcome {
+    let equities = totals.into_iter()
+        .map(|(wins, ties, share)| Equity {
+            win: wins as f64 / boards as f64,
+            tie: ties as f64 / boards as f64,
+            equity: share / boards as f64,
+        })
+        .collect();
+    Outcome { equities, boards, exact }
+}
+
+fn combinations(n: u64, k: u64) -> u64 {
+    (0..k).fold(1, |result, i| result * (n - i) / (i + 1))
+}
+
+// indices in increasing order, false after the last one
+fn next_combination(indices: &mut [usize], n: usize) -> bool {
+    let k = indices.len();
+    for i in (0..k).rev() {
+        if indices[i] < n - k + i {
+            indices[i] += 1;
+            for j in i + 1..k {
+                indices[j] = indices[j - 1] + 1;
+            }
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::card;
+
+    fn hands(hands: &[&str]) -> Vec<Cards> {
+        hands.iter().map(|hand| card::parse(hand)).collect()
+    }
+
+    #[test]
+    pub fn exact_on_the_turn() {
+        // only one of the two aces left saves the aces against a set
+        let outcome = Calculator::new(hands(&["AhAd", "KhKd"])).with_board(card::parse("2c 7s 9d Ks")).calculate().unwrap();
+        assert!(outcome.exact);
+        assert_eq!(outcome.boards, 44);
+        assert_eq!(outcome.equities[0].win, 2.0 / 44.0);
+        assert_eq!(outcome.equities[1].win, 42.0 / 44.0);
+
+        let outcome = Calculator::new(hands(&["2h3d", "4c5d"])).with_board(card::parse("As Ks Qs Js Ts")).calculate().unwrap();
+        assert_eq!(outcome.boards, 1);
+        assert_eq!(outcome.equities[0], Equity { win: 0.0, tie: 1.0, equity: 0.5 });
+    }
+
+    #[test]
+    pub fn sampled_preflop() {
+        let calculator = Calculator::new(hands(&["AhAd", "KhKd"])).with_trials(5_000).with_seed(7);
+        let outcome = calculator.calculate().unwrap();
+        assert!(!outcome.exact);
+        assert!((outcome.equities[0].equity - 0.82).abs() < 0.03);
+        assert_eq!(calculator.calculate().unwrap(), outcome);
+    }
+
+    #[test]
+    pub fn bad_queries() {
+        assert_eq!(Calculator::new(hands(&["AhAd"])).calculate().unwrap_err(), Error::NotEnoughHands);
+        assert_eq!(Calculator::new(hands(&["AhAd", "AhKd"])).calculate().unwrap_err(), Error::Duplicate(card::parse("Ah")[0]));
+        assert_eq!(
+            Calculator::new(hands(&["AhAd", "KhKd"])).with_omaha().calculate().unwrap_err(),
+            Error::PocketSize { hand: 0, expected: 4, actual: 2 },
+        );
+    }
+}
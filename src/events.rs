@@ -0,0 +1,438 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::bet::Bet;
+use crate::chips::{self, Chips};
+use crate::game::street;
+use crate::poker::Cards;
+use crate::seat;
+
+// everything that moves chips or cards at a table, in order, along with every seat changing
+// status and what it owes. folding the events over an empty State with apply gives the table's
+// state at any point, a snapshot is just a folded State.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    SeatTaken { position: u8, player: String, stack: Chips },
+    // rebuys and top-ups wait for the next hand and are added in one go
+    RebuyRequested { position: u8, amount: Chips },
+    StackAdded { position: u8, amount: Chips },
+    SeatLeft { position: u8 },
+    // a seat moving through its lifecycle, bets and pre-actions in a hand aren't followed
+    SeatChanged { position: u8, transition: seat::Transition, from: seat::Status, to: seat::Status },
+    // the blinds a seat owes after sitting out on them
+    BlindsMissed { position: u8, missed: seat::MissedBlinds },
+    HandStarted { id: u64, seed: u64, button: u8, dealt: Vec<u8> },
+    // no position for the board
+    CardsDealt { position: Option<u8>, cards: Cards },
+    StreetChanged { street: street::Holdem },
+    // amount is what left the stack, raises are to a total for the street
+    BetPlaced { position: u8, bet: Bet, amount: Chips },
+    UncalledReturned { position: u8, amount: Chips },
+    RakeTaken { amount: Chips },
+    // all-in insurance settled with the house, outside the pot
+    InsuranceSettled { position: u8, premium: Chips, payout: Chips },
+    PotAwarded { position: u8, amount: Chips, side_pot: usize },
+    HandFinished,
+    // a hand that can't go on, everyone gets back what they put in
+    HandVoided { refunds: Vec<(u8, Chips)> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    SeatTaken(u8),
+    SeatEmpty(u8),
+    HandInProgress,
+    NoHandInProgress,
+    NotDealtIn(u8),
+    // more chips leaving a stack or the pot than there are
+    Chips(chips::Error),
+    // a hand can't finish with chips left in the pot
+    Unbalanced(Chips),
+}
+
+impl From<chips::Error> for Error {
+    fn from(error: chips::Error) -> Self {
+        Error::Chips(error)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SeatTaken(position) => write!(f, "seat {} is taken", position),
+            Self::SeatEmpty(position) => write!(f, "seat {} is empty", position),
+            Self::HandInProgress => write!(f, "a hand is in progress"),
+            Self::NoHandInProgress => write!(f, "no hand in progress"),
+            Self::NotDealtIn(position) => write!(f, "seat {} is not dealt in", position),
+            Self::Chips(error) => write!(f, "{}", error),
+            Self::Unbalanced(left) => write!(f, "{} left in the pot", left),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Seat {
+    pub player: String,
+    pub stack: Chips,
+    // where the last hand left the seat, then sitting out, coming back and so on
+    pub status: seat::Status,
+    pub sit_out_next_hand: bool,
+    pub missed_blinds: seat::MissedBlinds,
+    pub pending_rebuy: Chips,
+    // none of these outlive the hand
+    pub dealt: bool,
+    pub folded: bool,
+    pub cards: Cards,
+    pub put: Chips,
+    pub in_pot: Chips,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hand {
+    pub id: u64,
+    pub seed: u64,
+    pub button: u8,
+    // stacks as the hand was dealt, before any blinds
+    pub stacks: Vec<(u8, Chips)>,
+    pub street: Option<street::Holdem>,
+    pub board: Cards,
+    pub actions: Vec<(u8, Bet)>,
+    pub pot: Chips,
+    pub rake: Chips,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct State {
+    // how many events went into this state
+    pub sequence: u64,
+    pub seats: BTreeMap<u8, Seat>,
+    pub hand: Option<Hand>,
+    // the last hand started and where its button was
+    pub hand_id: u64,
+    pub button: Option<u8>,
+}
+
+impl State {
+    pub fn stack(&self, position: u8) -> Option<Chips> {
+        self.seats.get(&position).map(|seat| seat.stack)
+    }
+
+    // what voiding the hand in progress gives back
+    pub fn refunds(&self) -> Vec<(u8, Chips)> {
+        self.seats.iter()
+            .filter(|(_, seat)| !seat.in_pot.is_zero())
+            .map(|(&position, seat)| (position, seat.in_pot))
+            .collect()
+    }
+
+    fn end_hand(&mut self) {
+        self.hand = None;
+        for seat in self.seats.values_mut() {
+            seat.dealt = false;
+            seat.folded = false;
+            seat.cards.clear();
+            seat.put = Chips::ZERO;
+            seat.in_pot = Chips::ZERO;
+        }
+    }
+}
+
+fn seat(state: &mut State, position: u8) -> Result<&mut Seat, Error> {
+    state.seats.get_mut(&position).ok_or(Error::SeatEmpty(position))
+}
+
+fn dealt(state: &mut State, position: u8) -> Result<&mut Seat, Error> {
+    seat(state, position).and_then(|seat| if seat.dealt { Ok(seat) } else { Err(Error::NotDealtIn(position)) })
+}
+
+fn hand(state: &mut State) -> Result<&mut Hand, Error> {
+    state.hand.as_mut().ok_or(Error::NoHandInProgress)
+}
+
+// the state after event, without touching anything else
+pub fn apply(mut state: State, event: &Event) -> Result<State, Error> {
+    match event {
+        Event::SeatTaken { position, player, stack } => {
+            if state.seats.contains_key(position) {
+                return Err(Error::SeatTaken(*position));
+            }
+            let seat = Seat {
+                player: player.clone(),
+                stack: *stack,
+                status: seat::Status::Reserved,
+                sit_out_next_hand: false,
+                missed_blinds: seat::MissedBlinds::default(),
+                pending_rebuy: Chips::ZERO,
+                dealt: false,
+                folded: false,
+                cards: vec![],
+                put: Chips::ZERO,
+                in_pot: Chips::ZERO,
+            };
+            state.seats.insert(*position, seat);
+        },
+        Event::RebuyRequested { position, amount } => {
+            let seat = seat(&mut state, *position)?;
+            seat.pending_rebuy = seat.pending_rebuy.checked_add(*amount)?;
+        },
+        Event::StackAdded { position, amount } => {
+            let seat = seat(&mut state, *position)?;
+            seat.stack = seat.stack.checked_add(*amount)?;
+            seat.pending_rebuy = seat.pending_rebuy.checked_sub(*amount)?;
+        },
+        Event::SeatLeft { position } => {
+            if seat(&mut state, *position)?.dealt && state.hand.is_some() {
+                return Err(Error::HandInProgress);
+            }
+            state.seats.remove(position);
+        },
+        Event::HandStarted { id, seed, button, dealt: positions } => {
+            if state.hand.is_some() {
+                return Err(Error::HandInProgress);
+            }
+            let mut stacks = vec![];
+            for &position in positions {
+                let seat = seat(&mut state, position)?;
+                seat.dealt = true;
+                stacks.push((position, seat.stack));
+            }
+            state.hand_id = *id;
+            state.button = Some(*button);
+            state.hand = Some(Hand { id: *id, seed: *seed, button: *button, stacks, street: None, board: vec![], actions: vec![], pot: Chips::ZERO, rake: Chips::ZERO });
+        },
+        Event::CardsDealt { position: Some(position), cards } => {
+            hand(&mut state)?;
+            dealt(&mut state, *position)?.cards.extend(cards.iter().copied());
+        },
+        Event::CardsDealt { position: None, cards } => hand(&mut state)?.board.extend(cards.iter().copied()),
+        Event::StreetChanged { street } => {
+            hand(&mut state)?.street = Some(*street);
+            // blinds count towards the first street
+            if *street != street::Holdem::Preflop {
+                for seat in state.seats.values_mut() {
+                    seat.put = Chips::ZERO;
+                }
+            }
+        },
+        Event::BetPlaced { position, bet, amount } => {
+            hand(&mut state)?.actions.push((*position, *bet));
+            let seat = dealt(&mut state, *position)?;
+            seat.stack = seat.stack.checked_sub(*amount)?;
+            seat.put += *amount;
+            seat.in_pot += *amount;
+            seat.folded |= *bet == Bet::Fold;
+            let hand = hand(&mut state)?;
+            hand.pot = hand.pot.checked_add(*amount)?;
+        },
+        Event::UncalledReturned { position, amount } | Event::PotAwarded { position, amount, .. } => {
+            let hand = hand(&mut state)?;
+            hand.pot = hand.pot.checked_sub(*amount)?;
+            let seat = dealt(&mut state, *position)?;
+            seat.stack = seat.stack.checked_add(*amount)?;
+        },
+        Event::RakeTaken { amount } => {
+            let hand = hand(&mut state)?;
+            hand.pot = hand.pot.checked_sub(*amount)?;
+            hand.rake += *amount;
+        },
+        Event::InsuranceSettled { position, premium, payout } => {
+            hand(&mut state)?;
+            let seat = dealt(&mut state, *position)?;
+            seat.stack = seat.stack.checked_add(*payout)?.checked_sub(*premium)?;
+        },
+        // leaving comes after the seat is gone
+        Event::SeatChanged { position, transition, from, to } => if let Some(seat) = state.seats.get_mut(position) {
+            seat.status = *to;
+            match transition {
+                seat::Transition::SitOut if from == to => seat.sit_out_next_hand = true,
+                seat::Transition::ComeBack | seat::Transition::FinishHand => seat.sit_out_next_hand = false,
+                seat::Transition::DealIn => seat.missed_blinds = seat::MissedBlinds::default(),
+                _ => {},
+            }
+        },
+        Event::BlindsMissed { position, missed } => seat(&mut state, *position)?.missed_blinds = *missed,
+        Event::HandFinished => {
+            let pot = hand(&mut state)?.pot;
+            if !pot.is_zero() {
+                return Err(Error::Unbalanced(pot));
+            }
+            state.end_hand();
+        },
+        Event::HandVoided { refunds } => {
+            let hand = hand(&mut state)?;
+            // only while every chip is still in the pot
+            let refunded = refunds.iter().map(|&(_, amount)| amount).sum::<Chips>();
+            if !hand.rake.is_zero() || hand.pot != refunded {
+                return Err(Error::Unbalanced(hand.pot.saturating_sub(refunded)));
+            }
+            if *refunds != state.refunds() {
+                return Err(Error::Unbalanced(Chips::ZERO));
+            }
+            for &(position, amount) in refunds {
+                let seat = seat(&mut state, position)?;
+                seat.stack = seat.stack.checked_add(amount)?;
+            }
+            // where finishing the hand would have left everyone, whoever left sits out with the refund
+            for seat in state.seats.values_mut().filter(|seat| seat.dealt) {
+                let sit_out = seat.sit_out_next_hand || seat.status == seat::Status::Gone || (seat.stack + seat.pending_rebuy).is_zero();
+                seat.status = if sit_out { seat::Status::SittingOut } else { seat::Status::Ready };
+                seat.sit_out_next_hand = false;
+            }
+            state.end_hand();
+        },
+    }
+    state.sequence += 1;
+    Ok(state)
+}
+
+// a snapshot caught up on the events that came after it
+pub fn rebuild(snapshot: State, tail: &[Event]) -> Result<State, Error> {
+    tail.iter().try_fold(snapshot, apply)
+}
+
+const SNAPSHOT_EVERY: u64 = 100;
+
+// the current state, the last snapshot and the events since, older events are left to storage
+#[derive(Debug, Clone)]
+pub struct Log {
+    state: State,
+    snapshot: State,
+    tail: Vec<Event>,
+    every: u64,
+}
+
+impl Default for Log {
+    fn default() -> Self {
+        Log { state: State::default(), snapshot: State::default(), tail: vec![], every: SNAPSHOT_EVERY }
+    }
+}
+
+impl Log {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // picks up where a stored snapshot and its tail left off
+    pub fn restore(snapshot: State, tail: Vec<Event>) -> Result<Self, Error> {
+        let state = rebuild(snapshot.clone(), &tail)?;
+        Ok(Log { state, snapshot, tail, ..Self::default() })
+    }
+
+    pub fn with_snapshots_every(mut self, events: u64) -> Self {
+        self.every = events.max(1);
+        self
+    }
+
+    // an event that doesn't apply is left out and the state stays as it was
+    pub fn push(&mut self, event: Event) -> Result<&State, Error> {
+        self.state = apply(self.state.clone(), &event)?;
+        self.tail.push(event);
+        if self.state.sequence % self.every == 0 {
+            self.snapshot = self.state.clone();
+            self.tail.clear();
+        }
+        Ok(&self.state)
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    pub fn snapshot(&self) -> &State {
+        &self.snapshot
+    }
+
+    pub fn tail(&self) -> &[Event] {
+        &self.tail
+    }
+
+    pub fn rebuild(&self) -> Result<State, Error> {
+        rebuild(self.snapshot.clone(), &self.tail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Game, Limit, Name, Stake};
+    use crate::player::Player;
+
+    fn played<'a>() -> Game<'a> {
+        let mut game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 6).with_seed(3).with_snapshots_every(7);
+        game.join(Player::blank_player("a"), 0, Chips(1000)).unwrap();
+        game.join(Player::blank_player("b"), 2, Chips(500)).unwrap();
+        game.join(Player::blank_player("c"), 4, Chips(800)).unwrap();
+        // one hand to showdown, one raised and folded to, one raised and called down
+        for hand in 0..3 {
+            game.start().unwrap();
+            let mut acted = 0;
+            while let Some(position) = game.to_act() {
+                let range = game.range(position).unwrap();
+                let bet = match (hand, acted) {
+                    (1 | 2, 0) => Bet::Raise(range.min_raise.unwrap()),
+                    (1, _) => Bet::Fold,
+                    _ if range.can_check() => Bet::Check,
+                    _ => Bet::Call(range.call),
+                };
+                game.act(position, bet).unwrap();
+                acted += 1;
+            }
+        }
+        game
+    }
+
+    #[test]
+    pub fn follow_the_game() {
+        let game = played();
+        let state = game.events().state();
+        assert_eq!((state.hand_id, state.hand.as_ref()), (3, None));
+        for (&position, seat) in state.seats.iter() {
+            assert_eq!(Some(seat.stack), game.table().seat(position).and_then(|seat| seat.stack()));
+        }
+        assert_eq!(state.seats.values().map(|seat| seat.stack).sum::<Chips>(), Chips(2300));
+    }
+
+    #[test]
+    pub fn snapshot_and_tail() {
+        let game = played();
+        let log = game.events();
+        assert_eq!(log.snapshot().sequence % 7, 0);
+        assert!(log.tail().len() < 7);
+        assert_eq!(&log.rebuild().unwrap(), log.state());
+        let json = serde_json::to_string(log.snapshot()).unwrap();
+        let restored = Log::restore(serde_json::from_str(&json).unwrap(), log.tail().to_vec()).unwrap();
+        assert_eq!(restored.state(), log.state());
+    }
+
+    #[test]
+    pub fn mid_hand() {
+        let mut game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 2).with_seed(1);
+        game.join(Player::blank_player("a"), 0, Chips(1000)).unwrap();
+        game.join(Player::blank_player("b"), 1, Chips(1000)).unwrap();
+        game.start().unwrap();
+        game.act(0, Bet::Raise(Chips(30))).unwrap();
+        let hand = game.events().state().hand.clone().unwrap();
+        assert_eq!((hand.pot, hand.street, hand.actions.last()), (Chips(40), Some(street::Holdem::Preflop), Some(&(0, Bet::Raise(Chips(30))))));
+        assert_eq!(game.events().state().seats[&1].cards, *game.pocket_cards(1).unwrap());
+    }
+
+    #[test]
+    pub fn bad_events() {
+        let mut log = Log::new();
+        log.push(Event::SeatTaken { position: 1, player: "a".to_string(), stack: Chips(100) }).unwrap();
+        assert_eq!(log.push(Event::SeatTaken { position: 1, player: "b".to_string(), stack: Chips(100) }).err(), Some(Error::SeatTaken(1)));
+        assert_eq!(log.push(Event::HandFinished).err(), Some(Error::NoHandInProgress));
+        log.push(Event::HandStarted { id: 1, seed: 0, button: 1, dealt: vec![1] }).unwrap();
+        assert_eq!(log.push(Event::BetPlaced { position: 1, bet: Bet::Call(Chips(200)), amount: Chips(200) }).err(), Some(Error::Chips(chips::Error::Underflow)));
+        log.push(Event::BetPlaced { position: 1, bet: Bet::Call(Chips(20)), amount: Chips(20) }).unwrap();
+        assert_eq!(log.push(Event::HandFinished).err(), Some(Error::Unbalanced(Chips(20))));
+        assert_eq!(log.state().sequence, 3);
+        assert!(log.push(Event::HandVoided { refunds: vec![(1, Chips(30))] }).is_err());
+        log.push(Event::HandVoided { refunds: log.state().refunds() }).unwrap();
+        assert_eq!((log.state().stack(1), log.state().hand.as_ref()), (Some(Chips(100)), None));
+    }
+}
@@ -0,0 +1,500 @@
+#![allow(clippy::missing_safety_doc)]
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::ffi::{c_char, CStr};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use serde::de::DeserializeOwned;
+use crate::bet::Bet;
+use crate::chips::Chips;
+use crate::game::{self, Game, Limit, Name, Stake};
+use crate::player::Player;
+use crate::poker::{card, hand, Card, Cards, Hand, Rank};
+use crate::protocol::{self, Event, Feed, Viewer};
+use crate::record::Record;
+use crate::table;
+
+// the c api, include/pokernode.h is generated from this file by cbindgen (see cbindgen.toml)
+//
+// strings in and out are nul terminated utf-8, events and tables come out as the same json
+// the network protocols use (docs/websocket.md). a card is one byte, rank * 4 + suit with
+// ranks from deuce 0 to ace 12 and suits spades 0, hearts 1, diamonds 2, clubs 3.
+
+/// What every call returns, the reason for anything but PN_STATUS_OK is left for pn_last_error.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PnStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidArgument = 2,
+    InvalidCards = 3,
+    NotEnoughPlayers = 4,
+    HandInProgress = 5,
+    NoHandInProgress = 6,
+    NotYourTurn = 7,
+    Unsupported = 8,
+    IllegalBet = 9,
+    Seat = 10,
+    Chips = 11,
+    NoEvent = 12,
+    BufferTooSmall = 13,
+    Panic = 14,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PnBet {
+    Check = 0,
+    Fold = 1,
+    Call = 2,
+    Raise = 3,
+}
+
+/// What the seat to act may do: call is 0 when it can check, min_raise and max_raise are 0 when it can't raise.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PnRange {
+    pub call: u64,
+    pub min_raise: u64,
+    pub max_raise: u64,
+    pub all_in: u64,
+}
+
+/// The best five cards, rank is 1 for high card up to 9 for a straight flush.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PnHand {
+    pub rank: u8,
+    pub cards: [u8; 5],
+}
+
+/// A table with its own event queue, from pn_game_new until pn_game_free.
+pub struct PnGame {
+    game: Game<'static>,
+    feed: Feed,
+    events: VecDeque<Event>,
+}
+
+impl PnGame {
+    fn sync(&mut self) {
+        let finished = !self.game.is_hand_in_progress();
+        if let Some(history) = self.game.history() {
+            self.events.extend(self.feed.update(&Record::from(history), finished));
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+struct Failure(PnStatus, String);
+
+impl Failure {
+    fn new(status: PnStatus, message: impl Into<String>) -> Self {
+        Failure(status, message.into())
+    }
+}
+
+impl From<game::Error> for Failure {
+    fn from(error: game::Error) -> Self {
+        let status = match error {
+            game::Error::NotEnoughPlayers => PnStatus::NotEnoughPlayers,
+            game::Error::HandInProgress => PnStatus::HandInProgress,
+            game::Error::NoHandInProgress => PnStatus::NoHandInProgress,
+            game::Error::NotYourTurn(_) => PnStatus::NotYourTurn,
+            game::Error::Unsupported(_) => PnStatus::Unsupported,
+            game::Error::Betting(_) => PnStatus::IllegalBet,
+            game::Error::Table(table::Error::Chips(_)) | game::Error::Chips(_) => PnStatus::Chips,
+            game::Error::Table(_) => PnStatus::Seat,
+            // only restoring a game from storage gets these
+            game::Error::Events(_) | game::Error::Showdown(_) | game::Error::Diverged => PnStatus::InvalidArgument,
+        };
+        Failure(status, error.to_string())
+    }
+}
+
+impl From<table::Error> for Failure {
+    fn from(error: table::Error) -> Self {
+        let status = match error {
+            table::Error::Chips(_) => PnStatus::Chips,
+            _ => PnStatus::Seat,
+        };
+        Failure(status, error.to_string())
+    }
+}
+
+impl From<card::Error> for Failure {
+    fn from(error: card::Error) -> Self {
+        Failure(PnStatus::InvalidCards, error.to_string())
+    }
+}
+
+fn call(f: impl FnOnce() -> Result<(), Failure>) -> PnStatus {
+    let (status, message) = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => return PnStatus::Ok,
+        Ok(Err(Failure(status, message))) => (status, message),
+        Err(_) => (PnStatus::Panic, "panicked".to_string()),
+    };
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+    status
+}
+
+unsafe fn string<'a>(s: *const c_char) -> Result<&'a str, Failure> {
+    if s.is_null() {
+        return Err(Failure::new(PnStatus::NullPointer, "null string"));
+    }
+    CStr::from_ptr(s).to_str().map_err(|_| Failure::new(PnStatus::InvalidArgument, "not utf-8"))
+}
+
+unsafe fn handle<'a>(game: *mut PnGame) -> Result<&'a mut PnGame, Failure> {
+    game.as_mut().ok_or_else(|| Failure::new(PnStatus::NullPointer, "null game"))
+}
+
+unsafe fn out<'a, T>(value: *mut T) -> Result<&'a mut T, Failure> {
+    value.as_mut().ok_or_else(|| Failure::new(PnStatus::NullPointer, "null output"))
+}
+
+unsafe fn cards(cards: *const u8, count: usize) -> Result<Cards, Failure> {
+    if count == 0 {
+        return Ok(vec![]);
+    }
+    if cards.is_null() {
+        return Err(Failure::new(PnStatus::NullPointer, "null cards"));
+    }
+    slice::from_raw_parts(cards, count).iter()
+        .map(|&card| Card::from_u8(card).ok_or_else(|| Failure::new(PnStatus::InvalidCards, format!("invalid card: {}", card))))
+        .collect()
+}
+
+// always sets written to the size needed with the nul, copies only when it fits
+unsafe fn copy(s: &str, buffer: *mut c_char, length: usize, written: *mut usize) -> Result<(), Failure> {
+    let needed = s.len() + 1;
+    if let Some(written) = written.as_mut() {
+        *written = needed;
+    }
+    if buffer.is_null() || length < needed {
+        return Err(Failure::new(PnStatus::BufferTooSmall, format!("needs {} bytes", needed)));
+    }
+    ptr::copy_nonoverlapping(s.as_ptr(), buffer as *mut u8, s.len());
+    *buffer.add(s.len()) = 0;
+    Ok(())
+}
+
+// the snake_case names used everywhere else, "texas" or "no_limit"
+fn parse<T: DeserializeOwned>(s: &str) -> Result<T, Failure> {
+    serde_json::from_value(serde_json::Value::String(s.to_string())).map_err(|_| Failure::new(PnStatus::InvalidArgument, format!("unknown: {}", s)))
+}
+
+// enums come in from C as plain integers, a value outside the enum would be undefined behaviour
+fn bet(bet: u8, amount: u64) -> Result<Bet, Failure> {
+    let known = [PnBet::Check, PnBet::Fold, PnBet::Call, PnBet::Raise].into_iter().find(|&known| known as u8 == bet);
+    match known.ok_or_else(|| Failure::new(PnStatus::InvalidArgument, format!("unknown bet: {}", bet)))? {
+        PnBet::Check => Ok(Bet::Check),
+        PnBet::Fold => Ok(Bet::Fold),
+        PnBet::Call => Ok(Bet::Call(Chips(amount))),
+        PnBet::Raise => Ok(Bet::Raise(Chips(amount))),
+    }
+}
+
+// a seat, -1 for a spectator or -2 for an admin who sees every card
+fn viewer(viewer: i16) -> Viewer {
+    match viewer {
+        -2 => Viewer::Admin,
+        viewer => u8::try_from(viewer).ok().into(),
+    }
+}
+
+fn rank(hand: &Hand) -> u8 {
+    match hand.rank() {
+        Some(Rank::High(rank)) => rank as u8 + 1,
+        _ => 0,
+    }
+}
+
+/// Copies the reason the last call on this thread failed.
+#[no_mangle]
+pub unsafe extern "C" fn pn_last_error(buffer: *mut c_char, length: usize, written: *mut usize) -> PnStatus {
+    // keeps the message even when it doesn't fit
+    let message = LAST_ERROR.with(|last| last.borrow().clone());
+    match copy(&message, buffer, length, written) {
+        Ok(()) => PnStatus::Ok,
+        Err(Failure(status, _)) => status,
+    }
+}
+
+/// A table for game ("texas", "omaha", "omaha8") and limit ("fixed_limit", "pot_limit", "no_limit"),
+/// a seed of 0 shuffles at random.
+#[no_mangle]
+pub unsafe extern "C" fn pn_game_new(game: *const c_char, limit: *const c_char, big_blind: u64, table_size: u8, seed: u64, result: *mut *mut PnGame) -> PnStatus {
+    call(|| {
+        let result = out(result)?;
+        let name: Name = parse(string(game)?)?;
+        let limit: Limit = parse(string(limit)?)?;
+        if big_blind < 2 || table_size < 2 {
+            return Err(Failure::new(PnStatus::InvalidArgument, "needs a big blind of at least 2 and two seats"));
+        }
+        let mut game = Game::new(name, limit, Stake::new(Chips(big_blind)), table_size);
+        if seed != 0 {
+            game = game.with_seed(seed);
+        }
+        *result = Box::into_raw(Box::new(PnGame { game, feed: Feed::default(), events: VecDeque::new() }));
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pn_game_free(game: *mut PnGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pn_game_join(game: *mut PnGame, position: u8, name: *const c_char, stack: u64) -> PnStatus {
+    call(|| {
+        let game = handle(game)?;
+        game.game.join(Player::blank_player(string(name)?), position, Chips(stack))?;
+        Ok(())
+    })
+}
+
+/// Folds first if the hand is still live, cash_out is what the seat takes away.
+#[no_mangle]
+pub unsafe extern "C" fn pn_game_leave(game: *mut PnGame, position: u8, cash_out: *mut u64) -> PnStatus {
+    call(|| {
+        let game = handle(game)?;
+        let cash_out = out(cash_out)?;
+        *cash_out = game.game.leave(position)?.unwrap_or_default().amount();
+        game.sync();
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pn_game_start(game: *mut PnGame) -> PnStatus {
+    call(|| {
+        let game = handle(game)?;
+        game.game.start()?;
+        game.sync();
+        Ok(())
+    })
+}
+
+/// The seat to act, -1 when nobody is.
+#[no_mangle]
+pub unsafe extern "C" fn pn_game_to_act(game: *mut PnGame, position: *mut i16) -> PnStatus {
+    call(|| {
+        let game = handle(game)?;
+        *out(position)? = game.game.to_act().map_or(-1, i16::from);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pn_game_range(game: *mut PnGame, position: u8, range: *mut PnRange) -> PnStatus {
+    call(|| {
+        let game = handle(game)?;
+        let result = out(range)?;
+        let range = game.game.range(position).ok_or(game::Error::NoHandInProgress)?;
+        *result = PnRange {
+            call: range.call.amount(),
+            min_raise: range.min_raise.unwrap_or_default().amount(),
+            max_raise: range.max_raise.unwrap_or_default().amount(),
+            all_in: range.all_in.amount(),
+        };
+        Ok(())
+    })
+}
+
+/// Bet is one of PnBet, anything else is PN_STATUS_INVALID_ARGUMENT.
+/// Raises are to a total for the street, amount is ignored for checks and folds.
+#[no_mangle]
+pub unsafe extern "C" fn pn_game_act(game: *mut PnGame, position: u8, bet: u8, amount: u64) -> PnStatus {
+    call(|| {
+        let game = handle(game)?;
+        game.game.act(position, self::bet(bet, amount)?)?;
+        game.sync();
+        Ok(())
+    })
+}
+
+/// Takes the next event as json, with other seats' cards hidden from viewer (-1 for a spectator, -2 for an admin).
+/// Returns PN_STATUS_NO_EVENT when there is none, an event that doesn't fit stays queued.
+#[no_mangle]
+pub unsafe extern "C" fn pn_game_poll_event(game: *mut PnGame, viewer: i16, buffer: *mut c_char, length: usize, written: *mut usize) -> PnStatus {
+    call(|| {
+        let game = handle(game)?;
+        let event = game.events.front().ok_or_else(|| Failure::new(PnStatus::NoEvent, "no event"))?;
+        let json = serde_json::to_string(&event.masked(self::viewer(viewer))).expect("events always serialize");
+        copy(&json, buffer, length, written)?;
+        game.events.pop_front();
+        Ok(())
+    })
+}
+
+/// The whole table as json the way viewer (-1 for a spectator, -2 for an admin) sees it.
+#[no_mangle]
+pub unsafe extern "C" fn pn_game_table(game: *mut PnGame, viewer: i16, buffer: *mut c_char, length: usize, written: *mut usize) -> PnStatus {
+    call(|| {
+        let game = handle(game)?;
+        let table = protocol::Table::view(&game.game, self::viewer(viewer));
+        copy(&serde_json::to_string(&table).expect("tables always serialize"), buffer, length, written)
+    })
+}
+
+/// Parses cards like "Ah Kd", count is how many there were even when they don't fit.
+#[no_mangle]
+pub unsafe extern "C" fn pn_parse_cards(s: *const c_char, cards: *mut u8, length: usize, count: *mut usize) -> PnStatus {
+    call(|| {
+        let parsed = card::parse_strict(string(s)?)?;
+        *out(count)? = parsed.len();
+        if parsed.len() > length || (cards.is_null() && !parsed.is_empty()) {
+            return Err(Failure::new(PnStatus::BufferTooSmall, format!("needs {} cards", parsed.len())));
+        }
+        for (index, card) in parsed.iter().enumerate() {
+            *cards.add(index) = card.to_u8();
+        }
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pn_format_cards(cards: *const u8, count: usize, buffer: *mut c_char, length: usize, written: *mut usize) -> PnStatus {
+    call(|| copy(&card::format(&self::cards(cards, count)?), buffer, length, written))
+}
+
+/// The best high hand from a pocket and a board, omaha plays exactly two pocket cards.
+#[no_mangle]
+pub unsafe extern "C" fn pn_evaluate(pocket: *const u8, pocket_count: usize, board: *const u8, board_count: usize, omaha: bool, result: *mut PnHand) -> PnStatus {
+    call(|| {
+        let result = out(result)?;
+        let best = hand::best_high(&cards(pocket, pocket_count)?, &cards(board, board_count)?, omaha)
+            .ok_or_else(|| Failure::new(PnStatus::InvalidCards, "not enough cards for a hand"))?;
+        let mut hand = PnHand { rank: rank(&best), cards: [0; 5] };
+        for (index, card) in best.cards().iter().enumerate() {
+            hand.cards[index] = card.to_u8();
+        }
+        *result = hand;
+        Ok(())
+    })
+}
+
+unsafe fn evaluated(hand: *const PnHand) -> Result<Hand, Failure> {
+    let hand = hand.as_ref().ok_or_else(|| Failure::new(PnStatus::NullPointer, "null hand"))?;
+    Ok(hand::high(&cards(hand.cards.as_ptr(), hand.cards.len())?))
+}
+
+/// order is 1 when a beats b, -1 when b wins and 0 for a split.
+#[no_mangle]
+pub unsafe extern "C" fn pn_hand_compare(a: *const PnHand, b: *const PnHand, order: *mut i8) -> PnStatus {
+    call(|| {
+        let (a, b) = (evaluated(a)?, evaluated(b)?);
+        *out(order)? = a.partial_cmp(&b).map_or(0, |order| order as i8);
+        Ok(())
+    })
+}
+
+/// Like "two pairs, As and 7s".
+#[no_mangle]
+pub unsafe extern "C" fn pn_hand_describe(hand: *const PnHand, buffer: *mut c_char, length: usize, written: *mut usize) -> PnStatus {
+    call(|| copy(&evaluated(hand)?.description(), buffer, length, written))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    fn text(f: impl FnOnce(*mut c_char, usize, *mut usize) -> PnStatus) -> Result<String, PnStatus> {
+        let mut buffer = vec![0 as c_char; 4096];
+        let mut written = 0;
+        match f(buffer.as_mut_ptr(), buffer.len(), &mut written) {
+            PnStatus::Ok => Ok(unsafe { CStr::from_ptr(buffer.as_ptr()) }.to_str().unwrap().to_string()),
+            status => Err(status),
+        }
+    }
+
+    #[test]
+    pub fn play_through_the_c_api() {
+        let (texas, no_limit) = (CString::new("texas").unwrap(), CString::new("no_limit").unwrap());
+        let mut game = ptr::null_mut();
+        unsafe {
+            assert_eq!(pn_game_new(texas.as_ptr(), no_limit.as_ptr(), 10, 6, 42, &mut game), PnStatus::Ok);
+            for (position, name) in [(0, "alice"), (3, "bob")] {
+                assert_eq!(pn_game_join(game, position, CString::new(name).unwrap().as_ptr(), 1000), PnStatus::Ok);
+            }
+            assert_eq!(pn_game_join(game, 3, CString::new("carol").unwrap().as_ptr(), 1000), PnStatus::Seat);
+            assert_eq!(text(|buffer, length, written| pn_last_error(buffer, length, written)).unwrap(), "seat 3 is taken");
+            assert_eq!(pn_game_start(game), PnStatus::Ok);
+
+            let mut position = -1;
+            assert_eq!(pn_game_to_act(game, &mut position), PnStatus::Ok);
+            let mut range = PnRange::default();
+            assert_eq!(pn_game_range(game, position as u8, &mut range), PnStatus::Ok);
+            assert_eq!(range.call, 5);
+            assert_eq!(pn_game_act(game, position as u8, PnBet::Check as u8, 0), PnStatus::IllegalBet);
+            assert_eq!(pn_game_act(game, position as u8, 7, 0), PnStatus::InvalidArgument);
+            assert_eq!(pn_game_act(game, position as u8, PnBet::Fold as u8, 0), PnStatus::Ok);
+            assert_eq!(pn_game_to_act(game, &mut position), PnStatus::Ok);
+            assert_eq!(position, -1);
+
+            // a spectator sees the deals face down, the first event is the hand starting
+            let mut events = vec![];
+            while let Ok(event) = text(|buffer, length, written| pn_game_poll_event(game, -1, buffer, length, written)) {
+                events.push(serde_json::from_str::<Event>(&event).unwrap());
+            }
+            assert!(matches!(events[0], Event::HandStarted { .. }));
+            assert!(events.iter().all(|event| !matches!(event, Event::CardsDealt { position: Some(_), cards, .. } if cards.iter().any(Option::is_some))));
+            assert!(matches!(events.last(), Some(Event::HandFinished { .. })));
+            assert_eq!(pn_game_poll_event(game, -1, ptr::null_mut(), 0, ptr::null_mut()), PnStatus::NoEvent);
+
+            let table = text(|buffer, length, written| pn_game_table(game, 0, buffer, length, written)).unwrap();
+            assert!(table.contains(r#""name":"alice""#));
+            let mut cash_out = 0;
+            assert_eq!(pn_game_leave(game, 3, &mut cash_out), PnStatus::Ok);
+            assert_eq!(cash_out, 1005);
+            pn_game_free(game);
+        }
+    }
+
+    #[test]
+    pub fn cards_and_hands() {
+        let mut cards = [0u8; 7];
+        let mut count = 0;
+        unsafe {
+            let s = CString::new("Ah Ad 7c 7d 2s Kh 9c").unwrap();
+            assert_eq!(pn_parse_cards(s.as_ptr(), cards.as_mut_ptr(), cards.len(), &mut count), PnStatus::Ok);
+            assert_eq!(count, 7);
+            assert_eq!(text(|buffer, length, written| pn_format_cards(cards.as_ptr(), 2, buffer, length, written)).unwrap(), "Ah Ad");
+            assert_eq!(pn_parse_cards(CString::new("Ah Ax").unwrap().as_ptr(), cards.as_mut_ptr(), cards.len(), &mut count), PnStatus::InvalidCards);
+            assert_eq!(pn_parse_cards(s.as_ptr(), cards.as_mut_ptr(), 2, &mut count), PnStatus::BufferTooSmall);
+
+            let (mut pairs, mut set) = (PnHand::default(), PnHand::default());
+            assert_eq!(pn_evaluate(cards.as_ptr(), 2, cards[2..].as_ptr(), 5, false, &mut pairs), PnStatus::Ok);
+            assert_eq!(pairs.rank, 3);
+            assert_eq!(text(|buffer, length, written| pn_hand_describe(&pairs, buffer, length, written)).unwrap(), "two pairs, As and 7s");
+            let quads = card::parse("7h 7s").iter().map(Card::to_u8).collect::<Vec<_>>();
+            assert_eq!(pn_evaluate(quads.as_ptr(), 2, cards[2..].as_ptr(), 5, false, &mut set), PnStatus::Ok);
+            assert_eq!(set.rank, 8);
+            let mut order = 0;
+            assert_eq!(pn_hand_compare(&pairs, &set, &mut order), PnStatus::Ok);
+            assert_eq!(order, -1);
+
+            let mut written = 0;
+            assert_eq!(pn_hand_describe(&pairs, ptr::null_mut(), 0, &mut written), PnStatus::BufferTooSmall);
+            assert_eq!(written, "two pairs, As and 7s".len() + 1);
+        }
+    }
+
+    #[test]
+    pub fn published_header() {
+        let mut header = vec![];
+        cbindgen::generate(env!("CARGO_MANIFEST_DIR")).unwrap().write(&mut header);
+        assert_eq!(String::from_utf8(header).unwrap(), HEADER, "regenerate include/pokernode.h with cbindgen");
+    }
+
+    const HEADER: &str = include_str!("../include/pokernode.h");
+}
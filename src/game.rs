@@ -1,8 +1,26 @@
-use crate::{table::Table, poker::Deck, bet::Action, locale::Currency};
+use crate::{table::{self, Table}, poker::{Card, Cards, Deck, Hand, hand}, bet::{Action, AutoPlay, Bet}, locale::Currency, player::Player, rake, showdown, insurance};
+use crate::betting::{self, Range, Round};
+use crate::button::Rotation;
+use crate::seat::{self, AutoRebuy};
+use crate::clock::{self, Alert, Clock, TimeSource};
+use crate::disconnect::Protection;
+use crate::chips::{self, Chips};
+use crate::history::{self, History};
+use crate::pot::SidePot;
+use crate::equity::Calculator;
+use crate::events::{self, Event};
+use std::collections::BTreeSet;
 use std::fmt;
 use std::fmt::{Display};
+use std::time;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use vec_map::VecMap;
 
-#[derive(Default, Debug, Clone, Copy, PartialEq)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum Limit {
     #[default]
     FixedLimit,
@@ -28,7 +46,8 @@ impl Limit {
     }
 }
 
-#[derive(Default, Debug, Clone, Copy, PartialEq)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum Name {
     #[default]
     Texas,
@@ -45,6 +64,8 @@ pub enum Name {
 }
 
 impl Name {
+    pub const ALL: [Self; 11] = [Self::Texas, Self::Omaha, Self::Omaha8, Self::Stud, Self::Stud8, Self::Razz, Self::London, Self::FiveCard, Self::Single27, Self::Triple27, Self::Badugi];
+
     pub fn human_readable_name(&self) -> &str {
         match self {
             Self::Texas => "Texas Hold'em",
@@ -98,6 +119,10 @@ impl Mix {
         }
     }
 
+    pub fn max_table_size(&self) -> usize {
+        self.games().iter().map(|game| game.0.options().max_table_size).min().unwrap_or(0)
+    }
+
     pub fn first(&self) -> Type {
         self.games()[0]
     }
@@ -121,7 +146,7 @@ impl Mix {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub enum Variant {
     #[default]
     Holdem,
@@ -148,6 +173,24 @@ pub struct Options {
     default_limit: Limit,
 }
 
+impl Options {
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn max_table_size(&self) -> usize {
+        self.max_table_size
+    }
+
+    pub fn pocket_cards_size(&self) -> usize {
+        self.pocket_cards_size
+    }
+
+    pub fn is_hi_lo(&self) -> bool {
+        self.lo_ranking.is_some()
+    }
+}
+
 impl Name {
     pub fn options(&self) -> Options {
         match self {
@@ -310,13 +353,13 @@ pub enum AceRanking {
     Badugi,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Stake {
-    small_blind: u16,
-    big_blind: u16,
-    buy_in: (u16, u16),
-    ante: Option<u16>,
-    bring_in: Option<u16>,
+    small_blind: Chips,
+    big_blind: Chips,
+    buy_in: (Chips, Chips),
+    ante: Option<Chips>,
+    bring_in: Option<Chips>,
     currency: Option<Currency>,
 }
 
@@ -327,17 +370,18 @@ pub mod forced_bets {
     pub const BIG_BLIND: f32 = 1.0;
 }
 
+// buy-in limits in big blinds
 pub mod stacks {
-    pub const DEFAULT: (u16, u16) = (20, 100);
-    pub const SHORT_STACK: (u16, u16) = (20, 40);
-    pub const DEEP_STACK: (u16, u16) = (100, 250);
+    pub const DEFAULT: (u64, u64) = (20, 100);
+    pub const SHORT_STACK: (u64, u64) = (20, 40);
+    pub const DEEP_STACK: (u64, u64) = (100, 250);
 }
 
 impl Stake {
-    pub fn new(big_blind: u16) -> Self {
+    pub fn new(big_blind: Chips) -> Self {
         let mut new_stake = Self::default();
         new_stake.big_blind = big_blind;
-        new_stake.small_blind = new_stake.big_blind / 2;
+        new_stake.small_blind = new_stake.big_blind.split(2).0;
         new_stake.default_stack()
     }
 
@@ -345,39 +389,109 @@ impl Stake {
         self.currency.is_some()
     }
 
+    pub fn small_blind(&self) -> Chips {
+        self.small_blind
+    }
+
+    pub fn big_blind(&self) -> Chips {
+        self.big_blind
+    }
+
+    pub fn buy_in(&self) -> (Chips, Chips) {
+        self.buy_in
+    }
+
+    pub fn ante(&self) -> Option<Chips> {
+        self.ante
+    }
+
+    pub fn bring_in(&self) -> Option<Chips> {
+        self.bring_in
+    }
+
+    pub fn currency(&self) -> Option<Currency> {
+        self.currency
+    }
+
+    pub fn check_buy_in(&self, amount: Chips) -> Result<(), table::Error> {
+        let (min, max) = self.buy_in;
+        if amount < min {
+            return Err(table::Error::BuyInTooSmall { min });
+        }
+        if amount > max {
+            return Err(table::Error::BuyInTooLarge { max });
+        }
+        Ok(())
+    }
+
+    pub fn with_small_blind(mut self, small_blind: Chips) -> Self {
+        self.small_blind = small_blind;
+        self
+    }
+
+    pub fn with_ante_of(mut self, ante: Chips) -> Self {
+        self.ante = Some(ante);
+        self
+    }
+
+    pub fn with_buy_in(mut self, min: Chips, max: Chips) -> Self {
+        self.buy_in = (min, max);
+        self
+    }
+
+    pub fn with_currency(mut self, currency: Currency) -> Self {
+        self.currency = Some(currency);
+        self
+    }
+
     pub fn with_ante(mut self) -> Self {
-        self.ante = Some(self.big_blind / 10);
+        self.ante = Some(self.big_blind.split(10).0);
+        self
+    }
+
+    pub fn with_bring_in_of(mut self, bring_in: Chips) -> Self {
+        self.bring_in = Some(bring_in);
         self
     }
 
     pub fn with_bring_in(mut self) -> Self {
-        self.bring_in = Some(self.big_blind / 4);
+        self.bring_in = Some(self.big_blind.split(4).0);
         self
     }
 
+    fn big_blinds(&self, (min, max): (u64, u64)) -> (Chips, Chips) {
+        let times = |n: u64| self.big_blind.checked_mul(n).unwrap_or(Chips(u64::MAX));
+        (times(min), times(max))
+    }
+
     pub fn default_stack(mut self) -> Self {
-        self.buy_in = (stacks::DEFAULT.0 * self.big_blind, stacks::DEFAULT.1 * self.big_blind);
+        self.buy_in = self.big_blinds(stacks::DEFAULT);
         self
     }
 
     pub fn short_stack(mut self) -> Self {
-        self.buy_in = (stacks::SHORT_STACK.0 * self.big_blind, stacks::SHORT_STACK.1 * self.big_blind);
+        self.buy_in = self.big_blinds(stacks::SHORT_STACK);
         self
     }
 
     pub fn deep_stack(mut self) -> Self {
-        self.buy_in = (stacks::DEEP_STACK.0 * self.big_blind, stacks::DEEP_STACK.1 * self.big_blind);
+        self.buy_in = self.big_blinds(stacks::DEEP_STACK);
         self
     }
 }
 
 impl Display for Stake {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "${}/${}", self.small_blind, self.big_blind)
+        write!(f, "${}/${}", self.small_blind.format(self.currency), self.big_blind.format(self.currency))
     }
 }
 
 pub mod street {
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+    #[serde(rename_all = "snake_case")]
     pub enum Holdem {
         Preflop,
         Flop,
@@ -385,6 +499,25 @@ pub mod street {
         River,
     }
 
+    impl Holdem {
+        pub fn next(&self) -> Option<Self> {
+            match self {
+                Self::Preflop => Some(Self::Flop),
+                Self::Flop => Some(Self::Turn),
+                Self::Turn => Some(Self::River),
+                Self::River => None,
+            }
+        }
+
+        pub fn board_cards(&self) -> usize {
+            match self {
+                Self::Preflop => 0,
+                Self::Flop => 3,
+                Self::Turn | Self::River => 1,
+            }
+        }
+    }
+
     pub enum SevenCard {
         Second,
         Third,
@@ -413,6 +546,80 @@ pub mod street {
 
 type Log<T> = Vec<T>;
 
+pub const MAX_RUNS: u8 = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    NotEnoughPlayers,
+    HandInProgress,
+    NoHandInProgress,
+    NotYourTurn(u8),
+    Unsupported(Name),
+    Betting(betting::Error),
+    Table(table::Error),
+    Chips(chips::Error),
+    Events(events::Error),
+    Showdown(showdown::Error),
+    // a restored hand dealt or played out differently from its events
+    Diverged,
+}
+
+impl From<betting::Error> for Error {
+    fn from(error: betting::Error) -> Self {
+        Error::Betting(error)
+    }
+}
+
+impl From<table::Error> for Error {
+    fn from(error: table::Error) -> Self {
+        Error::Table(error)
+    }
+}
+
+impl From<chips::Error> for Error {
+    fn from(error: chips::Error) -> Self {
+        Error::Chips(error)
+    }
+}
+
+impl From<events::Error> for Error {
+    fn from(error: events::Error) -> Self {
+        Error::Events(error)
+    }
+}
+
+impl From<showdown::Error> for Error {
+    fn from(error: showdown::Error) -> Self {
+        Error::Showdown(error)
+    }
+}
+
+impl From<seat::Error> for Error {
+    fn from(error: seat::Error) -> Self {
+        Error::Table(table::Error::Seat(error))
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotEnoughPlayers => write!(f, "not enough players"),
+            Self::HandInProgress => write!(f, "hand is in progress"),
+            Self::NoHandInProgress => write!(f, "no hand in progress"),
+            Self::NotYourTurn(position) => write!(f, "not seat {} turn", position),
+            Self::Unsupported(name) => write!(f, "{} is not supported", name.human_readable_name()),
+            Self::Betting(error) => write!(f, "{}", error),
+            Self::Table(error) => write!(f, "{}", error),
+            Self::Chips(error) => write!(f, "{}", error),
+            Self::Events(error) => write!(f, "{}", error),
+            Self::Showdown(error) => write!(f, "{}", error),
+            Self::Diverged => write!(f, "the hand can't be dealt again as it was"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 #[derive(Debug)]
 pub struct Game<'a> {
     game: Name,
@@ -422,32 +629,82 @@ pub struct Game<'a> {
     table: Table<'a>,
     deck: Deck,
     actions: Log<Action<'a>>,
+    events: events::Log,
+    rake: Option<rake::Policy>,
+    street: Option<street::Holdem>,
+    board: Cards,
+    pockets: VecMap<Cards>,
+    round: Option<Round>,
+    rotation: Option<Rotation>,
+    clock: clock::Settings,
+    time: Box<dyn TimeSource>,
+    protection: Protection,
+    top_ups: Vec<(u8, Chips)>,
+    cash_outs: Vec<(u8, Chips)>,
+    name: String,
+    hand_id: u64,
+    history: Option<History>,
+    next_deal: Option<(VecMap<Cards>, Cards)>,
+    next_seed: Option<u64>,
+    rabbit_hunting: bool,
+    max_runs: u8,
+    insurance: Option<insurance::Policy>,
+    // the board as far as it was dealt when an all-in ended the betting
+    runout_from: Option<usize>,
+    rng: StdRng,
 }
 
-impl Game<'_> {
+impl<'a> Game<'a> {
     pub fn new(name: Name, limit: Limit, stake: Stake, table_size: u8) -> Self {
-        return Game {
+        Self::build(name, limit, stake, table_size, StdRng::from_entropy(), Box::new(clock::SystemClock::new()))
+    }
+
+    // never touches the system clock or the os for randomness, for targets like wasm that have neither
+    pub fn seeded(name: Name, limit: Limit, stake: Stake, table_size: u8, seed: u64, time: impl TimeSource + 'static) -> Self {
+        Self::build(name, limit, stake, table_size, StdRng::seed_from_u64(seed), Box::new(time))
+    }
+
+    fn build(name: Name, limit: Limit, stake: Stake, table_size: u8, rng: StdRng, time: Box<dyn TimeSource>) -> Self {
+        let table_size = table_size.min(name.options().max_table_size as u8);
+        Game {
             game: name,
             limit,
             mix: None,
             stake,
             table: Table::new(table_size),
-            deck: Deck::new(),
+            // every hand gets a fresh deck from the seed
+            deck: Deck::seeded(0),
             actions: vec![],
-        };
+            events: events::Log::new(),
+            rake: None,
+            street: None,
+            board: vec![],
+            pockets: VecMap::new(),
+            round: None,
+            rotation: None,
+            clock: clock::Settings::default(),
+            time,
+            protection: Protection::default(),
+            top_ups: vec![],
+            cash_outs: vec![],
+            name: String::from("pokernode"),
+            hand_id: 0,
+            history: None,
+            next_deal: None,
+            next_seed: None,
+            rabbit_hunting: false,
+            max_runs: 1,
+            insurance: None,
+            runout_from: None,
+            rng,
+        }
     }
 
     pub fn mix(mix: Mix, stake: Stake, table_size: u8) -> Self {
         let first: (Name, Limit) = mix.first();
-        return Game {
-            game: first.0,
-            limit: first.1,
-            mix: Some(mix),
-            stake,
-            table: Table::new(table_size),
-            deck: Deck::new(),
-            actions: vec![],
-        };
+        let mut game = Self::new(first.0, first.1, stake, table_size.min(mix.max_table_size() as u8));
+        game.mix = Some(mix);
+        game
     }
 
     pub fn next_game(&mut self) -> Option<Type> {
@@ -462,8 +719,1083 @@ impl Game<'_> {
         }
     }
 
-    pub fn start(&self) {
+    pub fn game(&self) -> Name {
+        self.game
+    }
+
+    pub fn limit(&self) -> Limit {
+        self.limit
+    }
+
+    pub fn stake(&self) -> &Stake {
+        &self.stake
+    }
+
+    pub fn table(&self) -> &Table<'a> {
+        &self.table
+    }
+
+    // where the button goes for the next hand, forgetting who posted blinds last
+    pub fn set_button(&mut self, position: u8) -> Result<(), Error> {
+        if self.is_hand_in_progress() {
+            return Err(Error::HandInProgress);
+        }
+        self.table.set_button(position)?;
+        Ok(())
+    }
+
+    // the next hand is dealt these cards, anything missing comes from a shuffled deck
+    pub fn deal_next(&mut self, pockets: VecMap<Cards>, board: Cards) {
+        self.next_deal = Some((pockets, board));
+    }
+
+    pub fn join(&mut self, player: Player<'a>, position: u8, amount: Chips) -> Result<(), Error> {
+        self.stake.check_buy_in(amount)?;
+        self.table.join(player.clone(), position, amount)?;
+        self.emit(Event::SeatTaken { position, player: player.name.to_string(), stack: amount })?;
+        self.seat_events()?;
+        self.reset_clock(position)?;
+        Ok(())
+    }
+
+    pub fn join_random(&mut self, player: Player<'a>, amount: Chips) -> Result<u8, Error> {
+        self.stake.check_buy_in(amount)?;
+        let position = self.table.join_random(player.clone(), amount)?;
+        self.emit(Event::SeatTaken { position, player: player.name.to_string(), stack: amount })?;
+        self.seat_events()?;
+        self.reset_clock(position)?;
+        Ok(position)
+    }
+
+    fn reset_clock(&mut self, position: u8) -> Result<(), table::Error> {
+        if let Some(clock) = self.table.seat_mut(position)?.clock_mut() {
+            *clock = Clock::new(&self.clock);
+        }
+        Ok(())
+    }
+
+    // a player leaving mid-hand folds first unless already all-in
+    pub fn leave(&mut self, position: u8) -> Result<Option<Chips>, Error> {
+        let can_act = self.table.seat(position).is_some_and(|seat| seat.can_act());
+        if self.is_hand_in_progress() && can_act {
+            self.apply(position as usize, Bet::Fold, false)?;
+        }
+        let stack = self.table.leave(position)?;
+        if stack.is_some() {
+            self.emit(Event::SeatLeft { position })?;
+        }
+        self.seat_events()?;
+        self.advance()?;
+        Ok(stack)
+    }
+
+    pub fn sit_out(&mut self, position: u8) -> Result<(), Error> {
+        self.table.sit_out(position)?;
+        self.seat_events()?;
+        Ok(())
+    }
+
+    pub fn come_back(&mut self, position: u8, post: bool) -> Result<(), Error> {
+        self.table.come_back(position, post, self.stake.small_blind(), self.stake.big_blind())?;
+        self.seat_events()?;
+        Ok(())
+    }
+
+    // rebuys and top-ups wait for the next hand and may not take the stack over the maximum buy-in
+    pub fn rebuy(&mut self, position: u8, amount: Chips) -> Result<(), Error> {
+        let (min, max) = self.stake.buy_in();
+        // chips in the pot still count until the hand is over
+        let in_pot = if self.is_hand_in_progress() { self.table.pot().contribution(position as usize) } else { Chips::ZERO };
+        self.table.chips()?.checked_add(amount)?;
+        let seat = self.table.seat_mut(position)?;
+        let stack = seat.stack().ok_or(table::Error::SeatEmpty(position))?.checked_add(seat.pending_rebuy())?.checked_add(in_pot)?;
+        if stack.is_zero() && amount < min {
+            return Err(table::Error::BuyInTooSmall { min }.into());
+        }
+        if stack.checked_add(amount)? > max {
+            return Err(table::Error::RebuyTooLarge { headroom: max.saturating_sub(stack) }.into());
+        }
+        self.request_rebuy(position, amount)
+    }
+
+    fn request_rebuy(&mut self, position: u8, amount: Chips) -> Result<(), Error> {
+        self.table.seat_mut(position)?.request_rebuy(amount);
+        self.emit(Event::RebuyRequested { position, amount })
+    }
+
+    pub fn set_auto_rebuy(&mut self, position: u8, auto_rebuy: Option<AutoRebuy>) -> Result<(), Error> {
+        self.table.seat_mut(position)?.set_auto_rebuy(auto_rebuy);
+        Ok(())
+    }
+
+    pub fn set_auto_muck(&mut self, position: u8, auto_muck: bool) -> Result<(), Error> {
+        self.table.seat_mut(position)?.set_auto_muck(auto_muck);
+        Ok(())
+    }
+
+    // how many times the player agrees to run the board after an all-in
+    pub fn set_run_it(&mut self, position: u8, times: u8) -> Result<(), Error> {
+        self.table.seat_mut(position)?.set_runs(times.clamp(1, MAX_RUNS));
+        Ok(())
+    }
+
+    pub fn set_insurance(&mut self, position: u8, insured: bool) -> Result<(), Error> {
+        self.table.seat_mut(position)?.set_insured(insured);
+        Ok(())
+    }
+
+    pub fn top_ups(&self) -> &[(u8, Chips)] {
+        &self.top_ups
+    }
+
+    fn apply_rebuys(&mut self) -> Result<(), Error> {
+        self.top_ups = vec![];
+        for position in 0..self.table.size() {
+            if let Ok(seat) = self.table.seat_mut(position) {
+                let amount = seat.apply_rebuy();
+                if !amount.is_zero() {
+                    self.top_ups.push((position, amount));
+                }
+            }
+        }
+        for (position, amount) in self.top_ups.clone() {
+            self.emit(Event::StackAdded { position, amount })?;
+        }
+        Ok(())
+    }
+
+    fn request_auto_rebuys(&mut self) -> Result<(), Error> {
+        let (_, max) = self.stake.buy_in();
+        for position in self.dealt() {
+            let amount = match self.table.seat(position as u8) {
+                Some(seat) => {
+                    let stack = seat.stack().unwrap_or_default() + seat.pending_rebuy();
+                    seat.auto_rebuy().filter(|auto_rebuy| stack < auto_rebuy.threshold).map_or(Chips::ZERO, |auto_rebuy| auto_rebuy.to.min(max).saturating_sub(stack))
+                },
+                None => continue,
+            };
+            if !amount.is_zero() {
+                self.request_rebuy(position as u8, amount)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn with_rake(mut self, policy: rake::Policy) -> Self {
+        self.rake = Some(policy);
+        self
+    }
+
+    pub fn take_rake(&mut self, players: u8, flop: bool) -> Option<&rake::Rake> {
+        let policy = self.rake.as_ref()?;
+        let variant = self.game.options().variant();
+        let rake = policy.calculate(self.table.pot(), &self.stake, &variant, players, flop);
+        self.table.pot_mut().take_rake(rake);
+        self.table.pot().rake()
+    }
+
+    pub fn with_rabbit_hunting(mut self) -> Self {
+        self.rabbit_hunting = true;
+        self
+    }
+
+    // the board is run as many times as every player left in an all-in agreed to, up to times
+    pub fn with_run_it(mut self, times: u8) -> Self {
+        self.max_runs = times.clamp(1, MAX_RUNS);
+        self
+    }
+
+    pub fn with_insurance(mut self, policy: insurance::Policy) -> Self {
+        self.insurance = Some(policy);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: clock::Settings) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_time_source(mut self, time: impl TimeSource + 'static) -> Self {
+        self.time = Box::new(time);
+        self
+    }
+
+    pub fn clock(&self) -> &clock::Settings {
+        &self.clock
+    }
+
+    // every hand's deck is shuffled from a seed drawn from this one, so a session can be dealt again
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // the hand in progress or the last one played
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    // what happened at the table as events, see events.rs
+    pub fn events(&self) -> &events::Log {
+        &self.events
+    }
+
+    pub fn with_snapshots_every(mut self, events: u64) -> Self {
+        self.events = self.events.with_snapshots_every(events);
+        self
+    }
+
+    // picks an empty table up from its events, see storage.rs. seats get their stacks, status,
+    // missed blinds and pending rebuys back and a hand in progress is dealt again from its seed
+    // with every action replayed. anything that comes out differently is Diverged, the button
+    // moves on from the last hand.
+    pub fn restore(mut self, log: events::Log) -> Result<Self, Error> {
+        if self.is_hand_in_progress() || self.table.seats().iter().any(|seat| !seat.is_empty()) {
+            return Err(Error::HandInProgress);
+        }
+        let state = log.state();
+        let hand = state.hand.as_ref();
+        for (&position, seat) in state.seats.iter() {
+            let dealt_with = hand.and_then(|hand| hand.stacks.iter().find(|&&(dealt, _)| dealt == position));
+            let stack = dealt_with.map_or(seat.stack, |&(_, stack)| stack);
+            // seats in the hand get where they are by playing it again
+            let status = if dealt_with.is_some() { seat::Status::Ready } else { seat.status };
+            self.table.rejoin(Player::blank_player(&seat.player), position, stack, status == seat::Status::WaitBigBlind)?;
+            self.reset_clock(position)?;
+            self.emit(Event::SeatTaken { position, player: seat.player.clone(), stack })?;
+            self.seat_events()?;
+            if dealt_with.is_none() && seat.missed_blinds != seat::MissedBlinds::default() {
+                let restored = self.table.seat_mut(position)?;
+                if seat.missed_blinds.small_blind {
+                    restored.miss_small_blind();
+                }
+                if seat.missed_blinds.big_blind {
+                    restored.miss_big_blind();
+                }
+                self.emit(Event::BlindsMissed { position, missed: seat.missed_blinds })?;
+            }
+            match status {
+                seat::Status::Ready | seat::Status::WaitBigBlind => {},
+                seat::Status::PostBigBlind => {
+                    self.table.sit_out(position)?;
+                    self.table.come_back(position, true, self.stake.small_blind(), self.stake.big_blind())?;
+                },
+                seat::Status::SittingOut => self.table.sit_out(position)?,
+                seat::Status::Away => self.table.go_away(position)?,
+                seat::Status::Idle => self.table.timeout(position)?,
+                _ => return Err(Error::Diverged),
+            }
+            self.seat_events()?;
+        }
+        self.hand_id = state.hand_id;
+        match hand {
+            Some(hand) => {
+                self.table.set_button(hand.button)?;
+                self.hand_id = hand.id - 1;
+                self.next_seed = Some(hand.seed);
+                self.start()?;
+                for &(position, bet) in hand.actions.iter().filter(|(_, bet)| !bet.is_forced()) {
+                    self.apply(position as usize, bet, false)?;
+                    self.advance()?;
+                }
+                if self.events.state().hand.as_ref() != Some(hand) {
+                    return Err(Error::Diverged);
+                }
+            },
+            None => if let Some(button) = state.button {
+                self.table.set_button((button + 1) % self.table.size())?;
+            },
+        }
+        for (&position, seat) in state.seats.iter() {
+            if seat.sit_out_next_hand {
+                self.sit_out(position)?;
+            }
+            if !seat.pending_rebuy.is_zero() {
+                self.request_rebuy(position, seat.pending_rebuy)?;
+            }
+        }
+        if self.events.state().seats != state.seats {
+            return Err(Error::Diverged);
+        }
+        self.events = log;
+        Ok(self)
+    }
+
+    // the game and its events can't disagree, a failing event stops whatever caused it
+    fn emit(&mut self, event: Event) -> Result<(), Error> {
+        self.events.push(event)?;
+        Ok(())
+    }
+
+    // whatever the table did to its seats, after the chips that went with it
+    fn seat_events(&mut self) -> Result<(), Error> {
+        for event in self.table.take_events() {
+            self.emit(Event::SeatChanged { position: event.position, transition: event.transition, from: event.from, to: event.to })?;
+        }
+        Ok(())
+    }
+
+    pub fn with_protection(mut self, protection: Protection) -> Self {
+        self.protection = protection;
+        self
+    }
+
+    // a player losing the connection in the middle of a decision gets the grace period on top
+    pub fn disconnect(&mut self, position: u8) -> Result<(), Error> {
+        let seat = self.table.seat_mut(position)?;
+        if seat.is_online() {
+            seat.disconnect();
+            if let Some(clock) = seat.clock_mut().filter(|clock| clock.is_running()) {
+                clock.extend(self.protection.grace);
+            }
+        }
+        Ok(())
+    }
+
+    // players moved away while offline get their seat back
+    pub fn reconnect(&mut self, position: u8) -> Result<(), Error> {
+        let seat = self.table.seat_mut(position)?;
+        let offline_hands = seat.reconnect();
+        if seat.status() == seat::Status::Away && self.protection.is_away(offline_hands) {
+            self.come_back(position, false)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_hand_in_progress(&self) -> bool {
+        self.street.is_some()
+    }
+
+    pub fn street(&self) -> Option<street::Holdem> {
+        self.street
+    }
+
+    pub fn board(&self) -> &Cards {
+        &self.board
+    }
+
+    pub fn pocket_cards(&self, position: u8) -> Option<&Cards> {
+        self.pockets.get(position as usize)
+    }
+
+    pub fn actions(&self) -> &Log<Action<'a>> {
+        &self.actions
+    }
+
+    pub fn take_cash_outs(&mut self) -> Vec<(u8, Chips)> {
+        std::mem::take(&mut self.cash_outs)
+    }
+
+    pub fn to_act(&self) -> Option<u8> {
+        self.round.as_ref()?.next().map(|position| position as u8)
+    }
+
+    pub fn range(&self, position: u8) -> Option<Range> {
+        let round = self.round.as_ref()?;
+        let seat = self.table.seat(position)?;
+        Some(round.range(position as usize, seat.put(), seat.stack()?, self.table.pot().total()))
+    }
+
+    // turns up some or all of a pocket once the hand is over: one card, a hand mucked at showdown or a folded one
+    pub fn show(&mut self, position: u8, cards: Cards) -> Result<(), Error> {
+        if self.is_hand_in_progress() {
+            return Err(Error::HandInProgress);
+        }
+        let history = self.history.as_mut().ok_or(showdown::Error::NotDealtIn(position))?;
+        // a player who took the seat since can't show what was dealt to it
+        let player = self.table.seat(position).and_then(|seat| seat.player()).map(|player| player.name.to_string());
+        let pocket = history.pocket(position).filter(|_| player.as_deref() == history.player(position)).ok_or(showdown::Error::NotDealtIn(position))?;
+        if cards.is_empty() || !cards.iter().all(|card| pocket.contains(card)) {
+            return Err(showdown::Error::NotInPocket.into());
+        }
+
+        let omaha = self.game.options().pocket_cards_size() == 4;
+        let before = history.shown.iter().position(|shown| shown.position == position);
+        let cards: Cards = pocket.iter()
+            .filter(|card| cards.contains(card) || before.is_some_and(|index| history.shown[index].cards.contains(card)))
+            .copied()
+            .collect();
+        let description = if cards.len() == pocket.len() {
+            hand::best_high(&cards, &history.board, omaha).map_or(String::new(), |hand| hand.description())
+        } else {
+            String::new()
+        };
+        let shown = history::Shown { position, cards, description };
+        match before {
+            Some(index) => history.shown[index] = shown,
+            None => history.shown.push(shown),
+        }
+        Ok(())
+    }
+
+    fn bet_size(&self, street: street::Holdem) -> Chips {
+        let big_blind = self.stake.big_blind();
+        match (self.limit, street) {
+            (Limit::FixedLimit, street::Holdem::Turn | street::Holdem::River) => big_blind + big_blind,
+            _ => big_blind,
+        }
+    }
+
+    fn dealt(&self) -> Vec<usize> {
+        self.rotation.as_ref().map_or(vec![], |rotation| rotation.dealt.clone())
+    }
+
+    fn in_hand(&self) -> Vec<usize> {
+        self.dealt().into_iter().filter(|&position| !self.table.pot().is_folded(position)).collect()
+    }
+
+    fn able(&self) -> Vec<usize> {
+        self.dealt().into_iter()
+            .filter(|&position| self.table.seat(position as u8).is_some_and(|seat| seat.can_act()))
+            .collect()
+    }
+
+    pub fn start(&mut self) -> Result<(), Error> {
+        if self.is_hand_in_progress() {
+            return Err(Error::HandInProgress);
+        }
+        let options = self.game.options();
+        if options.variant() != Variant::Holdem {
+            return Err(Error::Unsupported(self.game));
+        }
+        self.apply_rebuys()?;
+        let rotation = self.table.move_button().ok_or(Error::NotEnoughPlayers)?;
+
+        let seed = self.next_seed.take().unwrap_or_else(|| self.rng.gen());
+        self.deck = match self.next_deal.take() {
+            Some((pockets, board)) => Deck::arranged(&deal_order(&rotation.dealt, &pockets, &board, options.pocket_cards_size()), seed),
+            None => Deck::seeded(seed),
+        };
+        self.board = vec![];
+        self.runout_from = None;
+        self.pockets = VecMap::new();
+        self.actions = vec![];
+        self.table.pot_mut().clear();
+
+        for &position in rotation.dealt.iter() {
+            self.table.start_hand(position as u8)?;
+        }
+
+        self.hand_id += 1;
+        self.emit(Event::HandStarted {
+            id: self.hand_id,
+            seed,
+            button: rotation.blinds.button as u8,
+            dealt: rotation.dealt.iter().map(|&position| position as u8).collect(),
+        })?;
+        self.seat_events()?;
+        let missed: BTreeSet<usize> = rotation.missed_small_blind.iter().chain(rotation.missed_big_blind.iter()).copied().collect();
+        for position in missed {
+            if let Some(seat) = self.table.seat(position as u8) {
+                self.emit(Event::BlindsMissed { position: position as u8, missed: seat.missed_blinds() })?;
+            }
+        }
+        let seats = rotation.dealt.iter()
+            .filter_map(|&position| {
+                let seat = self.table.seat(position as u8)?;
+                Some(history::Seat { position: position as u8, player: seat.player()?.name.to_string(), stack: seat.stack()? })
+            })
+            .collect();
+        self.history = Some(History {
+            id: self.hand_id,
+            seed: Some(seed),
+            time: self.time.wall(),
+            game: self.game,
+            limit: self.limit,
+            stake: self.stake.clone(),
+            table: self.name.clone(),
+            table_size: self.table.size(),
+            button: rotation.blinds.button as u8,
+            small_blind: (!rotation.blinds.dead_small_blind).then_some(rotation.blinds.small_blind as u8),
+            big_blind: rotation.blinds.big_blind as u8,
+            seats,
+            top_ups: self.top_ups.clone(),
+            posts: vec![],
+            pockets: vec![],
+            streets: vec![],
+            board: vec![],
+            uncalled: None,
+            shown: vec![],
+            rabbit: vec![],
+            runs: vec![],
+            insurance: vec![],
+            side_pots: vec![],
+            awards: vec![],
+            total: Chips::ZERO,
+            rake: Chips::ZERO,
+        });
+
+        if let Some(ante) = self.stake.ante() {
+            for &position in rotation.dealt.iter() {
+                self.post(position, Bet::Ante(ante))?;
+            }
+        }
+        let blinds = rotation.blinds;
+        let big_blind = self.stake.big_blind();
+        if !blinds.dead_small_blind {
+            self.post(blinds.small_blind, Bet::SmallBlind(self.stake.small_blind()))?;
+        }
+        self.post(blinds.big_blind, Bet::BigBlind(big_blind))?;
+        for &(position, amount) in rotation.posts.iter() {
+            let live = amount.min(big_blind);
+            self.post(position, Bet::GuestBlind(live))?;
+            if amount > live {
+                self.post_dead(position, Bet::SmallBlind(amount - live))?;
+            }
+        }
+
+        for &position in rotation.dealt.iter() {
+            let cards = self.deck.deal(options.pocket_cards_size());
+            self.emit(Event::CardsDealt { position: Some(position as u8), cards: cards.clone() })?;
+            if let Some(history) = self.history.as_mut() {
+                history.pockets.push((position as u8, cards.clone()));
+            }
+            self.pockets.insert(position, cards);
+        }
+
+        self.rotation = Some(rotation);
+        self.start_street(street::Holdem::Preflop)?;
+        self.advance()?;
+        Ok(())
+    }
+
+    fn post(&mut self, position: usize, bet: Bet) -> Result<(), Error> {
+        let seat = self.table.seat_mut(position as u8)?;
+        let amount = seat.bet(bet)?;
+        self.record(position, bet, amount, false)?;
+        Ok(())
+    }
+
+    fn post_dead(&mut self, position: usize, bet: Bet) -> Result<(), Error> {
+        let seat = self.table.seat_mut(position as u8)?;
+        let amount = seat.bet(Bet::Ante(match bet {
+            Bet::SmallBlind(amount) => amount,
+            _ => Chips::ZERO,
+        }))?;
+        self.record(position, bet, amount, false)?;
+        if let Some(entry) = self.history.as_mut().and_then(|history| history.posts.last_mut()) {
+            entry.dead = true;
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, position: usize, bet: Bet, amount: Chips, timed_out: bool) -> Result<(), Error> {
+        self.table.pot_mut().add(position, amount)?;
+        self.emit(Event::BetPlaced { position: position as u8, bet, amount })?;
+        let now = self.time.now();
+        let seat = match self.table.seat_mut(position as u8) {
+            Ok(seat) => seat,
+            Err(_) => return Ok(()),
+        };
+        let time_used = seat.clock_mut()
+            .filter(|clock| clock.is_running())
+            .map_or(time::Duration::ZERO, |clock| clock.stop(now));
+        let all_in = seat.is_all_in();
+        if let Some(player) = seat.player() {
+            let action = Action::new(player.clone(), bet, self.time.wall())
+                .with_all_in(all_in)
+                .with_timed_out(timed_out)
+                .with_time_used(time_used);
+            if let Some(history) = self.history.as_mut() {
+                let entry = history::Entry { position: position as u8, bet, all_in, timed_out, dead: false, time: action.time(), time_used };
+                match history.streets.last_mut() {
+                    Some(street) => street.entries.push(entry),
+                    None => history.posts.push(entry),
+                }
+            }
+            self.actions.push(action);
+        }
+        Ok(())
+    }
+
+    fn start_street(&mut self, street: street::Holdem) -> Result<(), Error> {
+        let rotation = match &self.rotation {
+            Some(rotation) => rotation,
+            None => return Ok(()),
+        };
+        let (call, order) = match street {
+            street::Holdem::Preflop => {
+                let call = rotation.dealt.iter()
+                    .filter_map(|&position| self.table.seat(position as u8).map(|seat| seat.put()))
+                    .max()
+                    .unwrap_or_default()
+                    .max(self.stake.big_blind());
+                (call, rotation.preflop_order())
+            },
+            _ => (Chips::ZERO, rotation.postflop_order()),
+        };
+
+        self.emit(Event::StreetChanged { street })?;
+        if street != street::Holdem::Preflop {
+            for position in self.dealt() {
+                if let Ok(seat) = self.table.seat_mut(position as u8) {
+                    seat.new_street();
+                    if seat.pre_action().is_some_and(|auto_play| auto_play != AutoPlay::FoldAnyBet) {
+                        let _ = seat.cancel_pre_action();
+                    }
+                }
+            }
+            self.deck.burn(1);
+            let cards = self.deck.deal(street.board_cards());
+            self.board.extend(cards.iter().copied());
+            self.emit(Event::CardsDealt { position: None, cards: cards.clone() })?;
+            if let Some(history) = self.history.as_mut() {
+                history.streets.push(history::Street { street, cards, entries: vec![] });
+            }
+        } else if let Some(history) = self.history.as_mut() {
+            history.streets.push(history::Street { street, cards: vec![], entries: vec![] });
+        }
+
+        // nobody bets when at most one player has chips behind, unless they still have to call
+        let able = self.able();
+        let pending = able.iter().copied()
+            .filter(|&position| able.len() >= 2 || self.table.seat(position as u8).is_some_and(|seat| seat.put() < call))
+            .collect();
+        self.round = Some(Round::new(self.limit, self.bet_size(street), call, order, pending));
+        self.street = Some(street);
+        Ok(())
+    }
+
+    pub fn act(&mut self, position: u8, bet: Bet) -> Result<(), Error> {
+        let next = self.round.as_ref().ok_or(Error::NoHandInProgress)?.next();
+        if next != Some(position as usize) {
+            return Err(Error::NotYourTurn(position));
+        }
+        let range = self.range(position).ok_or(Error::NotYourTurn(position))?;
+        range.validate(&bet)?;
+        self.apply(position as usize, bet, false)?;
+        self.advance()?;
+        Ok(())
+    }
+
+    fn apply(&mut self, position: usize, bet: Bet, timed_out: bool) -> Result<(), Error> {
+        let seat = self.table.seat_mut(position as u8)?;
+        let amount = seat.bet(bet)?;
+        let put = seat.put();
+        self.record(position, bet, amount, timed_out)?;
+
+        let able = self.able();
+        if let Some(round) = self.round.as_mut() {
+            match bet {
+                Bet::Fold => round.folded(position),
+                Bet::Raise(_) => round.raised(position, put, &able),
+                _ => round.called(position),
+            }
+        }
+        match bet {
+            Bet::Fold => self.table.pot_mut().fold(position),
+            Bet::Raise(_) => self.cancel_pre_actions(),
+            _ => {},
+        }
+        Ok(())
+    }
+
+    pub fn pre_act(&mut self, position: u8, auto_play: AutoPlay) -> Result<(), Error> {
+        if !self.is_hand_in_progress() {
+            return Err(Error::NoHandInProgress);
+        }
+        self.table.seat_mut(position)?.queue(auto_play)?;
+        if self.to_act() == Some(position) {
+            self.advance()?;
+        }
+        Ok(())
+    }
+
+    pub fn cancel_pre_act(&mut self, position: u8) -> Result<(), Error> {
+        self.table.seat_mut(position)?.cancel_pre_action()?;
+        Ok(())
+    }
+
+    // pre-actions that stopped making sense after a raise are dropped
+    fn cancel_pre_actions(&mut self) {
+        for position in self.able() {
+            let auto_play = self.table.seat(position as u8).and_then(|seat| seat.pre_action());
+            let range = self.range(position as u8);
+            if let (Some(auto_play), Some(range)) = (auto_play, range) {
+                if betting::resolve(&auto_play, &range).is_none() {
+                    if let Ok(seat) = self.table.seat_mut(position as u8) {
+                        let _ = seat.cancel_pre_action();
+                    }
+                }
+            }
+        }
+    }
+
+    fn advance(&mut self) -> Result<(), Error> {
+        loop {
+            if !self.is_hand_in_progress() {
+                return Ok(());
+            }
+            if self.in_hand().len() <= 1 {
+                return self.finish();
+            }
+            let next = self.round.as_ref().and_then(|round| round.next());
+            let position = match next {
+                Some(position) => position,
+                None => match self.street.and_then(|street| street.next()) {
+                    Some(street) => {
+                        if self.runout_from.is_none() && self.able().len() < 2 {
+                            self.runout_from = Some(self.board.len());
+                        }
+                        self.start_street(street)?;
+                        continue;
+                    },
+                    None => return self.finish(),
+                },
+            };
+
+            let auto_play = self.table.seat(position as u8).and_then(|seat| seat.pre_action());
+            if let (Some(auto_play), Some(range)) = (auto_play, self.range(position as u8)) {
+                match betting::resolve(&auto_play, &range) {
+                    Some(bet) => {
+                        if self.apply(position, bet, false).is_ok() {
+                            if auto_play == AutoPlay::FoldAnyBet && bet != Bet::Fold {
+                                if let Ok(seat) = self.table.seat_mut(position as u8) {
+                                    let _ = seat.queue(auto_play);
+                                }
+                            }
+                            continue;
+                        }
+                    },
+                    None => {
+                        if let Ok(seat) = self.table.seat_mut(position as u8) {
+                            let _ = seat.cancel_pre_action();
+                        }
+                    },
+                }
+            }
+
+            let now = self.time.now();
+            if let Ok(seat) = self.table.seat_mut(position as u8) {
+                let online = seat.is_online();
+                if let Some(clock) = seat.clock_mut() {
+                    clock.start(now, &self.clock);
+                    if !online {
+                        clock.extend(self.protection.grace);
+                    }
+                }
+            }
+            return Ok(());
+        }
+    }
+
+    // checks the clock of the player to act, timing them out once both action time and time bank are gone
+    pub fn poll(&mut self) -> Result<Option<(u8, Alert)>, Error> {
+        let position = self.to_act().ok_or(Error::NoHandInProgress)?;
+        let now = self.time.now();
+        let alert = self.table.seat_mut(position)?.clock_mut().and_then(|clock| clock.poll(now, &self.clock));
+        if alert == Some(Alert::Expired) {
+            self.timeout()?;
+        }
+        Ok(alert.map(|alert| (position, alert)))
+    }
+
+    // out of time: the seat's auto play decides, or check when possible and fold otherwise
+    pub fn timeout(&mut self) -> Result<(), Error> {
+        let position = self.to_act().ok_or(Error::NoHandInProgress)?;
+        let range = self.range(position).ok_or(Error::NoHandInProgress)?;
+        let seat = self.table.seat(position).ok_or(Error::NoHandInProgress)?;
+        let online = seat.is_online();
+
+        if !online && !range.can_check() && seat.all_in_protections() < self.protection.all_in_protections {
+            self.table.protect(position)?;
+            self.seat_events()?;
+            if let Some(round) = self.round.as_mut() {
+                round.folded(position as usize);
+            }
+            self.advance()?;
+            return Ok(());
+        }
+
+        let auto_play = match self.protection.auto_play {
+            Some(auto_play) if !online => auto_play,
+            _ => seat.auto_play().unwrap_or(AutoPlay::CheckFold),
+        };
+        let bet = betting::resolve(&auto_play, &range)
+            .unwrap_or(if range.can_check() { Bet::Check } else { Bet::Fold });
+        self.apply(position as usize, bet, true)?;
+        self.advance()?;
+        Ok(())
+    }
+
+    // sorted so odd chips go to the first winner left of the button
+    fn winners(&self, eligible: &[usize], board: &Cards) -> (Vec<usize>, Vec<usize>) {
+        let options = self.game.options();
+        let omaha = options.pocket_cards_size() == 4;
+        let empty = vec![];
+        let pocket = |position: usize| self.pockets.get(position).unwrap_or(&empty);
+
+        let mut high: Vec<(usize, Hand)> = eligible.iter()
+            .filter_map(|&position| hand::best_high(pocket(position), board, omaha).map(|hand| (position, hand)))
+            .collect();
+        let best = high.iter().map(|(_, hand)| hand).cloned().reduce(|best, hand| if hand > best { hand } else { best });
+        high.retain(|(_, hand)| Some(hand) == best.as_ref());
+
+        let mut low: Vec<(usize, Hand)> = if options.is_hi_lo() {
+            eligible.iter()
+                .filter_map(|&position| hand::best_low8(pocket(position), board, omaha).map(|hand| (position, hand)))
+                .collect()
+        } else {
+            vec![]
+        };
+        let best = low.iter().map(|(_, hand)| hand).cloned().reduce(|best, hand| if hand > best { hand } else { best });
+        low.retain(|(_, hand)| Some(hand) == best.as_ref());
+
+        let size = self.table.size() as usize;
+        let button = self.table.button() as usize;
+        let order = |position: &usize| (position + size - button - 1) % size;
+        let mut high: Vec<usize> = high.into_iter().map(|(position, _)| position).collect();
+        let mut low: Vec<usize> = low.into_iter().map(|(position, _)| position).collect();
+        high.sort_by_key(order);
+        low.sort_by_key(order);
+        (high, low)
+    }
+
+    // the hands turned up at the end in showing order; seats that auto-muck throw away hands
+    // that can't win anything, unless the betting ended all-in and every hand goes face up
+    fn showdown(&self, side_pots: &[SidePot]) -> Vec<history::Shown> {
+        let in_hand = self.in_hand();
+        if in_hand.len() < 2 {
+            return vec![];
+        }
+        let options = self.game.options();
+        let omaha = options.pocket_cards_size() == 4;
+        let tabled = self.able().len() < 2;
+        let aggressor = self.history.as_ref().and_then(|history| history.last_aggressor()).map(usize::from);
+        let order = showdown::order(&in_hand, aggressor, self.table.button() as usize, self.table.size() as usize);
+
+        let mut contest = showdown::Contest::new(side_pots);
+        let mut shown = vec![];
+        for position in order {
+            let cards = match self.pockets.get(position) {
+                Some(cards) => cards,
+                None => continue,
+            };
+            let high = hand::best_high(cards, &self.board, omaha);
+            let low = if options.is_hi_lo() { hand::best_low8(cards, &self.board, omaha) } else { None };
+            let auto_muck = self.table.seat(position as u8).is_some_and(|seat| seat.auto_muck());
+            if auto_muck && !tabled && !contest.contends(position, high.as_ref(), low.as_ref()) {
+                continue;
+            }
+            contest.show(position, high.as_ref(), low.as_ref());
+            let description = high.map_or(String::new(), |hand| hand.description());
+            shown.push(history::Shown { position: position as u8, cards: cards.clone(), description });
+        }
+        shown
+    }
+
+    // the board once, or once for every run the players left in an all-in agreed to,
+    // each dealt on from where the betting ended with the same deck
+    fn runouts(&mut self) -> Vec<Cards> {
+        let mut boards = vec![self.board.clone()];
+        let from = match self.runout_from {
+            Some(from) => from,
+            None => return boards,
+        };
+        let runs = self.in_hand().into_iter()
+            .map(|position| self.table.seat(position as u8).map_or(1, |seat| seat.runs()))
+            .min()
+            .unwrap_or(1)
+            .min(self.max_runs);
+        for _ in 1..runs {
+            let mut board = self.board[..from].to_vec();
+            let mut dealt = 0;
+            let mut street = street::Holdem::Preflop.next();
+            while let Some(current) = street {
+                if dealt >= from {
+                    self.deck.burn(1);
+                    board.extend(self.deck.deal(current.board_cards()));
+                }
+                dealt += current.board_cards();
+                street = current.next();
+            }
+            boards.push(board);
+        }
+        boards
+    }
+
+    // covers the favourite of a heads-up all-in who asked for insurance at the odds when the
+    // money went in, and settles it against what they won
+    fn insure(&mut self, side_pots: &[SidePot]) -> Result<Vec<insurance::Cover>, Error> {
+        let (policy, from) = match (self.insurance, self.runout_from) {
+            (Some(policy), Some(from)) => (policy, from),
+            _ => return Ok(vec![]),
+        };
+        let options = self.game.options();
+        let in_hand = self.in_hand();
+        if in_hand.len() != 2 || options.is_hi_lo() {
+            return Ok(vec![]);
+        }
+        let pockets = in_hand.iter().map(|&position| self.pockets.get(position).cloned().unwrap_or_default()).collect();
+        let seed = self.history.as_ref().and_then(|history| history.seed).unwrap_or(self.hand_id);
+        let mut calculator = Calculator::new(pockets).with_board(self.board[..from].to_vec()).with_seed(seed);
+        if options.pocket_cards_size() == 4 {
+            calculator = calculator.with_omaha();
+        }
+        let outcome = match calculator.calculate() {
+            Ok(outcome) => outcome,
+            Err(_) => return Ok(vec![]),
+        };
+
+        let mut covers = vec![];
+        for (index, &position) in in_hand.iter().enumerate() {
+            if !self.table.seat(position as u8).is_some_and(|seat| seat.is_insured()) {
+                continue;
+            }
+            let contested: Vec<usize> = side_pots.iter().enumerate()
+                .filter(|(_, side_pot)| side_pot.eligible.len() > 1 && side_pot.eligible.contains(&position))
+                .map(|(index, _)| index)
+                .collect();
+            let covered = contested.iter().map(|&index| side_pots[index].amount).sum();
+            let mut cover = match policy.quote(position as u8, outcome.equities[index].equity, covered) {
+                Some(cover) => cover,
+                None => continue,
+            };
+            let won = self.table.pot().awards().iter()
+                .filter(|award| award.position == position && contested.contains(&award.side_pot))
+                .map(|award| award.amount)
+                .sum();
+            cover.settle(won);
+            if let Ok(seat) = self.table.seat_mut(position as u8) {
+                if cover.payout > cover.premium {
+                    seat.win(cover.payout - cover.premium)?;
+                } else {
+                    // no more than the stack, the premium on record is what was collected
+                    let paid = seat.pay(cover.premium - cover.payout);
+                    cover.premium = cover.payout + paid;
+                }
+            }
+            self.emit(Event::InsuranceSettled { position: position as u8, premium: cover.premium, payout: cover.payout })?;
+            covers.push(cover);
+        }
+        Ok(covers)
+    }
+
+    // deals the rest of the board as it would have come for a hand that was over before the river
+    fn rabbit_hunt(&mut self) {
+        let history = match self.history.as_mut() {
+            Some(history) => history,
+            None => return,
+        };
+        let mut street = history.streets.last().and_then(|street| street.street.next());
+        while let Some(current) = street {
+            self.deck.burn(1);
+            history.rabbit.extend(self.deck.deal(current.board_cards()));
+            street = current.next();
+        }
+    }
 
+    fn split(&mut self, amount: Chips, winners: &[usize], side_pot: usize) -> Result<(), Error> {
+        if winners.is_empty() {
+            return Ok(());
+        }
+        let (share, odd) = amount.split(winners.len() as u64);
+        for (index, &position) in winners.iter().enumerate() {
+            let won = share + if (index as u64) < odd.amount() { Chips(1) } else { Chips::ZERO };
+            if !won.is_zero() {
+                if let Ok(seat) = self.table.seat_mut(position as u8) {
+                    seat.win(won)?;
+                }
+                self.table.pot_mut().award(position, won, side_pot);
+                self.emit(Event::PotAwarded { position: position as u8, amount: won, side_pot })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        let uncalled = self.table.pot_mut().return_uncalled();
+        if let Some((position, amount)) = uncalled {
+            if let Ok(seat) = self.table.seat_mut(position as u8) {
+                seat.win(amount)?;
+            }
+            self.emit(Event::UncalledReturned { position: position as u8, amount })?;
+        }
+
+        let players = self.dealt().len() as u8;
+        let flop = self.board.len() >= 3;
+        if let Some(rake) = self.take_rake(players, flop).map(|rake| rake.amount).filter(|amount| !amount.is_zero()) {
+            self.emit(Event::RakeTaken { amount: rake })?;
+        }
+
+        let side_pots = self.table.pot().net_side_pots();
+        let shown = self.showdown(&side_pots);
+        let boards = self.runouts();
+        for (index, side_pot) in side_pots.iter().enumerate() {
+            // every run gets its share of every pot, odd chips to the first runs
+            let (share, odd) = side_pot.amount.split(boards.len() as u64);
+            for (run, board) in boards.iter().enumerate() {
+                let amount = share + if (run as u64) < odd.amount() { Chips(1) } else { Chips::ZERO };
+                let (high, low) = if side_pot.eligible.len() == 1 {
+                    (side_pot.eligible.clone(), vec![])
+                } else {
+                    self.winners(&side_pot.eligible, board)
+                };
+                if low.is_empty() {
+                    self.split(amount, &high, index)?;
+                } else {
+                    let half = amount.split(2).0;
+                    self.split(amount - half, &high, index)?;
+                    self.split(half, &low, index)?;
+                }
+            }
+        }
+        let insurance = self.insure(&side_pots)?;
+
+        if let Some(history) = self.history.as_mut() {
+            let pot = self.table.pot();
+            history.board = self.board.clone();
+            history.uncalled = uncalled.map(|(position, amount)| (position as u8, amount));
+            history.shown = shown;
+            if boards.len() > 1 {
+                history.runs = boards;
+            }
+            history.insurance = insurance;
+            history.side_pots = side_pots;
+            history.awards = pot.awards().clone();
+            history.total = pot.total();
+            history.rake = pot.rake().map_or(Chips::ZERO, |rake| rake.amount);
+        }
+        if self.rabbit_hunting {
+            self.rabbit_hunt();
+        }
+
+        for position in self.dealt() {
+            if let Some(clock) = self.table.seat_mut(position as u8).ok().and_then(|seat| seat.clock_mut()) {
+                clock.finish_hand(&self.clock);
+            }
+        }
+
+        self.request_auto_rebuys()?;
+        self.round = None;
+        self.street = None;
+        self.emit(Event::HandFinished)?;
+        let cash_outs = self.table.finish_hand();
+        for &(position, _) in cash_outs.iter() {
+            self.emit(Event::SeatLeft { position })?;
+        }
+        self.cash_outs.extend(cash_outs);
+
+        for position in self.dealt() {
+            let seat = match self.table.seat_mut(position as u8) {
+                Ok(seat) if !seat.is_empty() && !seat.is_online() => seat,
+                _ => continue,
+            };
+            if self.protection.is_away(seat.miss_hand_offline()) {
+                let _ = self.table.go_away(position as u8);
+            }
+        }
+        self.seat_events()?;
+        Ok(())
     }
 }
 
@@ -479,14 +1811,32 @@ impl Display for Game<'_> {
     }
 }
 
+// the order start and start_street draw from the deck
+fn deal_order(dealt: &[usize], pockets: &VecMap<Cards>, board: &Cards, pocket_cards_size: usize) -> Vec<Option<Card>> {
+    let mut order = vec![];
+    for &position in dealt {
+        let pocket = pockets.get(position);
+        order.extend((0..pocket_cards_size).map(|i| pocket.and_then(|cards| cards.get(i).copied())));
+    }
+    let mut board = board.iter().copied();
+    let mut street = street::Holdem::Preflop.next();
+    while let Some(current) = street {
+        order.push(None);
+        order.extend((0..current.board_cards()).map(|_| board.next()));
+        street = current.next();
+    }
+    order
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::player::Player;
+    use crate::poker::card;
 
     #[test]
     pub fn next_game() {
-        let stake = Stake::new(10);
+        let stake = Stake::new(Chips(10));
         let mix = Mix::EightGame;
         let mut game = Game::mix(mix, stake, 2);
         for i in 1..100 {
@@ -496,14 +1846,389 @@ mod tests {
 
     #[test]
     pub fn start_game() {
-        let stake = Stake::new(10);
-        let game = Game::new(Name::Texas, Limit::NoLimit, stake, 2);
+        let stake = Stake::new(Chips(10));
+        let mut game = Game::new(Name::Texas, Limit::NoLimit, stake, 2);
         let player_1 = Player::blank_player("player-1");
         let player_2 = Player::blank_player("player-2");
-        game.table.join(player_1, 0, 1000);
-        game.table.join(player_2, 1, 1000);
-        game.start();
+        game.join(player_1, 0, Chips(1000)).unwrap();
+        game.join(player_2, 1, Chips(1000)).unwrap();
+        game.start().unwrap();
 
         println!("{}", game);
     }
+
+    #[test]
+    pub fn buy_in_limits() {
+        let stake = Stake::new(Chips(10));
+        let mut game = Game::new(Name::Texas, Limit::NoLimit, stake, 2);
+        assert_eq!(game.join(Player::blank_player("player-1"), 0, Chips(100)), Err(Error::Table(table::Error::BuyInTooSmall { min: Chips(200) })));
+        assert_eq!(game.join(Player::blank_player("player-1"), 0, Chips(1500)), Err(Error::Table(table::Error::BuyInTooLarge { max: Chips(1000) })));
+        assert_eq!(game.join(Player::blank_player("player-1"), 0, Chips(200)), Ok(()));
+    }
+
+    #[test]
+    pub fn max_table_size() {
+        let game = Game::new(Name::Badugi, Limit::FixedLimit, Stake::new(Chips(10)), 9);
+        assert_eq!(game.table().size(), 6);
+        let game = Game::mix(Mix::Horse, Stake::new(Chips(10)), 10);
+        assert_eq!(game.table().size(), 8);
+    }
+
+    fn heads_up<'a>() -> Game<'a> {
+        let mut game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 2);
+        game.join(Player::blank_player("player-1"), 0, Chips(1000)).unwrap();
+        game.join(Player::blank_player("player-2"), 1, Chips(1000)).unwrap();
+        game.start().unwrap();
+        game
+    }
+
+    fn stack(game: &Game, position: u8) -> Chips {
+        game.table().seat(position).and_then(|seat| seat.stack()).unwrap()
+    }
+
+    #[test]
+    pub fn fold_to_big_blind() {
+        let mut game = heads_up();
+        // heads-up the button posts the small blind and acts first
+        assert_eq!(game.to_act(), Some(0));
+        assert_eq!(game.act(1, Bet::Check), Err(Error::NotYourTurn(1)));
+        assert_eq!(game.act(0, Bet::Check), Err(Error::Betting(betting::Error::CannotCheck)));
+        game.act(0, Bet::Fold).unwrap();
+
+        assert!(!game.is_hand_in_progress());
+        assert_eq!(stack(&game, 0), Chips(995));
+        assert_eq!(stack(&game, 1), Chips(1005));
+    }
+
+    #[test]
+    pub fn showdown() {
+        let mut game = heads_up();
+        game.act(0, Bet::Call(Chips(5))).unwrap();
+        game.act(1, Bet::Check).unwrap();
+        assert_eq!(game.street(), Some(street::Holdem::Flop));
+        assert_eq!(game.board().len(), 3);
+        for _ in 0..2 {
+            game.act(1, Bet::Check).unwrap();
+            game.act(0, Bet::Check).unwrap();
+        }
+        assert_eq!(game.street(), Some(street::Holdem::River));
+
+        game.pockets.insert(0, card::parse("AsAd"));
+        game.pockets.insert(1, card::parse("KsKd"));
+        game.board = card::parse("2c7h9dJsQc");
+        game.act(1, Bet::Raise(Chips(10))).unwrap();
+        game.act(0, Bet::Call(Chips(10))).unwrap();
+
+        assert!(!game.is_hand_in_progress());
+        assert_eq!(stack(&game, 0), Chips(1020));
+        assert_eq!(stack(&game, 1), Chips(980));
+        assert_eq!(game.table().pot().awards().len(), 1);
+    }
+
+    // deals the given pockets and board for the river
+    fn to_river(game: &mut Game, pockets: [&str; 2], board: &str) {
+        game.act(0, Bet::Call(Chips(5))).unwrap();
+        game.act(1, Bet::Check).unwrap();
+        for _ in 0..2 {
+            game.act(1, Bet::Check).unwrap();
+            game.act(0, Bet::Check).unwrap();
+        }
+        for (position, pocket) in pockets.iter().enumerate() {
+            game.pockets.insert(position, card::parse(pocket));
+            let history = game.history.as_mut().unwrap();
+            history.pockets.iter_mut().find(|(seat, _)| *seat as usize == position).unwrap().1 = card::parse(pocket);
+        }
+        game.board = card::parse(board);
+    }
+
+    fn shown(game: &Game) -> Vec<(u8, Cards)> {
+        game.history().unwrap().shown.iter().map(|shown| (shown.position, shown.cards.clone())).collect()
+    }
+
+    #[test]
+    pub fn showdown_order() {
+        // the river bettor shows first
+        let mut game = heads_up();
+        to_river(&mut game, ["AsAd", "KsKd"], "2c7h9dJsQc");
+        game.act(1, Bet::Raise(Chips(10))).unwrap();
+        game.act(0, Bet::Call(Chips(10))).unwrap();
+        assert_eq!(shown(&game), vec![(1, card::parse("KsKd")), (0, card::parse("AsAd"))]);
+
+        // checked down, the first seat left of the button does
+        let mut game = heads_up();
+        to_river(&mut game, ["AsAd", "KsKd"], "2c7h9dJsQc");
+        game.act(1, Bet::Check).unwrap();
+        game.act(0, Bet::Check).unwrap();
+        assert_eq!(shown(&game), vec![(1, card::parse("KsKd")), (0, card::parse("AsAd"))]);
+    }
+
+    #[test]
+    pub fn muck_and_show() {
+        let mut game = heads_up();
+        game.set_auto_muck(0, true).unwrap();
+        to_river(&mut game, ["KsKd", "AsAd"], "2c7h9dJsQc");
+        game.act(1, Bet::Check).unwrap();
+        game.act(0, Bet::Check).unwrap();
+        assert_eq!(shown(&game), vec![(1, card::parse("AsAd"))]);
+        assert_eq!(stack(&game, 1), Chips(1010));
+        assert!(crate::pokerstars::write(game.history().unwrap(), None).contains("player-1: mucks hand"));
+
+        // one card after the hand, then the other
+        game.show(0, card::parse("Ks")).unwrap();
+        assert_eq!(game.history().unwrap().shown[1], history::Shown { position: 0, cards: card::parse("Ks"), description: String::new() });
+        game.show(0, card::parse("Kd")).unwrap();
+        assert_eq!(shown(&game), vec![(1, card::parse("AsAd")), (0, card::parse("KsKd"))]);
+        assert_eq!(game.show(0, card::parse("Ah")), Err(Error::Showdown(showdown::Error::NotInPocket)));
+
+        game.start().unwrap();
+        assert_eq!(game.show(0, card::parse("Ks")), Err(Error::HandInProgress));
+    }
+
+    #[test]
+    pub fn rabbit_hunting() {
+        let mut game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 2).with_rabbit_hunting();
+        game.join(Player::blank_player("player-1"), 0, Chips(1000)).unwrap();
+        game.join(Player::blank_player("player-2"), 1, Chips(1000)).unwrap();
+        game.start().unwrap();
+        game.act(0, Bet::Fold).unwrap();
+
+        let history = game.history().unwrap();
+        assert!(history.shown.is_empty());
+        assert_eq!(history.rabbit.len(), 5);
+        let dealt = history.pockets.iter().flat_map(|(_, cards)| cards.iter());
+        assert!(dealt.into_iter().all(|card| !history.rabbit.contains(card)));
+
+        // the folded hand can be shown too
+        let pocket = game.pocket_cards(0).unwrap().clone();
+        game.show(0, pocket.clone()).unwrap();
+        assert_eq!(shown(&game), vec![(0, pocket)]);
+        assert_eq!(game.show(3, card::parse("Ks")), Err(Error::Showdown(showdown::Error::NotDealtIn(3))));
+    }
+
+    fn all_in<'a>(game: Game<'a>, setup: impl Fn(&mut Game<'a>, u8)) -> Game<'a> {
+        let mut game = game.with_seed(7);
+        game.join(Player::blank_player("player-1"), 0, Chips(1000)).unwrap();
+        game.join(Player::blank_player("player-2"), 1, Chips(1000)).unwrap();
+        setup(&mut game, 0);
+        setup(&mut game, 1);
+        game.start().unwrap();
+        game.act(0, Bet::Raise(Chips(1000))).unwrap();
+        game.act(1, Bet::Call(Chips(990))).unwrap();
+        game
+    }
+
+    #[test]
+    pub fn run_it_twice() {
+        let game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 2).with_run_it(2);
+        let game = all_in(game, |game, position| game.set_run_it(position, 3).unwrap());
+        let history = game.history().unwrap();
+        assert_eq!(history.runs.len(), 2);
+        assert_eq!(history.runs[0], history.board);
+        assert!(history.runs.iter().all(|board| board.len() == 5));
+        // the second board comes off the same deck
+        assert!(history.runs[1].iter().all(|card| !history.board.contains(card)));
+        assert_eq!(stack(&game, 0) + stack(&game, 1), Chips(2000));
+
+        // once unless everyone agrees
+        let game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 2).with_run_it(3);
+        let game = all_in(game, |game, position| game.set_run_it(position, position + 1).unwrap());
+        assert!(game.history().unwrap().runs.is_empty());
+    }
+
+    #[test]
+    pub fn all_in_insurance() {
+        let game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 2).with_insurance(insurance::Policy::new(5.0));
+        let game = all_in(game, |game, position| game.set_insurance(position, true).unwrap());
+        let history = game.history().unwrap();
+        assert_eq!(history.insurance.len(), 1);
+        let cover = &history.insurance[0];
+        assert!(cover.equity > 0.5);
+        assert_eq!(cover.covered, Chips(2000));
+
+        // the house is the only one who makes or loses chips
+        let total = stack(&game, 0) + stack(&game, 1) + cover.premium;
+        assert_eq!(total, Chips(2000) + cover.payout);
+        assert_eq!(&game.events().rebuild().unwrap(), game.events().state());
+    }
+
+    #[test]
+    pub fn seat_changes_logged() {
+        let mut game = heads_up();
+        game.sit_out(1).unwrap();
+        let changes: Vec<_> = game.events().tail().iter().filter_map(|event| match event {
+            Event::SeatChanged { position: 1, to, .. } => Some(*to),
+            _ => None,
+        }).collect();
+        // sitting out mid-hand waits for the hand to finish
+        assert_eq!(changes, vec![seat::Status::Reserved, seat::Status::Ready, seat::Status::Ready, seat::Status::Playing, seat::Status::Playing]);
+        assert!(game.table.take_events().is_empty());
+    }
+
+    #[test]
+    pub fn pre_actions() {
+        let mut game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 3);
+        game.join(Player::blank_player("player-1"), 0, Chips(1000)).unwrap();
+        game.join(Player::blank_player("player-2"), 1, Chips(1000)).unwrap();
+        game.join(Player::blank_player("player-3"), 2, Chips(1000)).unwrap();
+        game.start().unwrap();
+
+        // big blind checks as soon as the action gets there
+        game.pre_act(2, AutoPlay::Check).unwrap();
+        game.act(0, Bet::Call(Chips(10))).unwrap();
+        game.act(1, Bet::Call(Chips(5))).unwrap();
+        assert_eq!(game.street(), Some(street::Holdem::Flop));
+        assert_eq!(game.actions().last().map(|action| action.bet()), Some(Bet::Check));
+
+        // a raise cancels a queued check but not check/fold or call any
+        game.pre_act(0, AutoPlay::Check).unwrap();
+        game.pre_act(2, AutoPlay::CallAny).unwrap();
+        game.act(1, Bet::Raise(Chips(20))).unwrap();
+        assert_eq!(game.actions().last().map(|action| action.bet()), Some(Bet::Call(Chips(20))));
+        assert_eq!(game.to_act(), Some(0));
+        assert_eq!(game.table().seat(0).and_then(|seat| seat.pre_action()), None);
+
+        game.pre_act(0, AutoPlay::CheckFold).unwrap();
+        assert_eq!(game.actions().last().map(|action| action.bet()), Some(Bet::Fold));
+        assert_eq!(game.street(), Some(street::Holdem::Turn));
+    }
+
+    #[test]
+    pub fn timeout() {
+        let time = clock::FakeClock::new();
+        let settings = clock::Settings::new(time::Duration::from_secs(10))
+            .with_time_bank(time::Duration::from_secs(20), time::Duration::from_secs(20))
+            .with_warning(time::Duration::from_secs(5));
+        let mut game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 2)
+            .with_clock(settings)
+            .with_time_source(time.clone());
+        game.join(Player::blank_player("player-1"), 0, Chips(1000)).unwrap();
+        game.join(Player::blank_player("player-2"), 1, Chips(1000)).unwrap();
+        game.start().unwrap();
+        time.advance(time::Duration::from_secs(3));
+        game.act(0, Bet::Call(Chips(5))).unwrap();
+        assert_eq!(game.actions().last().map(|action| action.time_used()), Some(time::Duration::from_secs(3)));
+
+        // default auto play checks when possible
+        time.advance(time::Duration::from_secs(10));
+        assert_eq!(game.poll(), Ok(Some((1, Alert::TimeBank(time::Duration::from_secs(20))))));
+        time.advance(time::Duration::from_secs(16));
+        assert_eq!(game.poll(), Ok(Some((1, Alert::Warning(time::Duration::from_secs(4))))));
+        time.advance(time::Duration::from_secs(4));
+        assert_eq!(game.poll(), Ok(Some((1, Alert::Expired))));
+        let action = game.actions().last().unwrap();
+        assert_eq!(action.bet(), Bet::Check);
+        assert!(action.is_timed_out());
+        assert_eq!(action.time_used(), time::Duration::from_secs(30));
+        assert_eq!(game.table().seat(1).and_then(|seat| seat.clock()).map(|clock| clock.time_bank()), Some(time::Duration::ZERO));
+
+        // or plays the seat's own default
+        game.table.seat_mut(0).unwrap().set_auto_play(AutoPlay::CallAny);
+        game.act(1, Bet::Raise(Chips(10))).unwrap();
+        time.advance(time::Duration::from_secs(30));
+        assert_eq!(game.poll(), Ok(Some((0, Alert::Expired))));
+        assert_eq!(game.actions().last().map(|action| action.bet()), Some(Bet::Call(Chips(10))));
+        assert_eq!(game.street(), Some(street::Holdem::Turn));
+    }
+
+    fn secs(secs: u64) -> time::Duration {
+        time::Duration::from_secs(secs)
+    }
+
+    fn protected<'a>(protection: Protection) -> (Game<'a>, clock::FakeClock) {
+        let time = clock::FakeClock::new();
+        let settings = clock::Settings::new(secs(10)).with_time_bank(secs(0), secs(0));
+        let mut game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 2)
+            .with_clock(settings)
+            .with_time_source(time.clone())
+            .with_protection(protection);
+        game.join(Player::blank_player("player-1"), 0, Chips(1000)).unwrap();
+        game.join(Player::blank_player("player-2"), 1, Chips(1000)).unwrap();
+        game.start().unwrap();
+        (game, time)
+    }
+
+    #[test]
+    pub fn disconnect_grace() {
+        let (mut game, time) = protected(Protection::new().with_grace(secs(20)));
+        game.table.seat_mut(0).unwrap().set_auto_play(AutoPlay::CallAny);
+        game.disconnect(0).unwrap();
+
+        time.advance(secs(20));
+        assert_eq!(game.poll(), Ok(Some((0, Alert::Warning(secs(10))))));
+        time.advance(secs(10));
+        assert_eq!(game.poll(), Ok(Some((0, Alert::Expired))));
+        // check/fold while offline, whatever the seat's own default is
+        assert_eq!(game.actions().last().map(|action| action.bet()), Some(Bet::Fold));
+        assert!(!game.is_hand_in_progress());
+    }
+
+    #[test]
+    pub fn disconnected_all_in_protection() {
+        let (mut game, time) = protected(Protection::new().with_grace(secs(0)).with_all_in_protections(1));
+        game.disconnect(0).unwrap();
+        time.advance(secs(10));
+        assert_eq!(game.poll(), Ok(Some((0, Alert::Expired))));
+        assert!(game.table().seat(0).unwrap().is_all_in());
+        assert_eq!(game.table().seat(0).unwrap().all_in_protections(), 1);
+
+        // the big blind has the option, then the board runs out
+        game.act(1, Bet::Check).unwrap();
+        assert!(!game.is_hand_in_progress());
+        assert_eq!(game.board().len(), 5);
+        assert_eq!(stack(&game, 0) + stack(&game, 1), Chips(2000));
+
+        // protection is used up
+        game.start().unwrap();
+        let position = game.to_act().unwrap();
+        game.disconnect(position).unwrap();
+        game.act(position, Bet::Raise(Chips(20))).unwrap();
+        time.advance(secs(10));
+        game.poll().unwrap();
+        assert_eq!(game.actions().last().map(|action| action.bet()), Some(Bet::Fold));
+    }
+
+    #[test]
+    pub fn away_while_offline() {
+        let (mut game, _) = protected(Protection::new().with_away_after_hands(Some(1)));
+        game.disconnect(0).unwrap();
+        game.act(0, Bet::Fold).unwrap();
+        assert_eq!(game.table().seat(0).map(|seat| seat.status()), Some(seat::Status::Away));
+        assert_eq!(game.start(), Err(Error::NotEnoughPlayers));
+
+        game.reconnect(0).unwrap();
+        assert_eq!(game.table().seat(0).map(|seat| seat.status()), Some(seat::Status::Ready));
+        assert_eq!(stack(&game, 0), Chips(995));
+        game.start().unwrap();
+    }
+
+    #[test]
+    pub fn rebuy_between_hands() {
+        let mut game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 2);
+        game.join(Player::blank_player("player-1"), 0, Chips(800)).unwrap();
+        game.join(Player::blank_player("player-2"), 1, Chips(800)).unwrap();
+        game.start().unwrap();
+
+        // requested mid-hand, chips in the pot count towards the maximum
+        game.rebuy(0, Chips(100)).unwrap();
+        assert_eq!(game.rebuy(0, Chips(101)), Err(Error::Table(table::Error::RebuyTooLarge { headroom: Chips(100) })));
+        assert_eq!(stack(&game, 0), Chips(795));
+        game.act(0, Bet::Fold).unwrap();
+        assert_eq!(stack(&game, 0), Chips(795));
+
+        game.start().unwrap();
+        assert_eq!(game.top_ups(), &[(0, Chips(100))]);
+        // the big blind is posted from the topped up stack
+        assert_eq!(stack(&game, 0), Chips(885));
+        assert_eq!(game.rebuy(1, Chips(200)), Err(Error::Table(table::Error::RebuyTooLarge { headroom: Chips(195) })));
+    }
+
+    #[test]
+    pub fn auto_rebuy() {
+        let mut game = heads_up();
+        game.set_auto_rebuy(0, Some(AutoRebuy { threshold: Chips(1000), to: Chips(1200) })).unwrap();
+        game.act(0, Bet::Fold).unwrap();
+        assert_eq!(game.table().seat(0).map(|seat| seat.pending_rebuy()), Some(Chips(5)));
+        game.start().unwrap();
+        assert_eq!(game.top_ups(), &[(0, Chips(5))]);
+    }
 }
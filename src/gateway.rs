@@ -0,0 +1,172 @@
+use std::future::Future;
+use std::io;
+use std::sync::Arc;
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, watch};
+use tokio_tungstenite::tungstenite;
+use crate::server::{Message, Server, Session};
+
+// the same requests and messages as the tcp server, one json text frame each, see docs/websocket.md
+
+pub async fn serve(server: Arc<Server>, listener: TcpListener, shutdown: impl Future<Output = ()>) -> io::Result<()> {
+    server.listen(listener, shutdown, connection).await
+}
+
+fn io_error(error: tungstenite::Error) -> io::Error {
+    match error {
+        tungstenite::Error::Io(error) => error,
+        error => io::Error::new(io::ErrorKind::InvalidData, error),
+    }
+}
+
+async fn connection(server: Arc<Server>, stream: TcpStream, mut stopped: watch::Receiver<bool>) -> io::Result<()> {
+    let (mut sink, mut frames) = tokio_tungstenite::accept_async(stream).await.map_err(io_error)?.split();
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut heartbeat = tokio::time::interval(server.heartbeat() / 2);
+    let mut session = Session::new(server, sender);
+    loop {
+        tokio::select! {
+            frame = frames.next() => match frame {
+                Some(Ok(tungstenite::Message::Text(text))) => match serde_json::from_str(&text) {
+                    Ok(request) => session.handle(request),
+                    Err(error) => session.send(Message::Error { reason: format!("invalid request: {}", error) }),
+                },
+                Some(Ok(tungstenite::Message::Close(_))) | Some(Err(_)) | None => break,
+                // tungstenite answers pings itself
+                Some(Ok(_)) => {}
+            },
+            Some(message) = receiver.recv() => {
+                let text = serde_json::to_string(&message).expect("messages always serialize");
+                sink.send(tungstenite::Message::Text(text)).await.map_err(io_error)?;
+            }
+            _ = heartbeat.tick() => session.check_heartbeat(),
+            _ = stopped.changed() => break,
+        }
+    }
+    drop(session);
+    // the client may already be gone
+    let _ = sink.close().await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time;
+    use tokio::net::TcpStream;
+    use tokio::sync::oneshot;
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+    use crate::bet::{AutoPlay, Bet};
+    use crate::chips::Chips;
+    use crate::game::{Game, Limit, Name, Stake};
+    use crate::protocol;
+    use crate::server::Request;
+
+    struct Client(WebSocketStream<MaybeTlsStream<TcpStream>>);
+
+    impl Client {
+        async fn connect(address: std::net::SocketAddr) -> Self {
+            let (socket, _) = tokio_tungstenite::connect_async(format!("ws://{}", address)).await.unwrap();
+            Client(socket)
+        }
+
+        async fn send(&mut self, request: Request) {
+            self.0.send(tungstenite::Message::Text(serde_json::to_string(&request).unwrap())).await.unwrap();
+        }
+
+        async fn until<T>(&mut self, mut f: impl FnMut(Message) -> Option<T>) -> T {
+            loop {
+                let frame = tokio::time::timeout(time::Duration::from_secs(5), self.0.next()).await.unwrap();
+                if let Some(Ok(tungstenite::Message::Text(text))) = frame {
+                    if let Some(found) = f(serde_json::from_str(&text).unwrap()) {
+                        return found;
+                    }
+                }
+            }
+        }
+
+        async fn table(&mut self, f: impl Fn(&protocol::Table) -> bool) -> protocol::Table {
+            self.until(|message| match message {
+                Message::Table { table } if f(&table) => Some(table),
+                _ => None,
+            }).await
+        }
+    }
+
+    #[tokio::test]
+    pub async fn browser_session() {
+        let game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 6).with_name("Alpha").with_seed(3);
+        let server = Server::new().with_heartbeat(time::Duration::from_millis(200)).with_table(game);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let serving = tokio::spawn(serve(Arc::new(server), listener, async { stopped.await.ok(); }));
+
+        let mut alice = Client::connect(address).await;
+        alice.send(Request::Login { name: "alice".to_string(), token: String::new() }).await;
+        let resume = alice.until(|message| match message {
+            Message::LoggedIn { resume, .. } => Some(resume),
+            _ => None,
+        }).await;
+        alice.send(Request::Tables).await;
+        let tables = alice.until(|message| match message {
+            Message::Tables { tables } => Some(tables),
+            _ => None,
+        }).await;
+        assert_eq!((tables[0].game.table.as_str(), tables[0].players), ("Alpha", 0));
+
+        let mut bob = Client::connect(address).await;
+        bob.send(Request::Login { name: "bob".to_string(), token: String::new() }).await;
+        for (client, position) in [(&mut alice, 1), (&mut bob, 4)] {
+            client.send(Request::Subscribe { table: "Alpha".to_string() }).await;
+            client.send(Request::Join { table: "Alpha".to_string(), position, amount: Chips(1000) }).await;
+        }
+        let table = bob.table(|table| table.street.is_some()).await;
+        assert_eq!(table.seats[1].cards, vec![None, None]);
+
+        // whoever waits queues a fold, which goes in as soon as the other one calls
+        let (first, second) = (table.to_act.unwrap(), if table.to_act == Some(1) { 4 } else { 1 });
+        let (acting, waiting) = if first == 1 { (&mut alice, &mut bob) } else { (&mut bob, &mut alice) };
+        waiting.send(Request::PreAct { table: "Alpha".to_string(), auto_play: AutoPlay::Fold }).await;
+        acting.send(Request::Act { table: "Alpha".to_string(), bet: Bet::Raise(Chips(40)) }).await;
+        let folded = acting.until(|message| match message {
+            Message::Event { event: protocol::Event::Action(action), .. } if action.position == second && !action.forced => Some(action.bet),
+            _ => None,
+        }).await;
+        assert_eq!(folded, Bet::Fold);
+
+        // alice goes quiet and shows as offline, then drops and comes back with her token
+        alice.table(|table| !table.seats[1].online).await;
+        drop(alice);
+        // the server parks the session once it notices the connection is gone
+        let mut alice = Client::connect(address).await;
+        loop {
+            alice.send(Request::Resume { token: resume.clone() }).await;
+            let resumed = alice.until(|message| match message {
+                Message::LoggedIn { .. } => Some(true),
+                Message::Error { .. } => Some(false),
+                _ => None,
+            }).await;
+            if resumed {
+                break;
+            }
+            tokio::time::sleep(time::Duration::from_millis(20)).await;
+        }
+        alice.table(|table| table.seats[1].online && table.seats[1].player.is_some()).await;
+        alice.send(Request::Heartbeat).await;
+        alice.until(|message| (message == Message::Heartbeat).then_some(())).await;
+
+        // a token only works while its session is parked
+        let mut mallory = Client::connect(address).await;
+        mallory.send(Request::Resume { token: resume }).await;
+        let error = mallory.until(|message| match message {
+            Message::Error { reason } => Some(reason),
+            _ => None,
+        }).await;
+        assert_eq!(error, "nothing to resume");
+
+        shutdown.send(()).unwrap();
+        serving.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+use std::time;
+use crate::bet::Bet;
+use crate::chips::Chips;
+use crate::game::{Limit, Name, Stake, street};
+use crate::insurance::Cover;
+use crate::poker::Cards;
+use crate::pot::{Award, SidePot};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Seat {
+    pub position: u8,
+    pub player: String,
+    pub stack: Chips,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub position: u8,
+    pub bet: Bet,
+    pub all_in: bool,
+    pub timed_out: bool,
+    // dead blinds go to the pot without counting towards the call
+    pub dead: bool,
+    pub time: time::SystemTime,
+    pub time_used: time::Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Street {
+    pub street: street::Holdem,
+    pub cards: Cards,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shown {
+    pub position: u8,
+    pub cards: Cards,
+    pub description: String,
+}
+
+// everything needed to tell the story of a finished hand
+#[derive(Debug, Clone)]
+pub struct History {
+    pub id: u64,
+    // what the deck was shuffled from, unknown for imported hands
+    pub seed: Option<u64>,
+    pub time: time::SystemTime,
+    pub game: Name,
+    pub limit: Limit,
+    pub stake: Stake,
+    pub table: String,
+    pub table_size: u8,
+    pub button: u8,
+    pub small_blind: Option<u8>,
+    pub big_blind: u8,
+    pub seats: Vec<Seat>,
+    pub top_ups: Vec<(u8, Chips)>,
+    pub posts: Vec<Entry>,
+    pub pockets: Vec<(u8, Cards)>,
+    pub streets: Vec<Street>,
+    pub board: Cards,
+    pub uncalled: Option<(u8, Chips)>,
+    // in the order they were turned up, with anything shown after the hand at the end
+    pub shown: Vec<Shown>,
+    // the rest of the board for a hand that ended early, when someone asked to see it
+    pub rabbit: Cards,
+    // every board of a hand run more than once, the first is the board
+    pub runs: Vec<Cards>,
+    pub insurance: Vec<Cover>,
+    pub side_pots: Vec<SidePot>,
+    pub awards: Vec<Award>,
+    pub total: Chips,
+    pub rake: Chips,
+}
+
+impl History {
+    pub fn player(&self, position: u8) -> Option<&str> {
+        self.seats.iter().find(|seat| seat.position == position).map(|seat| seat.player.as_str())
+    }
+
+    pub fn pocket(&self, position: u8) -> Option<&Cards> {
+        self.pockets.iter().find(|(seat, _)| *seat == position).map(|(_, cards)| cards)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.posts.iter().chain(self.streets.iter().flat_map(|street| street.entries.iter()))
+    }
+
+    // the last seat to bet or raise on the last street anyone acted on
+    pub fn last_aggressor(&self) -> Option<u8> {
+        let street = self.streets.iter().rev().find(|street| !street.entries.is_empty())?;
+        street.entries.iter().rev().find(|entry| matches!(entry.bet, Bet::Raise(_))).map(|entry| entry.position)
+    }
+
+    // street on which the player folded, None if they never did
+    pub fn folded_on(&self, position: u8) -> Option<street::Holdem> {
+        self.streets.iter()
+            .find(|street| street.entries.iter().any(|entry| entry.position == position && entry.bet == Bet::Fold))
+            .map(|street| street.street)
+    }
+
+    // chips put into the pot, raises are to an amount for the street and antes and dead blinds don't count towards it
+    pub fn contributed(&self, position: u8) -> Chips {
+        let mut total = Chips::ZERO;
+        let mut put = Chips::ZERO;
+        for entry in self.posts.iter().filter(|entry| entry.position == position) {
+            total += entry.bet.amount();
+            if !entry.dead && !matches!(entry.bet, Bet::Ante(_)) {
+                put += entry.bet.amount();
+            }
+        }
+        for street in self.streets.iter() {
+            if street.street != street::Holdem::Preflop {
+                put = Chips::ZERO;
+            }
+            for entry in street.entries.iter().filter(|entry| entry.position == position) {
+                match entry.bet {
+                    Bet::Raise(to) => {
+                        total += to.saturating_sub(put);
+                        put = to;
+                    },
+                    Bet::Call(amount) => {
+                        total += amount;
+                        put += amount;
+                    },
+                    _ => {},
+                }
+            }
+        }
+        total
+    }
+
+    pub fn won(&self, position: u8) -> Chips {
+        self.awards.iter().filter(|award| award.position as u8 == position).map(|award| award.amount).sum()
+    }
+}
@@ -0,0 +1,66 @@
+use crate::chips::Chips;
+use crate::rake::BASIS_POINTS;
+
+// all-in insurance sold by the house: a player ahead when the money goes in covers the pot
+// they stand to win, paying what the equity calculator says the cover is worth plus a margin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Policy {
+    margin_basis_points: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cover {
+    pub position: u8,
+    // the share of the pot the hand was expected to win when it was covered
+    pub equity: f64,
+    pub covered: Chips,
+    pub premium: Chips,
+    // whatever the hand didn't win of the covered pot
+    pub payout: Chips,
+}
+
+impl Policy {
+    pub fn new(margin_percent: f32) -> Self {
+        Policy { margin_basis_points: (margin_percent * 100.0).round() as u32 }
+    }
+
+    pub fn margin_basis_points(&self) -> u32 {
+        self.margin_basis_points
+    }
+
+    // only the favourite gets offered cover, at the fair price of the part it's expected to lose
+    pub fn quote(&self, position: u8, equity: f64, covered: Chips) -> Option<Cover> {
+        if equity <= 0.5 || equity >= 1.0 || covered.is_zero() {
+            return None;
+        }
+        let fair = (covered.amount() as f64 * (1.0 - equity)).round() as u64;
+        let premium = Chips(fair).basis_points(BASIS_POINTS + self.margin_basis_points).min(covered);
+        Some(Cover { position, equity, covered, premium, payout: Chips::ZERO })
+    }
+}
+
+impl Cover {
+    pub fn settle(&mut self, won: Chips) {
+        self.payout = self.covered.saturating_sub(won);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn quotes() {
+        let policy = Policy::new(5.0);
+        let cover = policy.quote(0, 0.8, Chips(1000)).unwrap();
+        assert_eq!((cover.premium, cover.payout), (Chips(210), Chips::ZERO));
+        assert_eq!(policy.quote(1, 0.2, Chips(1000)), None);
+        assert_eq!(policy.quote(1, 0.5, Chips(1000)), None);
+
+        let mut cover = cover;
+        cover.settle(Chips(500));
+        assert_eq!(cover.payout, Chips(500));
+        cover.settle(Chips(1000));
+        assert_eq!(cover.payout, Chips::ZERO);
+    }
+}
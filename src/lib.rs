@@ -1,10 +1,37 @@
 pub mod poker;
 pub mod game;
 pub mod bet;
+pub mod chips;
+pub mod betting;
+pub mod clock;
+pub mod disconnect;
 pub mod seat;
+pub mod button;
 pub mod table;
 pub mod pot;
+pub mod rake;
+pub mod showdown;
+pub mod insurance;
+pub mod history;
+pub mod events;
+pub mod storage;
+pub mod equity;
+pub mod range;
+pub mod pokerstars;
+pub mod replay;
+pub mod record;
+pub mod protocol;
 pub mod locale;
 pub mod player;
 pub mod cli;
+#[cfg(feature = "server")]
 pub mod server;
+#[cfg(feature = "server")]
+pub mod gateway;
+#[cfg(feature = "server")]
+pub mod config;
+pub mod ffi;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "python")]
+pub mod python;
@@ -1,4 +1,7 @@
-#[derive(Debug, Clone)]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum Currency {
     USD,
     EUR,
@@ -62,6 +65,16 @@ pub enum Country {
 }
 
 impl Currency {
+    pub const ALL: [Self; 25] = [
+        Self::USD, Self::EUR, Self::JPY, Self::GBP, Self::CHF, Self::AUD, Self::CAD, Self::CNY, Self::HKD,
+        Self::SGD, Self::MOP, Self::MYR, Self::RUB, Self::BRL, Self::INR, Self::SEK, Self::KRW, Self::IDR,
+        Self::PHP, Self::MXN, Self::UAH, Self::BYN, Self::ZAR, Self::KES, Self::NGN,
+    ];
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|currency| format!("{:?}", currency) == code)
+    }
+
     pub fn countries(&self) -> Vec<Country> {
         match self {
             Currency::USD => vec![Country::USA],
@@ -89,7 +102,13 @@ impl Currency {
             Currency::ZAR => vec![Country::ZAF],
             Currency::KES => vec![Country::KEN],
             Currency::NGN => vec![Country::NGA],
-            _ => todo!(),
+        }
+    }
+
+    pub fn minor_units(&self) -> u8 {
+        match self {
+            Currency::JPY | Currency::KRW => 0,
+            _ => 2,
         }
     }
 }
@@ -1,3 +1,4 @@
+use std::sync::Arc;
 use crate::locale::Country;
 
 #[derive(Debug, Copy, Clone)]
@@ -5,17 +6,18 @@ pub struct Avatar<'a> {
     url: &'a str,
 }
 
+// the name is shared by every seat, action and hand the player shows up in
 #[derive(Debug, Clone)]
 pub struct Player<'a> {
-    pub name: &'a str,
+    pub name: Arc<str>,
     avatar: Option<Avatar<'a>>,
     country: Option<Country>,
 }
 
-impl<'a> Player<'a> {
-    pub fn blank_player(name: &'a str) -> Self {
+impl Player<'_> {
+    pub fn blank_player(name: &str) -> Self {
         Player {
-            name,
+            name: Arc::from(name),
             avatar: None,
             country: None,
         }
@@ -1,21 +1,24 @@
 use std::fmt;
-use vec_map::VecMap;
 use std::cmp::Ordering;
 use ansi_term::Colour;
+use schemars::JsonSchema;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject, StringValidation};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 pub mod bits {
     const BIT_FLAG: u8 = 1 << 6;
 
-    fn hide(card: u8) -> u8 { card | BIT_FLAG }
+    pub fn hide(card: u8) -> u8 { card | BIT_FLAG }
 
-    fn show(card: u8) -> u8 { card & !BIT_FLAG }
+    pub fn show(card: u8) -> u8 { card & !BIT_FLAG }
 
-    fn is_hidden(card: u8) -> bool { card & BIT_FLAG == BIT_FLAG }
+    pub fn is_hidden(card: u8) -> bool { card & BIT_FLAG == BIT_FLAG }
 
-    fn is_visible(card: u8) -> bool { !is_hidden(card) }
+    pub fn is_visible(card: u8) -> bool { !is_hidden(card) }
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Kind {
     Deuce,
     Three,
@@ -74,21 +77,25 @@ impl Kind {
 pub mod kind {
     use super::Kind;
     pub fn from_str(s: &str) -> Kind {
+        parse(s).unwrap_or_else(|| panic!("unknown card kind: {}", s))
+    }
+
+    pub fn parse(s: &str) -> Option<Kind> {
         match s {
-            "2" => Kind::Deuce,
-            "3" => Kind::Three,
-            "4" => Kind::Four,
-            "5" => Kind::Five,
-            "6" => Kind::Six,
-            "7" => Kind::Seven,
-            "8" => Kind::Eight,
-            "9" => Kind::Nine,
-            "T" => Kind::Ten,
-            "J" => Kind::Jack,
-            "Q" => Kind::Queen,
-            "K" => Kind::King,
-            "A" => Kind::Ace,
-            &_ => todo!(),
+            "2" => Some(Kind::Deuce),
+            "3" => Some(Kind::Three),
+            "4" => Some(Kind::Four),
+            "5" => Some(Kind::Five),
+            "6" => Some(Kind::Six),
+            "7" => Some(Kind::Seven),
+            "8" => Some(Kind::Eight),
+            "9" => Some(Kind::Nine),
+            "T" => Some(Kind::Ten),
+            "J" => Some(Kind::Jack),
+            "Q" => Some(Kind::Queen),
+            "K" => Some(Kind::King),
+            "A" => Some(Kind::Ace),
+            &_ => None,
         }
     }
 }
@@ -103,7 +110,7 @@ impl fmt::Display for Kind {
     }
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Suit {
     Spade,
     Heart,
@@ -158,12 +165,16 @@ pub mod suit {
     use super::Suit;
 
     pub fn from_str(s: &str) -> Suit {
+        parse(s).unwrap_or_else(|| panic!("unknown card suit: {}", s))
+    }
+
+    pub fn parse(s: &str) -> Option<Suit> {
         match s {
-            "s" => Suit::Spade,
-            "h" => Suit::Heart,
-            "d" => Suit::Diamond,
-            "c" => Suit::Club,
-            &_ => todo!(),
+            "s" => Some(Suit::Spade),
+            "h" => Some(Suit::Heart),
+            "d" => Some(Suit::Diamond),
+            "c" => Some(Suit::Club),
+            &_ => None,
         }
     }
 }
@@ -175,8 +186,26 @@ pub struct Card {
 }
 
 impl Card {
+    pub fn new(kind: Kind, suit: Suit) -> Self {
+        Card { kind, suit }
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    pub fn suit(&self) -> Suit {
+        self.suit
+    }
+
     pub fn to_u8(&self) -> u8 {
-        return (self.kind as u8) << 2 + (self.suit as u8);
+        (self.kind as u8) << 2 | self.suit as u8
+    }
+
+    pub fn from_u8(card: u8) -> Option<Card> {
+        let kind = *Kind::ALL.get((card >> 2) as usize)?;
+        let suit = Suit::ALL[(card & 3) as usize];
+        Some(Card { kind, suit })
     }
 }
 
@@ -195,6 +224,37 @@ impl fmt::Display for Card {
     }
 }
 
+// cards travel as "Ah" strings
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("{:?}", self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        card::from_str(&s).ok_or_else(|| serde::de::Error::custom(card::Error::Invalid(s)))
+    }
+}
+
+impl JsonSchema for Card {
+    fn schema_name() -> String {
+        "Card".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        Schema::Object(SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                pattern: Some("^[2-9TJQKA][shdc]$".to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        })
+    }
+}
+
 impl PartialOrd for Card {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some((self.kind as u8).cmp(&(other.kind as u8)))
@@ -207,7 +267,7 @@ impl PartialEq for Card {
     }
 }
 
-type Cards = Vec<Card>;
+pub type Cards = Vec<Card>;
 
 // impl fmt::Display for Cards {
 //     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -216,18 +276,61 @@ type Cards = Vec<Card>;
 // }
 
 pub mod card {
+    use std::fmt;
     use regex::Regex;
     use super::{Card, Cards, kind, suit};
 
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Error {
+        Invalid(String),
+        Duplicate(Card),
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Invalid(s) => write!(f, "invalid card: {}", s),
+                Self::Duplicate(card) => write!(f, "duplicate card: {:?}", card),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+
     pub fn new(kind: &str, suit: &str) -> Card {
         return Card { kind: kind::from_str(kind), suit: suit::from_str(suit) }
     }
 
     pub fn from_str(s: &str) -> Option<Card> {
-        if s.len() != 2 {
+        if s.len() != 2 || !s.is_char_boundary(1) {
             return None;
         }
-        None
+        Some(Card { kind: kind::parse(&s[..1])?, suit: suit::parse(&s[1..])? })
+    }
+
+    // unlike parse, rejects anything that is not a whitespace separated list of distinct cards
+    pub fn parse_strict(s: &str) -> Result<Cards, Error> {
+        let mut cards: Cards = vec![];
+        for token in s.split_whitespace() {
+            let card = from_str(token).ok_or_else(|| Error::Invalid(token.to_string()))?;
+            if cards.contains(&card) {
+                return Err(Error::Duplicate(card));
+            }
+            cards.push(card);
+        }
+        Ok(cards)
+    }
+
+    // like parse_strict but the cards of a hand may be written together, "AhKd" or "Ah,Kd"
+    pub fn parse_joined(s: &str) -> Result<Cards, Error> {
+        let mut tokens = vec![];
+        for word in s.split(|c: char| c.is_whitespace() || c == ',').filter(|word| !word.is_empty()) {
+            if !word.is_ascii() || word.len() % 2 != 0 {
+                return Err(Error::Invalid(word.to_string()));
+            }
+            tokens.extend((0..word.len()).step_by(2).map(|i| &word[i..i + 2]));
+        }
+        parse_strict(&tokens.join(" "))
     }
 
     pub fn parse(s: &str) -> Cards {
@@ -239,10 +342,15 @@ pub mod card {
         }
         return cards
     }
+
+    // plain "Ah Kd" without the terminal colours
+    pub fn format(cards: &[Card]) -> String {
+        cards.iter().map(|card| format!("{:?}", card)).collect::<Vec<_>>().join(" ")
+    }
 }
 
 pub mod rank {
-    #[derive(Copy, Clone)]
+    #[derive(Copy, Clone, Debug, PartialEq)]
     pub enum High {
         HighCard,
         OnePair,
@@ -271,7 +379,7 @@ pub mod rank {
         }
     }
 
-    #[derive(Copy, Clone)]
+    #[derive(Copy, Clone, Debug, PartialEq)]
     pub enum Badugi {
         BadugiOne,
         BadugiTwo,
@@ -290,7 +398,7 @@ pub mod rank {
         }
     }
 
-    #[derive(Copy, Clone)]
+    #[derive(Copy, Clone, Debug, PartialEq)]
     pub enum Low {
         CompleteLow,
         IncompleteLow,
@@ -308,230 +416,265 @@ pub mod rank {
     use super::Rank;
 
     pub fn straight_flush() -> Rank {
-        Rank{ high: High::StraightFlush }
+        Rank::High(High::StraightFlush)
     }
 
     pub fn four_kind() -> Rank {
-        Rank{ high: High::FourKind }
+        Rank::High(High::FourKind)
     }
 }
 
 use std::cmp::Ordering::{Equal, Greater, Less};
 
-use crate::game::AceRanking;
-
 mod compare {
-    use super::{Cards, Rank, rank};
+    use super::{Cards, Kind, Rank};
 
     use std::cmp::Ordering;
-    use std::cmp::Ordering::{Less, Equal, Greater};
+    use std::cmp::Ordering::Equal;
 
     pub fn compare_ranks(a: &Rank, b: &Rank) -> Ordering {
-        unsafe {
-            match &a {
-                Rank { high } => (*high as u8).cmp(&(b.high as u8)),
-                Rank { low } => (*low as u8).cmp(&(b.low as u8)),
-                Rank { badugi } => (*badugi as u8).cmp(&(b.badugi as u8)),
-            }
+        match (a, b) {
+            (Rank::High(a), Rank::High(b)) => (*a as u8).cmp(&(*b as u8)),
+            (Rank::Low(a), Rank::Low(b)) => (*a as u8).cmp(&(*b as u8)),
+            (Rank::Badugi(a), Rank::Badugi(b)) => (*a as u8).cmp(&(*b as u8)),
+            _ => Equal,
         }
     }
 
     pub fn compare_cards(a: &Cards, b: &Cards) -> Ordering {
-        if a.len() == b.len() {
-            let mut result = Equal;
-            //a.iter().for_each( |card| result = card.cmp(b[i]); result == Equal );
-            return result;
-        }
-        Equal
+        let a = a.iter().map(|card| card.kind as u8);
+        let b = b.iter().map(|card| card.kind as u8);
+        a.cmp(b)
+    }
+
+    // ace plays low, lower is better
+    pub fn low_value(kind: Kind) -> u8 {
+        if kind == Kind::Ace { 0 } else { kind as u8 + 1 }
+    }
 
-        // let min_size = a.len().min(b.len());
-        // compare_cards(a.iter().take(min_size).collect().to_vec(), b.iter().take(min_size).collect().to_vec())
+    pub fn compare_low(a: &Cards, b: &Cards) -> Ordering {
+        let a = a.iter().map(|card| low_value(card.kind));
+        let b = b.iter().map(|card| low_value(card.kind));
+        b.cmp(a)
     }
 }
 
-#[derive(Clone)]
-pub struct Hand<'a> {
-    cards: &'a Cards,
-    value: &'a Cards,
+#[derive(Clone, Debug)]
+pub struct Hand {
+    cards: Cards,
+    value: Cards,
     rank: Option<Rank>,
-    high: Result<&'a Cards, bool>,
-    kicker: Result<&'a Cards, bool>,
+    high: Cards,
+    kicker: Cards,
 }
 
-impl Hand<'_> {
+impl Hand {
+    pub fn rank(&self) -> Option<Rank> {
+        self.rank
+    }
+
+    pub fn cards(&self) -> &Cards {
+        &self.cards
+    }
+
+    pub fn value(&self) -> &Cards {
+        &self.value
+    }
+
     pub fn meaningful_high(&self) -> &Cards {
-        match self.high {
-            Ok(cards) => cards,
-            Err(true) => &self.cards,
-            Err(false) => &self.cards,
-        }
+        if self.high.is_empty() { &self.cards } else { &self.high }
     }
 
     pub fn meaningful_kicker(&self) -> &Cards {
-        match self.kicker {
-            Ok(cards) => cards,
-            Err(true) => &self.cards,
-            Err(false) => &self.cards,
-        }
+        &self.kicker
     }
 
     pub fn description(&self) -> String {
-        unsafe {
-            match self.rank {
-                Some(rank) => match rank {
-                    Rank{ high: rank::High::HighCard } => format!("high card {}", self.cards[0].kind),
-                    Rank{ high: rank::High::OnePair } => format!("pair of {}s", self.cards[0].kind),
-                    Rank{ high: rank::High::TwoPair } => format!("two pairs, {}s and {}s", self.cards[0].kind, self.cards[1].kind),
-                    Rank{ high: rank::High::ThreeKind } => format!("three of a kind, {}s", self.cards[0].kind),
-                    // Rank{ high: rank::High::Straight } => {
-                    //     let (from, to) = if self.cards[0].kind == Kind::Five {
-                    //         (self.value.min(AceRanking::Low).kind, self.value.max(AceRanking::Low).kind)
-                    //     } else {
-                    //         (self.value.min().kind, self.value.max().kind)
-                    //     };
-                    //     format!("straight, {} to {}", from, to)
-                    // }
-                    Rank{ high: rank::High::Flush } => format!("flush, {} high", self.cards[0].kind),
-                    Rank{ high: rank::High::FullHouse } => format!("full house, {}s full of {}s", self.cards[0].kind, self.cards[1].kind),
-                    Rank{ high: rank::High::FourKind } => format!("four of a kind, {}s", self.cards[0].kind),
-                    //Rank{ high: rank::High::StraightFlush } => format!("straight flush, {} to {}", self.value.min().kind, self.value.max().kind),
-
-                    Rank{ badugi: rank::Badugi::BadugiOne } => format!("1-card badugi: {}", self.value[0]),
-                    Rank{ badugi: rank::Badugi::BadugiTwo } => format!("2-card badugi: {} + {}", self.value[0], self.value[1]),
-                    Rank{ badugi: rank::Badugi::BadugiThree } => format!("3-card badugi: {} + {} + {}", self.value[0], self.value[1], self.value[2]),
-                    Rank{ badugi: rank::Badugi::BadugiFour } => format!("4-card badugi: {} + {} + {} + {}", self.value[0], self.value[1], self.value[2], self.value[3]),
-
-                    _ => todo!() //format!("(unknown: {})", self),
-                },
-                _ => "(none)".to_string(),
-            }
+        let high = self.meaningful_high();
+        match self.rank {
+            Some(Rank::High(rank)) => match rank {
+                rank::High::HighCard => format!("high card {}", high[0].kind),
+                rank::High::OnePair => format!("pair of {}s", high[0].kind),
+                rank::High::TwoPair => format!("two pairs, {}s and {}s", high[0].kind, high[1].kind),
+                rank::High::ThreeKind => format!("three of a kind, {}s", high[0].kind),
+                rank::High::Straight => format!("straight, {} to {}", self.value[4].kind, self.value[0].kind),
+                rank::High::Flush => format!("flush, {} high", high[0].kind),
+                rank::High::FullHouse => format!("full house, {}s full of {}s", high[0].kind, high[1].kind),
+                rank::High::FourKind => format!("four of a kind, {}s", high[0].kind),
+                rank::High::StraightFlush => format!("straight flush, {} to {}", self.value[4].kind, self.value[0].kind),
+            },
+
+            Some(Rank::Badugi(rank)) => match rank {
+                rank::Badugi::BadugiOne => format!("1-card badugi: {}", self.value[0]),
+                rank::Badugi::BadugiTwo => format!("2-card badugi: {} + {}", self.value[0], self.value[1]),
+                rank::Badugi::BadugiThree => format!("3-card badugi: {} + {} + {}", self.value[0], self.value[1], self.value[2]),
+                rank::Badugi::BadugiFour => format!("4-card badugi: {} + {} + {} + {}", self.value[0], self.value[1], self.value[2], self.value[3]),
+            },
+
+            Some(Rank::Low(_)) => {
+                let kinds: Vec<String> = self.value.iter().map(|card| card.kind.to_string()).collect();
+                format!("{}-low: {}", self.value[0].kind, kinds.join(" "))
+            },
+
+            None => "(none)".to_string(),
         }
     }
 }
 
-impl PartialOrd for Hand<'_> {
+impl PartialOrd for Hand {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        let mut result: Ordering = Equal;
-
-        match (&self.rank, &other.rank) {
-            (Some(rank1), Some(rank2)) =>
-                result = compare::compare_ranks(rank1, rank2),
-            (Some(rank1), None) =>
-                result = Greater,
-            (None, Some(rank2)) =>
-                result = Equal,
-            (None, None) =>
-                result = Less,
-        }
-        
+        let mut result: Ordering = match (&self.rank, &other.rank) {
+            (Some(rank1), Some(rank2)) => compare::compare_ranks(rank1, rank2),
+            (Some(_), None) => Greater,
+            (None, Some(_)) => Less,
+            (None, None) => Equal,
+        };
+
         if result != Equal {
             return Some(result);
         }
 
-        result = compare::compare_cards(self.meaningful_high(), other.meaningful_high());
-        if result != Equal {
-            return Some(result);
+        if let Some(Rank::Low(_)) = self.rank {
+            return Some(compare::compare_low(&self.value, &other.value));
         }
 
-        result = compare::compare_cards(&self.value, &other.value);
+        result = compare::compare_cards(self.meaningful_high(), other.meaningful_high());
         if result != Equal {
             return Some(result);
         }
 
-        result = compare::compare_cards(self.meaningful_kicker(), other.meaningful_kicker());
+        result = compare::compare_cards(&self.value, &other.value);
         if result != Equal {
             return Some(result);
         }
 
-        Some(Equal)
+        Some(compare::compare_cards(self.meaningful_kicker(), other.meaningful_kicker()))
     }
 }
 
-impl PartialEq for Hand<'_> {
+impl PartialEq for Hand {
     fn eq(&self, other: &Self) -> bool {
-        true
-    }
-}
-
-#[derive(Clone, Copy)]
-pub union Rank {
-    high: rank::High,
-    badugi: rank::Badugi,
-    low: rank::Low,
-}
-
-trait HighHand {
-    fn new_hand(&self, hand: Hand) -> &Self;
-    fn repetitions(&self) -> VecMap<Vec<&Cards>>;
-    fn is_straight_flush(&self) -> Option<Hand> {
-        match self.is_flush() {
-            Some(hand) => {
-                let mut hand2 = hand.clone();
-                hand2.cards = hand.value;
-                hand2.rank = Some(rank::straight_flush());
-                return self.new_hand(hand2).is_straight().or_else( ||
-                    self.is_full_house().or_else( ||
-                        self.is_straight()
-                    )
-                );
-            },
-            None =>
-                self.is_four_kind().or_else( ||
-                    self.is_full_house().or_else( ||
-                        self.is_straight()
-                    )
-                ),
-        }
-    }
-
-    fn is_four_kind(&self) -> Option<Hand> {
-        match self.repetitions().get(4) {
-            Some(card_group) => {
-                let cards = card_group.first().unwrap();
-                return Some(
-                    Hand { cards, value: cards, rank: Some(rank::four_kind()), high: Ok(cards), kicker: Err(true) }
-                )
-            },
-            None => None,
-        }
+        self.partial_cmp(other) == Some(Equal)
     }
+}
 
-    fn is_full_house(&self) -> Option<Hand> {
-        // match self.repetitions().get(3) {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rank {
+    High(rank::High),
+    Badugi(rank::Badugi),
+    Low(rank::Low),
+}
 
-        // }
-        // val (major, minor) = if (sets.size > 1) {
-        //     val Seq(minor, major, _*) = sets.sorted(CardOrdering.ByHead).reverse.take(2)
-        //     (major, minor)
-        //   } else {
-        //     paired.get(2) match {
-        //       case None ⇒ return None
-        //       case Some(pairs) ⇒
-        //         val minor = pairs.sorted(CardOrdering.ByHead).reverse.head
-        //         val major = sets.head
-        //         (major, minor)
-        //     }
-        //   }
-        //   hand(
-        //       value   = major ++ minor,
-        //       high    = Left(List(major head, minor head))
-        //     ).map(_.ranked(FullHouse))
-        None
-    }
+pub mod hand {
+    use super::{Card, Cards, Hand, Kind, Rank, rank, compare};
 
-    fn is_flush(&self) -> Option<Hand> { return None; }
+    fn combinations(cards: &[Card], k: usize) -> Vec<Cards> {
+        if k == 0 {
+            return vec![vec![]];
+        }
+        if cards.len() < k {
+            return vec![];
+        }
+        let mut result = vec![];
+        for (i, &card) in cards.iter().enumerate() {
+            for mut rest in combinations(&cards[i + 1..], k - 1) {
+                rest.insert(0, card);
+                result.push(rest);
+            }
+        }
+        result
+    }
 
-    fn is_straight(&self) -> Option<Hand> { return None; }
+    // exactly five cards
+    pub fn high(cards: &[Card]) -> Hand {
+        let mut sorted = cards.to_vec();
+        sorted.sort_by_key(|card| std::cmp::Reverse(card.kind as u8));
 
-    fn is_three_kind(&self) -> Option<Hand> { return None; }
+        let mut groups: Vec<Cards> = vec![];
+        for &card in sorted.iter() {
+            match groups.iter_mut().find(|group| group[0].kind == card.kind) {
+                Some(group) => group.push(card),
+                None => groups.push(vec![card]),
+            }
+        }
+        groups.sort_by(|a, b| b.len().cmp(&a.len()).then((b[0].kind as u8).cmp(&(a[0].kind as u8))));
+
+        let flush = sorted.iter().all(|card| card.suit == sorted[0].suit);
+        let wheel = groups.len() == 5
+            && sorted[0].kind == Kind::Ace
+            && sorted[1].kind == Kind::Five;
+        let straight = groups.len() == 5
+            && (wheel || sorted[0].kind as u8 - sorted[4].kind as u8 == 4);
+        if wheel {
+            sorted.rotate_left(1);
+        }
 
-    fn is_two_pair(&self) -> Option<Hand> { return None; }
+        let hand = |rank: rank::High, value: Cards, high: Cards, kicker: Cards| Hand {
+            cards: sorted.clone(),
+            value,
+            rank: Some(Rank::High(rank)),
+            high,
+            kicker,
+        };
+        let firsts = |groups: &[Cards]| -> Cards { groups.iter().map(|group| group[0]).collect() };
+        let rest = |groups: &[Cards]| -> Cards { groups.concat() };
+
+        match groups[0].len() {
+            _ if straight && flush => hand(rank::High::StraightFlush, sorted.clone(), vec![sorted[0]], vec![]),
+            4 => hand(rank::High::FourKind, groups[0].clone(), firsts(&groups[..1]), rest(&groups[1..])),
+            3 if groups[1].len() == 2 => hand(rank::High::FullHouse, sorted.clone(), firsts(&groups[..2]), vec![]),
+            _ if flush => hand(rank::High::Flush, sorted.clone(), sorted.clone(), vec![]),
+            _ if straight => hand(rank::High::Straight, sorted.clone(), vec![sorted[0]], vec![]),
+            3 => hand(rank::High::ThreeKind, groups[0].clone(), firsts(&groups[..1]), rest(&groups[1..])),
+            2 if groups[1].len() == 2 => hand(rank::High::TwoPair, rest(&groups[..2]), firsts(&groups[..2]), rest(&groups[2..])),
+            2 => hand(rank::High::OnePair, groups[0].clone(), firsts(&groups[..1]), rest(&groups[1..])),
+            _ => hand(rank::High::HighCard, vec![sorted[0]], vec![sorted[0]], sorted[1..].to_vec()),
+        }
+    }
 
-    fn is_one_pair(&self) -> Option<Hand> { return None; }
+    // five distinct kinds of eight or lower, ace plays low
+    pub fn low8(cards: &[Card]) -> Option<Hand> {
+        let mut sorted = cards.to_vec();
+        sorted.sort_by_key(|card| std::cmp::Reverse(compare::low_value(card.kind)));
+        let distinct = sorted.windows(2).all(|pair| pair[0].kind != pair[1].kind);
+        if !distinct || compare::low_value(sorted[0].kind) > 8 {
+            return None;
+        }
+        Some(Hand {
+            cards: sorted.clone(),
+            value: sorted,
+            rank: Some(Rank::Low(rank::Low::CompleteLow)),
+            high: vec![],
+            kicker: vec![],
+        })
+    }
+
+    fn candidates(pocket: &Cards, board: &Cards, omaha: bool) -> Vec<Cards> {
+        if omaha {
+            let mut result = vec![];
+            for own in combinations(pocket, 2) {
+                for shared in combinations(board, 3) {
+                    result.push([own.clone(), shared].concat());
+                }
+            }
+            result
+        } else {
+            combinations(&[pocket.clone(), board.clone()].concat(), 5)
+        }
+    }
 
-    fn is_high_card(&self) -> Option<Hand> { return None; }
+    // omaha hands use exactly two pocket cards and three from the board
+    pub fn best_high(pocket: &Cards, board: &Cards, omaha: bool) -> Option<Hand> {
+        candidates(pocket, board, omaha).iter()
+            .map(|cards| high(cards))
+            .reduce(|best, hand| if hand > best { hand } else { best })
+    }
 
-    fn is_high(&self) -> Option<Hand> { return None; }
+    pub fn best_low8(pocket: &Cards, board: &Cards, omaha: bool) -> Option<Hand> {
+        candidates(pocket, board, omaha).iter()
+            .filter_map(|cards| low8(cards))
+            .reduce(|best, hand| if hand > best { hand } else { best })
+    }
 }
 
 trait Badugi {
@@ -553,11 +696,13 @@ trait Badugi {
 }
 
 trait LowHand {
-    fn is_low() -> Option<Hand<'static>> { return None; }
-    fn is_gap_low() -> Option<Hand<'static>> { return None; }
+    fn is_low() -> Option<Hand> { return None; }
+    fn is_gap_low() -> Option<Hand> { return None; }
 }
 
 mod deck {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
     use rand::seq::SliceRandom;
     use rand::thread_rng;
     use super::{Cards, Card, Kind, Suit};
@@ -580,6 +725,12 @@ mod deck {
         cards
     }
 
+    // the same seed always gives the same order
+    pub fn shuffle_seeded(mut cards: Cards, seed: u64) -> Cards {
+        cards.shuffle(&mut StdRng::seed_from_u64(seed));
+        cards
+    }
+
     pub fn default() -> Cards { shuffle(new()) }
 }
 
@@ -601,6 +752,30 @@ impl Deck {
         }
     }
 
+    pub fn seeded(seed: u64) -> Self {
+        Deck {
+            cards: deck::shuffle_seeded(deck::new(), seed),
+            dealt: vec![],
+            discarded: vec![],
+            burned: vec![],
+        }
+    }
+
+    // every card in suit order, unshuffled
+    pub fn all() -> Cards {
+        deck::new()
+    }
+
+    // known cards dealt in the given order, gaps and the rest of the deck shuffled
+    pub fn arranged(order: &[Option<Card>], seed: u64) -> Self {
+        let mut rest = deck::shuffle_seeded(deck::new().into_iter()
+            .filter(|card| !order.contains(&Some(*card)))
+            .collect(), seed).into_iter();
+        let mut cards: Cards = order.iter().filter_map(|card| card.or_else(|| rest.next())).collect();
+        cards.extend(rest);
+        Deck { cards, dealt: vec![], discarded: vec![], burned: vec![] }
+    }
+
     pub fn drop(&mut self, n: usize) -> Cards {
         self.cards.drain(0..n).collect()
     }
@@ -684,6 +859,71 @@ mod tests {
         println!("{:?}", cards);
     }
 
+    #[test]
+    pub fn parse_cards_strictly() {
+        assert_eq!(card::parse_strict("Ah Td"), Ok(card::parse("AhTd")));
+        assert_eq!(card::parse_strict("Ah 1d"), Err(card::Error::Invalid("1d".to_string())));
+        assert_eq!(card::parse_strict("ah"), Err(card::Error::Invalid("ah".to_string())));
+        assert_eq!(card::parse_strict("Ah Td Ah"), Err(card::Error::Duplicate(card::new("A", "h"))));
+        assert_eq!(card::parse_joined("AhTd,9c 2s"), Ok(card::parse("Ah Td 9c 2s")));
+        assert_eq!(card::parse_joined("AhT"), Err(card::Error::Invalid("AhT".to_string())));
+        assert_eq!(card::parse_joined("AhAh"), Err(card::Error::Duplicate(card::new("A", "h"))));
+    }
+
+    #[test]
+    pub fn arranged_deck() {
+        let known = card::parse("AhKd");
+        let mut deck = Deck::arranged(&[Some(known[0]), None, Some(known[1])], 1);
+        let dealt = deck.deal(3);
+        assert_eq!((dealt[0], dealt[2]), (known[0], known[1]));
+        assert!(!known.contains(&dealt[1]));
+        assert_eq!(deck.deal(49).len(), 49);
+    }
+
+    #[test]
+    pub fn high_hands() {
+        let describe = |s: &str| hand::high(&card::parse(s)).description();
+        assert_eq!(describe("AhKhQhJhTh"), "straight flush, T to A");
+        assert_eq!(describe("9s9h9d9cAs"), "four of a kind, 9s");
+        assert_eq!(describe("3s3h3dKcKs"), "full house, 3s full of Ks");
+        assert_eq!(describe("2h7h9hJhKh"), "flush, K high");
+        assert_eq!(describe("Ah2d3c4s5h"), "straight, A to 5");
+        assert_eq!(describe("7s7h7dAcKs"), "three of a kind, 7s");
+        assert_eq!(describe("JsJhQdQcKs"), "two pairs, Qs and Js");
+        assert_eq!(describe("4s4hAdKc9s"), "pair of 4s");
+        assert_eq!(describe("2s4h6d8cTs"), "high card T");
+    }
+
+    #[test]
+    pub fn compare_hands() {
+        let high = |s: &str| hand::high(&card::parse(s));
+        assert!(high("Ah2d3c4s5h") < high("2h3d4c5s6h"));
+        assert!(high("AsAhKdQcJs") > high("AsAhKdQc9s"));
+        assert!(high("KsKhKdQcQs") > high("QsQhQdAcAs"));
+        assert!(high("2s4h6d8cTs") == high("2h4s6c8dTd"));
+        assert!(high("JsJhQdQc2s") < high("JsJhQdQc3s"));
+    }
+
+    #[test]
+    pub fn best_hands() {
+        let board = card::parse("AhKh7h2d9h");
+        let best = hand::best_high(&card::parse("QhTs"), &board, false).unwrap();
+        assert_eq!(best.description(), "flush, A high");
+        // omaha must use exactly two pocket cards
+        let best = hand::best_high(&card::parse("Qh3s4s5s"), &board, true).unwrap();
+        assert_eq!(best.description(), "high card A");
+
+        let low = hand::best_low8(&card::parse("As3s"), &card::parse("4h5d8cKhKd"), false).unwrap();
+        assert_eq!(low.description(), "8-low: 8 5 4 3 A");
+        assert!(hand::best_low8(&card::parse("AsKs"), &card::parse("4h5d9cKhKd"), false).is_none());
+    }
+
+    #[test]
+    pub fn seeded_deck() {
+        assert_eq!(Deck::seeded(7).deal(52), Deck::seeded(7).deal(52));
+        assert_ne!(Deck::seeded(7).deal(52), Deck::seeded(8).deal(52));
+    }
+
     #[test]
     pub fn shuffle_cards() {
         println!("{:?}", deck::default());
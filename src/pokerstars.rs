@@ -0,0 +1,841 @@
+use std::fmt;
+use std::fmt::Write;
+use std::time;
+use vec_map::VecMap;
+use crate::bet::Bet;
+use crate::chips::{self, Chips};
+use crate::game::{Limit, Name, Stake, street};
+use crate::history::{self, Entry, History};
+use crate::locale::Currency;
+use crate::poker::{Cards, card};
+use crate::pot::{Award, SidePot};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Syntax { line: usize, reason: String },
+    Unsupported(String),
+    Card { line: usize, error: card::Error },
+    Chips { line: usize, error: chips::Error },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax { line, reason } => write!(f, "line {}: {}", line, reason),
+            Self::Unsupported(what) => write!(f, "unsupported hand history: {}", what),
+            Self::Card { line, error } => write!(f, "line {}: {}", line, error),
+            Self::Chips { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+const HEADERS: [&str; 3] = ["PokerStars Hand #", "PokerStars Game #", "PokerStars Zoom Hand #"];
+
+pub fn game_name(name: Name) -> &'static str {
+    match name {
+        Name::Texas => "Hold'em",
+        Name::Omaha => "Omaha",
+        Name::Omaha8 => "Omaha Hi/Lo",
+        Name::Stud => "7 Card Stud",
+        Name::Stud8 => "7 Card Stud Hi/Lo",
+        Name::Razz => "Razz",
+        Name::London => "London Lowball",
+        Name::FiveCard => "5 Card Draw",
+        Name::Single27 => "2-7 Single Draw",
+        Name::Triple27 => "2-7 Triple Draw",
+        Name::Badugi => "Badugi",
+    }
+}
+
+pub fn limit_name(limit: Limit) -> &'static str {
+    match limit {
+        Limit::FixedLimit => "Limit",
+        Limit::PotLimit => "Pot Limit",
+        Limit::NoLimit => "No Limit",
+    }
+}
+
+fn street_name(street: street::Holdem) -> &'static str {
+    match street {
+        street::Holdem::Preflop => "HOLE CARDS",
+        street::Holdem::Flop => "FLOP",
+        street::Holdem::Turn => "TURN",
+        street::Holdem::River => "RIVER",
+    }
+}
+
+fn summary_street_name(street: street::Holdem) -> &'static str {
+    match street {
+        street::Holdem::Preflop => "before Flop",
+        street::Holdem::Flop => "on the Flop",
+        street::Holdem::Turn => "on the Turn",
+        street::Holdem::River => "on the River",
+    }
+}
+
+pub fn symbol(currency: Option<Currency>) -> &'static str {
+    match currency {
+        Some(Currency::USD | Currency::CAD | Currency::AUD) => "$",
+        Some(Currency::EUR) => "€",
+        Some(Currency::GBP) => "£",
+        _ => "",
+    }
+}
+
+// whole amounts drop the minor units, like the real thing
+pub fn money(amount: Chips, currency: Option<Currency>) -> String {
+    let formatted = amount.format(currency);
+    let trimmed = match formatted.split_once('.') {
+        Some((whole, fraction)) if fraction.chars().all(|c| c == '0') => whole.to_string(),
+        _ => formatted,
+    };
+    format!("{}{}", symbol(currency), trimmed)
+}
+
+const RUNS: [&str; 3] = ["FIRST", "SECOND", "THIRD"];
+
+fn cards(cards: &[crate::poker::Card]) -> String {
+    format!("[{}]", card::format(cards))
+}
+
+// "2026/10/18 12:00:00 UTC" without pulling in a date crate
+pub fn timestamp(time: time::SystemTime) -> String {
+    let seconds = time.duration_since(time::UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    let (days, rest) = (seconds / 86_400, seconds % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{}/{:02}/{:02} {:02}:{:02}:{:02} UTC", year, month, day, rest / 3600, rest % 3600 / 60, rest % 60)
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn post(entry: &Entry, money: &dyn Fn(Chips) -> String) -> String {
+    match entry.bet {
+        Bet::Ante(amount) => format!("posts the ante {}", money(amount)),
+        Bet::SmallBlind(amount) => format!("posts small blind {}", money(amount)),
+        Bet::BigBlind(amount) | Bet::GuestBlind(amount) => format!("posts big blind {}", money(amount)),
+        Bet::Straddle(amount) => format!("posts straddle {}", money(amount)),
+        Bet::BringIn(amount) => format!("brings in for {}", money(amount)),
+        _ => String::new(),
+    }
+}
+
+// the text hand history as seen by hero, whose hole cards are the only ones dealt face up
+pub fn write(history: &History, hero: Option<u8>) -> String {
+    let currency = history.stake.currency();
+    let money = |amount: Chips| money(amount, currency);
+    let name = |position: u8| history.player(position).unwrap_or("?").to_string();
+    let mut out = String::new();
+
+    let stakes = match currency {
+        Some(currency) => format!("{}/{} {:?}", money(history.stake.small_blind()), money(history.stake.big_blind()), currency),
+        None => format!("{}/{}", money(history.stake.small_blind()), money(history.stake.big_blind())),
+    };
+    let _ = writeln!(out, "PokerStars Hand #{}: {} {} ({}) - {}",
+        history.id, game_name(history.game), limit_name(history.limit), stakes, timestamp(history.time));
+    let _ = writeln!(out, "Table '{}' {}-max Seat #{} is the button", history.table, history.table_size, history.button + 1);
+    for seat in history.seats.iter() {
+        let _ = writeln!(out, "Seat {}: {} ({} in chips)", seat.position + 1, seat.player, money(seat.stack));
+    }
+
+    let mut put: VecMap<Chips> = VecMap::new();
+    for entry in history.posts.iter() {
+        let _ = writeln!(out, "{}: {}", name(entry.position), post(entry, &money));
+        if !entry.dead && !matches!(entry.bet, Bet::Ante(_)) {
+            *put.entry(entry.position as usize).or_insert(Chips::ZERO) += entry.bet.amount();
+        }
+    }
+
+    for street in history.streets.iter() {
+        match street.street {
+            street::Holdem::Preflop => {
+                let _ = writeln!(out, "*** HOLE CARDS ***");
+                if let Some(cards) = hero.and_then(|hero| history.pocket(hero).map(|cards| (hero, cards))) {
+                    let _ = writeln!(out, "Dealt to {} {}", name(cards.0), self::cards(cards.1));
+                }
+            },
+            street::Holdem::Flop => {
+                let _ = writeln!(out, "*** FLOP *** {}", cards(&street.cards));
+            },
+            _ => {
+                let before = history.streets.iter()
+                    .take_while(|previous| previous.street != street.street)
+                    .flat_map(|previous| previous.cards.iter().copied())
+                    .collect::<Vec<_>>();
+                let _ = writeln!(out, "*** {} *** {} {}", street_name(street.street), cards(&before), cards(&street.cards));
+            },
+        }
+        if street.street != street::Holdem::Preflop {
+            put.clear();
+        }
+
+        for entry in street.entries.iter() {
+            let player = name(entry.position);
+            if entry.timed_out {
+                let _ = writeln!(out, "{} has timed out", player);
+            }
+            let level = put.values().copied().max().unwrap_or_default();
+            let current = put.get(entry.position as usize).copied().unwrap_or_default();
+            let action = match entry.bet {
+                Bet::Fold => "folds".to_string(),
+                Bet::Check => "checks".to_string(),
+                Bet::Call(amount) => {
+                    put.insert(entry.position as usize, current + amount);
+                    format!("calls {}", money(amount))
+                },
+                Bet::Raise(to) => {
+                    put.insert(entry.position as usize, to);
+                    if level.is_zero() {
+                        format!("bets {}", money(to - current))
+                    } else {
+                        format!("raises {} to {}", money(to - level), money(to))
+                    }
+                },
+                _ => post(entry, &money),
+            };
+            let all_in = if entry.all_in { " and is all-in" } else { "" };
+            let _ = writeln!(out, "{}: {}{}", player, action, all_in);
+        }
+    }
+
+    if let Some((position, amount)) = history.uncalled {
+        let _ = writeln!(out, "Uncalled bet ({}) returned to {}", money(amount), name(position));
+    }
+
+    let pot_name = |side_pot: usize| match (history.side_pots.len(), side_pot) {
+        (0 | 1, _) => "pot".to_string(),
+        (_, 0) => "main pot".to_string(),
+        (_, index) => format!("side pot-{}", index),
+    };
+    if !history.shown.is_empty() {
+        let _ = writeln!(out, "*** SHOW DOWN ***");
+        for shown in history.shown.iter() {
+            // a card or two shown after the hand has no description
+            let description = if shown.description.is_empty() { String::new() } else { format!(" ({})", shown.description) };
+            let _ = writeln!(out, "{}: shows {}{}", name(shown.position), cards(&shown.cards), description);
+        }
+        let showdown: Vec<u8> = history.pockets.iter().map(|(position, _)| *position).filter(|&position| history.folded_on(position).is_none()).collect();
+        if showdown.len() > 1 {
+            for &position in showdown.iter().filter(|&&position| !history.shown.iter().any(|shown| shown.position == position)) {
+                let _ = writeln!(out, "{}: mucks hand", name(position));
+            }
+        }
+    }
+    for award in history.awards.iter() {
+        let _ = writeln!(out, "{} collected {} from {}", name(award.position as u8), money(award.amount), pot_name(award.side_pot));
+    }
+    if history.shown.is_empty() {
+        for award in history.awards.iter() {
+            let _ = writeln!(out, "{}: doesn't show hand", name(award.position as u8));
+        }
+    }
+
+    let _ = writeln!(out, "*** SUMMARY ***");
+    let mut total = format!("Total pot {}", money(history.total));
+    if history.side_pots.len() > 1 {
+        for (index, side_pot) in history.side_pots.iter().enumerate() {
+            let _ = write!(total, " {} {}.", if index == 0 { "Main pot".to_string() } else { format!("Side pot-{}", index) }, money(side_pot.amount));
+        }
+    }
+    let _ = writeln!(out, "{} | Rake {}", total, money(history.rake));
+    if history.runs.len() > 1 {
+        let _ = writeln!(out, "Hand was run {}", if history.runs.len() == 2 { "twice" } else { "three times" });
+        for (run, board) in RUNS.iter().zip(history.runs.iter()) {
+            let _ = writeln!(out, "{} Board {}", run, cards(board));
+        }
+    } else if !history.board.is_empty() {
+        let _ = writeln!(out, "Board {}", cards(&history.board));
+    }
+
+    for seat in history.seats.iter() {
+        let position = seat.position;
+        let mut line = format!("Seat {}: {}", position + 1, seat.player);
+        if position == history.button {
+            line.push_str(" (button)");
+        }
+        if Some(position) == history.small_blind {
+            line.push_str(" (small blind)");
+        } else if position == history.big_blind {
+            line.push_str(" (big blind)");
+        }
+        let won = history.won(position);
+        let shown = history.shown.iter().find(|shown| shown.position == position);
+        let outcome = match (history.folded_on(position), shown) {
+            (Some(street), _) => format!("folded {}", summary_street_name(street)),
+            (None, Some(shown)) if !won.is_zero() =>
+                format!("showed {} and won ({}) with {}", cards(&shown.cards), money(won), shown.description),
+            (None, Some(shown)) => format!("showed {} and lost with {}", cards(&shown.cards), shown.description),
+            (None, None) if !won.is_zero() => format!("collected ({})", money(won)),
+            (None, None) => "mucked".to_string(),
+        };
+        let _ = writeln!(out, "{} {}", line, outcome);
+    }
+
+    out
+}
+
+// "2026/10/18 12:00:00", the time zone is taken to be UTC
+pub fn parse_timestamp(s: &str) -> Option<time::SystemTime> {
+    let mut parts = s.split_whitespace();
+    let date: Vec<i64> = parts.next()?.split('/').map(|n| n.parse().ok()).collect::<Option<_>>()?;
+    let clock: Vec<u64> = parts.next()?.split(':').map(|n| n.parse().ok()).collect::<Option<_>>()?;
+    match (date.as_slice(), clock.as_slice()) {
+        (&[year, month, day], &[hours, minutes, seconds]) if (1..=12).contains(&month) && (1..=31).contains(&day) => {
+            let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+            let seconds = days * 86_400 + hours * 3600 + minutes * 60 + seconds;
+            Some(time::UNIX_EPOCH + time::Duration::from_secs(seconds))
+        },
+        _ => None,
+    }
+}
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// every hand in a file, hands start with the PokerStars header line
+pub fn parse(text: &str) -> Result<Vec<History>, Error> {
+    let mut hands: Vec<Vec<(usize, &str)>> = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if HEADERS.iter().any(|header| line.starts_with(header)) {
+            hands.push(vec![]);
+        }
+        if let Some(hand) = hands.last_mut() {
+            if !line.is_empty() {
+                hand.push((index + 1, line));
+            }
+        }
+    }
+    hands.iter().map(|lines| parse_hand(lines)).collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Seats,
+    Streets,
+    Showdown,
+    Summary,
+}
+
+struct Parser {
+    line: usize,
+    currency: Option<Currency>,
+    section: Section,
+    timed_out: Vec<u8>,
+    history: History,
+}
+
+fn parse_hand(lines: &[(usize, &str)]) -> Result<History, Error> {
+    let (&(line, header), rest) = lines.split_first().ok_or_else(|| Error::Unsupported("empty hand".to_string()))?;
+    let mut parser = Parser::header(line, header)?;
+    for &(line, text) in rest {
+        parser.line = line;
+        parser.parse_line(text)?;
+    }
+    parser.finish()
+}
+
+impl Parser {
+    fn syntax(&self, reason: &str) -> Error {
+        Error::Syntax { line: self.line, reason: reason.to_string() }
+    }
+
+    fn cards(&self, s: &str) -> Result<Cards, Error> {
+        let inner = s.trim().trim_start_matches('[').trim_end_matches(']');
+        card::parse_strict(inner).map_err(|error| Error::Card { line: self.line, error })
+    }
+
+    fn amount(&self, s: &str) -> Result<Chips, Error> {
+        let digits = s.trim().trim_start_matches(|c: char| !c.is_ascii_digit());
+        Chips::parse(digits, self.currency).map_err(|error| Error::Chips { line: self.line, error })
+    }
+
+    fn header(line: usize, header: &str) -> Result<Self, Error> {
+        let syntax = |reason: &str| Error::Syntax { line, reason: reason.to_string() };
+        let rest = HEADERS.iter().find_map(|prefix| header.strip_prefix(prefix)).ok_or_else(|| syntax("expected a hand header"))?;
+        let (id, rest) = rest.split_once(": ").ok_or_else(|| syntax("expected a hand number"))?;
+        let id = id.parse().map_err(|_| syntax("invalid hand number"))?;
+        if rest.starts_with("Tournament") {
+            return Err(Error::Unsupported("tournament hands".to_string()));
+        }
+
+        let mut names = Name::ALL.to_vec();
+        names.sort_by_key(|&name| std::cmp::Reverse(game_name(name).len()));
+        let game = names.into_iter().find(|&name| rest.starts_with(game_name(name)))
+            .ok_or_else(|| Error::Unsupported(rest.to_string()))?;
+        let rest = rest[game_name(game).len()..].trim_start();
+        let limit = [Limit::NoLimit, Limit::PotLimit, Limit::FixedLimit].into_iter()
+            .find(|&limit| rest.starts_with(limit_name(limit)))
+            .ok_or_else(|| syntax("expected a betting limit"))?;
+        let rest = rest[limit_name(limit).len()..].trim_start();
+
+        let (stakes, rest) = rest.strip_prefix('(').and_then(|rest| rest.split_once(')')).ok_or_else(|| syntax("expected stakes"))?;
+        let mut stakes = stakes.split_whitespace();
+        let blinds = stakes.next().ok_or_else(|| syntax("expected blinds"))?;
+        let currency = match stakes.next() {
+            Some(code) => Some(Currency::from_code(code).ok_or_else(|| Error::Unsupported(format!("currency {}", code)))?),
+            None if blinds.starts_with('$') => Some(Currency::USD),
+            None if blinds.starts_with('€') => Some(Currency::EUR),
+            None if blinds.starts_with('£') => Some(Currency::GBP),
+            None => None,
+        };
+        let time = rest.trim_start().strip_prefix("- ").and_then(parse_timestamp).ok_or_else(|| syntax("expected a timestamp"))?;
+
+        let mut parser = Parser {
+            line,
+            currency,
+            section: Section::Seats,
+            timed_out: vec![],
+            history: History {
+                id,
+                seed: None,
+                time,
+                game,
+                limit,
+                stake: Stake::default(),
+                table: String::new(),
+                table_size: 0,
+                button: 0,
+                small_blind: None,
+                big_blind: 0,
+                seats: vec![],
+                top_ups: vec![],
+                posts: vec![],
+                pockets: vec![],
+                streets: vec![],
+                board: vec![],
+                uncalled: None,
+                shown: vec![],
+                rabbit: vec![],
+                runs: vec![],
+                insurance: vec![],
+                side_pots: vec![],
+                awards: vec![],
+                total: Chips::ZERO,
+                rake: Chips::ZERO,
+            },
+        };
+        let (small_blind, big_blind) = blinds.split_once('/').ok_or_else(|| syntax("expected blinds"))?;
+        let (small_blind, big_blind) = (parser.amount(small_blind)?, parser.amount(big_blind)?);
+        let mut stake = Stake::new(big_blind).with_small_blind(small_blind).with_buy_in(Chips::ZERO, Chips(u64::MAX));
+        if let Some(currency) = currency {
+            stake = stake.with_currency(currency);
+        }
+        parser.history.stake = stake;
+        Ok(parser)
+    }
+
+    // the seat whose player name starts the line and is followed by the separator
+    fn player<'t>(&self, line: &'t str, separator: &str) -> Option<(u8, &'t str)> {
+        self.history.seats.iter()
+            .filter_map(|seat| line.strip_prefix(seat.player.as_str())
+                .and_then(|rest| rest.strip_prefix(separator))
+                .map(|rest| (seat.position, seat.player.len(), rest)))
+            .max_by_key(|&(_, length, _)| length)
+            .map(|(position, _, rest)| (position, rest))
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), Error> {
+        if let Some(marker) = line.strip_prefix("*** ") {
+            return self.street(marker);
+        }
+        if let Some(rest) = line.strip_prefix("Table '") {
+            return self.table(rest);
+        }
+        match self.section {
+            Section::Seats if line.starts_with("Seat ") && line.ends_with(" in chips)") => self.seat(line),
+            Section::Seats => match self.player(line, ": ") {
+                Some((position, action)) if action.starts_with("posts ") => self.post(position, action),
+                _ => Ok(()),
+            },
+            Section::Streets | Section::Showdown => self.action(line),
+            Section::Summary => self.summary(line),
+        }
+    }
+
+    fn table(&mut self, rest: &str) -> Result<(), Error> {
+        let (name, rest) = rest.rsplit_once("' ").ok_or_else(|| self.syntax("expected a table name"))?;
+        let size = rest.split_once("-max").and_then(|(size, _)| size.trim().parse().ok()).ok_or_else(|| self.syntax("expected a table size"))?;
+        let button: u8 = rest.split_once("Seat #")
+            .and_then(|(_, rest)| rest.split_whitespace().next())
+            .and_then(|button| button.parse().ok())
+            .filter(|&button| button > 0)
+            .ok_or_else(|| self.syntax("expected a button seat"))?;
+        self.history.table = name.to_string();
+        self.history.table_size = size;
+        self.history.button = button - 1;
+        Ok(())
+    }
+
+    fn seat(&mut self, line: &str) -> Result<(), Error> {
+        let (number, rest) = line["Seat ".len()..].split_once(": ").ok_or_else(|| self.syntax("expected a seat number"))?;
+        let number: u8 = number.parse().ok().filter(|&number| number > 0).ok_or_else(|| self.syntax("invalid seat number"))?;
+        let (player, chips) = rest.rsplit_once(" (").ok_or_else(|| self.syntax("expected a stack"))?;
+        let stack = self.amount(chips.trim_end_matches(" in chips)"))?;
+        self.history.seats.push(history::Seat { position: number - 1, player: player.to_string(), stack });
+        Ok(())
+    }
+
+    // the text has no per-action timing, everything happens when the hand started
+    fn entry(&self, position: u8, bet: Bet) -> Entry {
+        Entry { position, bet, all_in: false, timed_out: false, dead: false, time: self.history.time, time_used: time::Duration::ZERO }
+    }
+
+    fn post(&mut self, position: u8, action: &str) -> Result<(), Error> {
+        let (action, all_in) = match action.strip_suffix(" and is all-in") {
+            Some(action) => (action, true),
+            None => (action, false),
+        };
+        let (kind, amount) = action.rsplit_once(' ').ok_or_else(|| self.syntax("expected an amount"))?;
+        let amount = self.amount(amount)?;
+        let big_blind = self.history.stake.big_blind();
+        let mut entries = match kind {
+            "posts small blind" if self.history.small_blind.is_none() && self.history.posts.iter().all(|entry| !matches!(entry.bet, Bet::BigBlind(_))) => {
+                self.history.small_blind = Some(position);
+                vec![self.entry(position, Bet::SmallBlind(amount))]
+            },
+            "posts small blind" => vec![Entry { dead: true, ..self.entry(position, Bet::SmallBlind(amount)) }],
+            "posts big blind" if self.history.posts.iter().all(|entry| !matches!(entry.bet, Bet::BigBlind(_))) => {
+                self.history.big_blind = position;
+                vec![self.entry(position, Bet::BigBlind(amount))]
+            },
+            "posts big blind" => vec![self.entry(position, Bet::GuestBlind(amount))],
+            "posts small & big blinds" => vec![
+                self.entry(position, Bet::GuestBlind(amount.min(big_blind))),
+                Entry { dead: true, ..self.entry(position, Bet::SmallBlind(amount.saturating_sub(big_blind))) },
+            ],
+            "posts the ante" => vec![self.entry(position, Bet::Ante(amount))],
+            "posts straddle" => vec![self.entry(position, Bet::Straddle(amount))],
+            _ => return Err(self.syntax("unknown forced bet")),
+        };
+        if let Some(last) = entries.last_mut() {
+            last.all_in = all_in;
+        }
+        self.history.posts.extend(entries);
+        Ok(())
+    }
+
+    fn street(&mut self, marker: &str) -> Result<(), Error> {
+        let (name, cards) = marker.split_once(" ***").ok_or_else(|| self.syntax("expected a street"))?;
+        let street = match name {
+            "HOLE CARDS" => street::Holdem::Preflop,
+            "FLOP" => street::Holdem::Flop,
+            "TURN" => street::Holdem::Turn,
+            "RIVER" => street::Holdem::River,
+            "SHOW DOWN" => {
+                self.section = Section::Showdown;
+                return Ok(());
+            },
+            "SUMMARY" => {
+                self.section = Section::Summary;
+                return Ok(());
+            },
+            _ => return Err(Error::Unsupported(name.to_lowercase())),
+        };
+        let cards = match cards.rfind('[') {
+            Some(start) if street != street::Holdem::Preflop => self.cards(&cards[start..])?,
+            _ => vec![],
+        };
+        if cards.len() != street.board_cards() {
+            return Err(self.syntax("wrong number of board cards"));
+        }
+        self.history.board.extend(cards.iter().copied());
+        self.history.streets.push(history::Street { street, cards, entries: vec![] });
+        self.section = Section::Streets;
+        Ok(())
+    }
+
+    fn action(&mut self, line: &str) -> Result<(), Error> {
+        if let Some(rest) = line.strip_prefix("Uncalled bet (") {
+            let (amount, player) = rest.split_once(") returned to ").ok_or_else(|| self.syntax("expected an uncalled bet"))?;
+            let position = self.player(player, "").map(|(position, _)| position).ok_or_else(|| self.syntax("unknown player"))?;
+            self.history.uncalled = Some((position, self.amount(amount)?));
+            return Ok(());
+        }
+        if let Some((position, rest)) = self.player(line, " collected ") {
+            let (amount, pot) = rest.split_once(" from ").ok_or_else(|| self.syntax("expected a pot"))?;
+            let side_pot = match pot {
+                "pot" | "main pot" => 0,
+                "side pot" => 1,
+                pot => pot.strip_prefix("side pot-").and_then(|n| n.parse().ok()).ok_or_else(|| self.syntax("unknown pot"))?,
+            };
+            let amount = self.amount(amount)?;
+            self.history.awards.push(Award { position: position as usize, amount, side_pot });
+            return Ok(());
+        }
+        if let Some((position, _)) = self.player(line, " has timed out") {
+            self.timed_out.push(position);
+            return Ok(());
+        }
+        if let Some((position, action)) = self.player(line, ": ") {
+            if self.section == Section::Showdown || action.starts_with("shows ") || action.starts_with("mucks") || action.starts_with("doesn't show") {
+                return self.show(position, action);
+            }
+            if let Some(bet) = self.bet(action)? {
+                let timed_out = self.timed_out.contains(&position);
+                self.timed_out.retain(|&timed_out| timed_out != position);
+                let entry = Entry { all_in: action.ends_with(" and is all-in"), timed_out, ..self.entry(position, bet) };
+                let street = self.history.streets.last_mut().ok_or_else(|| Error::Syntax { line: self.line, reason: "action before the deal".to_string() })?;
+                street.entries.push(entry);
+            }
+            return Ok(());
+        }
+        if let Some(rest) = line.strip_prefix("Dealt to ") {
+            if let Some((position, cards)) = self.player(rest, " ") {
+                let cards = self.cards(cards)?;
+                self.history.pockets.push((position, cards));
+            }
+        }
+        Ok(())
+    }
+
+    fn bet(&self, action: &str) -> Result<Option<Bet>, Error> {
+        let action = action.strip_suffix(" and is all-in").unwrap_or(action);
+        let mut words = action.split_whitespace();
+        let bet = match (words.next(), words.next(), words.next(), words.next()) {
+            (Some("folds"), ..) => Bet::Fold,
+            (Some("checks"), None, ..) => Bet::Check,
+            (Some("calls"), Some(amount), None, _) => Bet::Call(self.amount(amount)?),
+            (Some("bets"), Some(amount), None, _) => Bet::Raise(self.amount(amount)?),
+            (Some("raises"), Some(_), Some("to"), Some(to)) => Bet::Raise(self.amount(to)?),
+            // table chatter and status changes
+            (Some("sits" | "is" | "said," | "leaves" | "joins" | "has" | "was"), ..) => return Ok(None),
+            _ => return Err(self.syntax("unknown action")),
+        };
+        Ok(Some(bet))
+    }
+
+    fn show(&mut self, position: u8, action: &str) -> Result<(), Error> {
+        if let Some(rest) = action.strip_prefix("shows ") {
+            let (cards, description) = match rest.split_once("] (") {
+                Some((cards, description)) => (cards, description.trim_end_matches(')')),
+                None => (rest, ""),
+            };
+            let cards = self.cards(cards)?;
+            self.pocket(position, &cards);
+            self.history.shown.push(history::Shown { position, cards, description: description.to_string() });
+        }
+        Ok(())
+    }
+
+    fn pocket(&mut self, position: u8, cards: &Cards) {
+        if self.history.pocket(position).is_none() {
+            self.history.pockets.push((position, cards.clone()));
+        }
+    }
+
+    fn summary(&mut self, line: &str) -> Result<(), Error> {
+        if let Some(rest) = line.strip_prefix("Total pot ") {
+            let (pots, rake) = rest.split_once(" | Rake ").ok_or_else(|| self.syntax("expected the rake"))?;
+            let mut pots = pots.split(". ").map(|pot| pot.trim_end_matches('.'));
+            let total = pots.next().unwrap_or_default();
+            let (total, main_pot) = match total.split_once(" Main pot ") {
+                Some((total, main_pot)) => (total, Some(main_pot)),
+                None => (total, None),
+            };
+            self.history.total = self.amount(total)?;
+            self.history.rake = self.amount(rake.split_whitespace().next().unwrap_or_default())?;
+            if let Some(main_pot) = main_pot {
+                let main_pot = self.amount(main_pot)?;
+                self.history.side_pots.push(SidePot { amount: main_pot, eligible: vec![] });
+                for side_pot in pots {
+                    let amount = side_pot.rsplit_once(' ').map(|(_, amount)| amount).ok_or_else(|| self.syntax("expected a side pot"))?;
+                    let amount = self.amount(amount)?;
+                    self.history.side_pots.push(SidePot { amount, eligible: vec![] });
+                }
+            }
+        } else if let Some(board) = line.strip_prefix("Board ") {
+            let board = self.cards(board)?;
+            if self.history.board.is_empty() {
+                self.history.board = board;
+            } else if self.history.board != board {
+                return Err(self.syntax("board does not match the streets"));
+            }
+        } else if let Some(board) = RUNS.iter().find_map(|run| line.strip_prefix(run)?.strip_prefix(" Board ")) {
+            // a hand run more than once lists every board, the first is the one on the streets
+            let board = self.cards(board)?;
+            if self.history.runs.is_empty() && self.history.board.is_empty() {
+                self.history.board = board.clone();
+            } else if self.history.runs.is_empty() && self.history.board != board {
+                return Err(self.syntax("the first board does not match the streets"));
+            }
+            self.history.runs.push(board);
+        } else if let Some(rest) = line.strip_prefix("Seat ") {
+            // mucked hands only show up in the summary
+            let position = rest.split_once(": ").and_then(|(number, _)| number.parse::<u8>().ok()).filter(|&number| number > 0);
+            let cards = rest.split_once(" mucked [").or_else(|| rest.split_once(" showed [")).map(|(_, cards)| cards);
+            if let (Some(number), Some(cards)) = (position, cards) {
+                let cards = cards.split_once(']').map_or(cards, |(cards, _)| cards);
+                let cards = self.cards(cards)?;
+                self.pocket(number - 1, &cards);
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<History, Error> {
+        if self.history.seats.is_empty() {
+            return Err(self.syntax("no seats"));
+        }
+        if !self.history.posts.iter().any(|entry| matches!(entry.bet, Bet::BigBlind(_))) {
+            return Err(self.syntax("no big blind"));
+        }
+        if self.history.streets.is_empty() {
+            return Err(self.syntax("no hole cards"));
+        }
+        Ok(self.history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Game, Stake};
+    use crate::player::Player;
+
+    #[test]
+    pub fn timestamps() {
+        let time = time::UNIX_EPOCH + time::Duration::from_secs(1_792_324_800);
+        assert_eq!(timestamp(time), "2026/10/18 12:00:00 UTC");
+        assert_eq!(timestamp(time::UNIX_EPOCH), "1970/01/01 00:00:00 UTC");
+    }
+
+    #[test]
+    pub fn amounts() {
+        assert_eq!(money(Chips(1000), Some(Currency::USD)), "$10");
+        assert_eq!(money(Chips(5), Some(Currency::USD)), "$0.05");
+        assert_eq!(money(Chips(1250), Some(Currency::EUR)), "€12.50");
+        assert_eq!(money(Chips(1250), None), "1250");
+    }
+
+    #[test]
+    pub fn write_hand() {
+        let stake = Stake::new(Chips(10)).with_currency(Currency::USD);
+        let mut game = Game::new(Name::Texas, Limit::NoLimit, stake, 2).with_name("Alpha");
+        game.join(Player::blank_player("player-1"), 0, Chips(1000)).unwrap();
+        game.join(Player::blank_player("player-2"), 1, Chips(1000)).unwrap();
+        game.start().unwrap();
+        game.act(0, Bet::Raise(Chips(30))).unwrap();
+        game.act(1, Bet::Call(Chips(20))).unwrap();
+        game.act(1, Bet::Check).unwrap();
+        game.act(0, Bet::Check).unwrap();
+        game.act(1, Bet::Raise(Chips(40))).unwrap();
+        game.act(0, Bet::Fold).unwrap();
+
+        let history = game.history().unwrap();
+        let text = write(history, Some(0));
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("PokerStars Hand #1: Hold'em No Limit ($0.05/$0.10 USD) - "));
+        assert_eq!(lines[1..8], [
+            "Table 'Alpha' 2-max Seat #1 is the button",
+            "Seat 1: player-1 ($10 in chips)",
+            "Seat 2: player-2 ($10 in chips)",
+            "player-1: posts small blind $0.05",
+            "player-2: posts big blind $0.10",
+            "*** HOLE CARDS ***",
+            &format!("Dealt to player-1 {}", cards(history.pocket(0).unwrap())),
+        ]);
+        assert_eq!(lines[8..11], ["player-1: raises $0.20 to $0.30", "player-2: calls $0.20", &format!("*** FLOP *** {}", cards(&history.board[..3]))]);
+        assert_eq!(lines[13], format!("*** TURN *** {} {}", cards(&history.board[..3]), cards(&history.board[3..4])));
+        assert_eq!(lines[14..], [
+            "player-2: bets $0.40",
+            "player-1: folds",
+            "Uncalled bet ($0.40) returned to player-2",
+            "player-2 collected $0.60 from pot",
+            "player-2: doesn't show hand",
+            "*** SUMMARY ***",
+            "Total pot $0.60 | Rake $0",
+            &format!("Board {}", cards(&history.board)),
+            "Seat 1: player-1 (button) (small blind) folded on the Turn",
+            "Seat 2: player-2 (big blind) collected ($0.60)",
+        ]);
+    }
+
+    #[test]
+    pub fn parse_written_hand() {
+        let mut game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)).with_currency(Currency::EUR), 6).with_name("Beta");
+        game.join(Player::blank_player("player 1"), 1, Chips(1000)).unwrap();
+        game.join(Player::blank_player("player 2"), 3, Chips(500)).unwrap();
+        game.join(Player::blank_player("player 3"), 5, Chips(800)).unwrap();
+        game.start().unwrap();
+        while let Some(position) = game.to_act() {
+            let range = game.range(position).unwrap();
+            let bet = match range.max_raise {
+                Some(max_raise) => Bet::Raise(max_raise),
+                None => Bet::Call(range.call),
+            };
+            game.act(position, bet).unwrap();
+        }
+
+        let history = game.history().unwrap();
+        let text = write(history, Some(1));
+        let parsed = parse(&text).unwrap().remove(0);
+        assert_eq!((parsed.id, timestamp(parsed.time), parsed.table.as_str(), parsed.table_size), (history.id, timestamp(history.time), "Beta", 6));
+        assert_eq!((parsed.button, parsed.small_blind, parsed.big_blind), (history.button, history.small_blind, history.big_blind));
+        // the text format has no timings
+        let entries = |entries: &[Entry]| entries.iter().map(|entry| (entry.position, entry.bet, entry.all_in, entry.timed_out, entry.dead)).collect::<Vec<_>>();
+        let streets = |history: &History| history.streets.iter().map(|street| (street.street, street.cards.clone(), entries(&street.entries))).collect::<Vec<_>>();
+        assert_eq!((&parsed.seats, entries(&parsed.posts)), (&history.seats, entries(&history.posts)));
+        assert_eq!((streets(&parsed), &parsed.board), (streets(history), &history.board));
+        assert_eq!((parsed.uncalled, parsed.awards), (history.uncalled, history.awards.clone()));
+        assert_eq!((parsed.total, parsed.rake), (history.total, history.rake));
+        assert_eq!(parsed.shown, history.shown);
+        assert_eq!(write(&parse(&text).unwrap()[0], Some(1)), text);
+    }
+
+    #[test]
+    pub fn run_twice() {
+        let mut game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 2).with_name("Alpha").with_run_it(2);
+        game.join(Player::blank_player("player-1"), 0, Chips(1000)).unwrap();
+        game.join(Player::blank_player("player-2"), 1, Chips(1000)).unwrap();
+        game.set_run_it(0, 2).unwrap();
+        game.set_run_it(1, 2).unwrap();
+        game.start().unwrap();
+        game.act(0, Bet::Raise(Chips(1000))).unwrap();
+        game.act(1, Bet::Call(Chips(990))).unwrap();
+
+        let history = game.history().unwrap();
+        let text = write(history, None);
+        assert!(text.contains("Hand was run twice\nFIRST Board ["));
+        let parsed = parse(&text).unwrap().remove(0);
+        assert_eq!((&parsed.runs, &parsed.board), (&history.runs, &history.board));
+        assert_eq!(write(&parsed, None), text);
+    }
+
+    #[test]
+    pub fn parse_errors() {
+        let hand = |line: &str| format!("PokerStars Hand #1: Hold'em No Limit (5/10) - 2026/10/18 12:00:00 UTC\n\
+            Table 'Alpha' 2-max Seat #1 is the button\n\
+            Seat 1: a (1000 in chips)\nSeat 2: b (1000 in chips)\n\
+            a: posts small blind 5\nb: posts big blind 10\n*** HOLE CARDS ***\n{}\n", line);
+        assert!(parse(&hand("a: folds")).is_ok());
+        assert_eq!(parse(&hand("Dealt to a [Ah 1c]")).err(), Some(Error::Card { line: 8, error: card::Error::Invalid("1c".to_string()) }));
+        assert_eq!(parse(&hand("Dealt to a [Ah Ah]")).err(), Some(Error::Card { line: 8, error: card::Error::Duplicate(card::new("A", "h")) }));
+        assert_eq!(parse(&hand("a: dances")).err(), Some(Error::Syntax { line: 8, reason: "unknown action".to_string() }));
+        assert_eq!(parse(&hand("a: calls ten")).err(), Some(Error::Chips { line: 8, error: chips::Error::Invalid("not a number") }));
+        assert_eq!(parse(&hand("*** FIRST FLOP *** [Ah Kd 2c]")).err(), Some(Error::Unsupported("first flop".to_string())));
+        assert_eq!(
+            parse("PokerStars Hand #2: Tournament #3, $1+$0.10 USD Hold'em No Limit - Level I (10/20)").err(),
+            Some(Error::Unsupported("tournament hands".to_string())),
+        );
+    }
+}
@@ -1,2 +1,188 @@
-#[derive(Debug)]
-pub struct Pot {}
+use vec_map::VecMap;
+use crate::chips::{self, Chips};
+use crate::rake::Rake;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SidePot {
+    pub amount: Chips,
+    pub eligible: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Award {
+    pub position: usize,
+    pub amount: Chips,
+    pub side_pot: usize,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Pot {
+    contributions: VecMap<Chips>,
+    folded: Vec<usize>,
+    awards: Vec<Award>,
+    rake: Option<Rake>,
+}
+
+impl Pot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, position: usize, amount: Chips) -> Result<(), chips::Error> {
+        let contribution = self.contributions.entry(position).or_insert(Chips::ZERO);
+        *contribution = contribution.checked_add(amount)?;
+        Ok(())
+    }
+
+    pub fn fold(&mut self, position: usize) {
+        if !self.folded.contains(&position) {
+            self.folded.push(position);
+        }
+    }
+
+    pub fn is_folded(&self, position: usize) -> bool {
+        self.folded.contains(&position)
+    }
+
+    pub fn contribution(&self, position: usize) -> Chips {
+        self.contributions.get(position).copied().unwrap_or_default()
+    }
+
+    pub fn total(&self) -> Chips {
+        self.contributions.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total().is_zero()
+    }
+
+    // the part of the biggest contribution nobody else matched
+    pub fn uncalled(&self) -> Option<(usize, Chips)> {
+        let mut top: Option<(usize, Chips)> = None;
+        let mut second = Chips::ZERO;
+        for (position, &amount) in self.contributions.iter() {
+            match top {
+                Some((_, top_amount)) if amount <= top_amount => second = second.max(amount),
+                Some((_, top_amount)) => {
+                    second = top_amount;
+                    top = Some((position, amount));
+                }
+                None => top = Some((position, amount)),
+            }
+        }
+        match top {
+            Some((position, amount)) if amount > second => Some((position, amount - second)),
+            _ => None,
+        }
+    }
+
+    pub fn return_uncalled(&mut self) -> Option<(usize, Chips)> {
+        let (position, amount) = self.uncalled()?;
+        if let Some(contribution) = self.contributions.get_mut(position) {
+            *contribution -= amount;
+        }
+        Some((position, amount))
+    }
+
+    pub fn side_pots(&self) -> Vec<SidePot> {
+        let live: Vec<(usize, Chips)> = self.contributions.iter()
+            .filter(|(position, _)| !self.folded.contains(position))
+            .map(|(position, &amount)| (position, amount))
+            .collect();
+
+        let mut levels: Vec<Chips> = live.iter().map(|&(_, amount)| amount).filter(|amount| !amount.is_zero()).collect();
+        levels.sort_unstable();
+        levels.dedup();
+
+        let mut side_pots = vec![];
+        let mut previous = Chips::ZERO;
+        for &level in levels.iter() {
+            let amount = self.contributions.values()
+                .map(|&contribution| contribution.min(level) - contribution.min(previous))
+                .sum();
+            let eligible = live.iter()
+                .filter(|&&(_, contribution)| contribution >= level)
+                .map(|&(position, _)| position)
+                .collect();
+            side_pots.push(SidePot { amount, eligible });
+            previous = level;
+        }
+
+        // folded chips above the last live level still belong to the last pot
+        let dead: Chips = self.contributions.values().map(|&contribution| contribution.saturating_sub(previous)).sum();
+        if !dead.is_zero() {
+            match side_pots.last_mut() {
+                Some(side_pot) => side_pot.amount += dead,
+                None => side_pots.push(SidePot { amount: dead, eligible: vec![] }),
+            }
+        }
+
+        side_pots
+    }
+
+    // side pots with the rake taken out, starting from the main pot
+    pub fn net_side_pots(&self) -> Vec<SidePot> {
+        let mut side_pots = self.side_pots();
+        let mut rake = self.rake.as_ref().map_or(Chips::ZERO, |rake| rake.amount);
+        for side_pot in side_pots.iter_mut() {
+            let taken = rake.min(side_pot.amount);
+            side_pot.amount -= taken;
+            rake -= taken;
+        }
+        side_pots
+    }
+
+    pub fn take_rake(&mut self, rake: Rake) {
+        self.rake = Some(rake);
+    }
+
+    pub fn rake(&self) -> Option<&Rake> {
+        self.rake.as_ref()
+    }
+
+    pub fn award(&mut self, position: usize, amount: Chips, side_pot: usize) {
+        self.awards.push(Award { position, amount, side_pot });
+    }
+
+    pub fn awards(&self) -> &Vec<Award> {
+        &self.awards
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn side_pots() {
+        let mut pot = Pot::new();
+        pot.add(0, Chips(50)).unwrap();
+        pot.add(1, Chips(100)).unwrap();
+        pot.add(2, Chips(100)).unwrap();
+        pot.add(3, Chips(30)).unwrap();
+        pot.fold(3);
+
+        let side_pots = pot.side_pots();
+        assert_eq!(side_pots, vec![
+            SidePot { amount: Chips(180), eligible: vec![0, 1, 2] },
+            SidePot { amount: Chips(100), eligible: vec![1, 2] },
+        ]);
+        assert_eq!(pot.total(), Chips(280));
+    }
+
+    #[test]
+    pub fn uncalled_bet() {
+        let mut pot = Pot::new();
+        pot.add(0, Chips(20)).unwrap();
+        pot.add(1, Chips(100)).unwrap();
+        pot.fold(0);
+
+        assert_eq!(pot.return_uncalled(), Some((1, Chips(80))));
+        assert_eq!(pot.total(), Chips(40));
+        assert_eq!(pot.uncalled(), None);
+    }
+}
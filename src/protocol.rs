@@ -0,0 +1,1150 @@
+use serde::{Deserialize, Serialize};
+use thrift::protocol::{
+    TBinaryInputProtocol, TBinaryOutputProtocol, TCompactInputProtocol, TCompactOutputProtocol, TFieldIdentifier,
+    TInputProtocol, TListIdentifier, TOutputProtocol, TSerializable, TStructIdentifier, TType,
+};
+use thrift::{new_protocol_error, ProtocolErrorKind};
+use crate::bet::Bet;
+use crate::chips::Chips;
+use crate::game::{self, street, Limit, Name, Stake};
+use crate::locale::Currency;
+use crate::poker::{bits, Card, Cards};
+use crate::record::{self, Record, Visibility};
+use crate::seat::Status;
+
+// the wire format of src/thrift/protocol.thrift, field ids and enum values must match it
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Binary,
+    Compact,
+}
+
+pub fn encode<T: TSerializable>(value: &T, encoding: Encoding) -> thrift::Result<Vec<u8>> {
+    let mut buffer = vec![];
+    match encoding {
+        Encoding::Binary => value.write_to_out_protocol(&mut TBinaryOutputProtocol::new(&mut buffer, true))?,
+        Encoding::Compact => value.write_to_out_protocol(&mut TCompactOutputProtocol::new(&mut buffer))?,
+    }
+    Ok(buffer)
+}
+
+pub fn decode<T: TSerializable>(mut bytes: &[u8], encoding: Encoding) -> thrift::Result<T> {
+    match encoding {
+        Encoding::Binary => T::read_from_in_protocol(&mut TBinaryInputProtocol::new(&mut bytes, true)),
+        Encoding::Compact => T::read_from_in_protocol(&mut TCompactInputProtocol::new(&mut bytes)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct User {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Game {
+    pub name: Name,
+    pub limit: Limit,
+    pub stake: Stake,
+    pub table_size: u8,
+    pub table: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Seat {
+    pub position: u8,
+    pub status: Status,
+    pub player: Option<User>,
+    pub stack: Option<Chips>,
+    pub put: Chips,
+    pub online: bool,
+    // none for cards the viewer can't see
+    pub cards: Vec<Option<Card>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Table {
+    pub game: Game,
+    pub button: u8,
+    pub seats: Vec<Seat>,
+    pub board: Cards,
+    pub pot: Chips,
+    pub street: Option<street::Holdem>,
+    pub to_act: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    HandStarted { id: u64, button: u8, seats: Vec<record::Seat> },
+    // no position for the board
+    CardsDealt { street: street::Holdem, position: Option<u8>, cards: Vec<Option<Card>> },
+    Action(record::Action),
+    CardsShown { position: u8, cards: Cards },
+    Pot(record::Pot),
+    HandFinished { id: u64, total: Chips, rake: Chips },
+    // the rest of the board after a hand that ended early
+    Rabbit { cards: Cards },
+    // another board for a hand run more than once, the first run is the board dealt as usual
+    RunOut { run: u8, board: Cards },
+}
+
+// who a table or an event is shown to, admins see every card
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Viewer {
+    Seat(u8),
+    Spectator,
+    Admin,
+}
+
+impl Viewer {
+    // whether the pocket cards at position are face up for the viewer
+    pub fn sees(self, position: u8) -> bool {
+        match self {
+            Viewer::Seat(seat) => seat == position,
+            Viewer::Spectator => false,
+            Viewer::Admin => true,
+        }
+    }
+}
+
+// the seat of a player, if any
+impl From<Option<u8>> for Viewer {
+    fn from(position: Option<u8>) -> Self {
+        position.map_or(Viewer::Spectator, Viewer::Seat)
+    }
+}
+
+fn mask(cards: &[Card], visible: bool) -> Vec<Option<Card>> {
+    cards.iter().map(|&card| Some(card).filter(|_| visible)).collect()
+}
+
+impl Table {
+    // the table as viewer sees it: their own pocket cards and whatever was shown down,
+    // folded and mucked hands stay face down even after the hand
+    pub fn view(game: &game::Game, viewer: Viewer) -> Self {
+        let table = game.table();
+        let shown = |position: u8| {
+            let history = game.history().filter(|_| !game.is_hand_in_progress());
+            history.and_then(|history| history.shown.iter().find(|shown| shown.position == position)).map_or(&[][..], |shown| &shown.cards[..])
+        };
+        Table {
+            game: Game {
+                name: game.game(),
+                limit: game.limit(),
+                stake: game.stake().clone(),
+                table_size: table.size(),
+                table: game.name().to_string(),
+            },
+            button: table.button(),
+            seats: table.seats().iter()
+                .map(|seat| {
+                    // a player who took the seat since doesn't get the cards dealt to it
+                    let dealt = game.history().and_then(|history| history.player(seat.position())) == seat.player().map(|player| &*player.name);
+                    let pocket = game.pocket_cards(seat.position()).filter(|_| dealt).map_or(&[][..], |cards| cards);
+                    Seat {
+                        position: seat.position(),
+                        status: seat.status(),
+                        player: seat.player().map(|player| User { name: player.name.to_string() }),
+                        stack: seat.stack(),
+                        put: seat.put(),
+                        online: seat.is_online(),
+                        cards: pocket.iter()
+                            .map(|&card| Some(card).filter(|card| viewer.sees(seat.position()) || shown(seat.position()).contains(card)))
+                            .collect(),
+                    }
+                })
+                .collect(),
+            board: game.board().clone(),
+            pot: table.pot().total(),
+            street: game.street(),
+            to_act: game.to_act(),
+        }
+    }
+}
+
+impl Event {
+    // the event as viewer may see it
+    pub fn masked(&self, viewer: Viewer) -> Self {
+        match self {
+            Event::CardsDealt { street, position: Some(position), cards } if !viewer.sees(*position) => {
+                Event::CardsDealt { street: *street, position: Some(*position), cards: vec![None; cards.len()] }
+            }
+            event => event.clone(),
+        }
+    }
+}
+
+// follows a hand as it's played and hands out the events nobody has seen yet, unmasked
+#[derive(Debug, Default)]
+pub struct Feed {
+    hand: Option<u64>,
+    actions: usize,
+    boards: usize,
+    finished: bool,
+    shown: Vec<record::Shown>,
+    rabbit: bool,
+}
+
+impl Feed {
+    pub fn update(&mut self, record: &Record, finished: bool) -> Vec<Event> {
+        let mut events = vec![];
+        let pockets = record.cards.iter().filter_map(|dealt| Some((dealt.position?, dealt)));
+        if self.hand != Some(record.id) {
+            *self = Feed { hand: Some(record.id), ..Feed::default() };
+            events.push(Event::HandStarted { id: record.id, button: record.button, seats: record.seats.clone() });
+            events.extend(pockets.clone()
+                .map(|(position, dealt)| Event::CardsDealt { street: dealt.street, position: Some(position), cards: mask(&dealt.cards, true) }));
+        }
+        if self.finished {
+            events.extend(self.shows(record));
+            events.extend(self.rabbit(record));
+            return events;
+        }
+
+        let board = |dealt: &record::Dealt| Event::CardsDealt { street: dealt.street, position: None, cards: mask(&dealt.cards, true) };
+        let mut boards = record.cards.iter().filter(|dealt| dealt.position.is_none()).skip(self.boards).peekable();
+        for action in record.actions.iter().skip(self.actions) {
+            if let Some(dealt) = boards.next_if(|dealt| dealt.street == action.street) {
+                events.push(board(dealt));
+                self.boards += 1;
+            }
+            events.push(Event::Action(action.clone()));
+            self.actions += 1;
+        }
+        // dealt but not acted on yet, or nobody could act after an all-in
+        for dealt in boards {
+            events.push(board(dealt));
+            self.boards += 1;
+        }
+
+        if finished {
+            // records from before shows were kept on their own only mark the pockets public
+            if record.shown.is_empty() {
+                events.extend(pockets
+                    .filter(|(_, dealt)| dealt.visibility == Visibility::Public)
+                    .map(|(position, dealt)| Event::CardsShown { position, cards: dealt.cards.clone() }));
+            }
+            events.extend(record.runs.iter().enumerate().skip(1)
+                .map(|(run, board)| Event::RunOut { run: run as u8 + 1, board: board.clone() }));
+            events.extend(self.shows(record));
+            events.extend(record.pots.iter().cloned().map(Event::Pot));
+            events.push(Event::HandFinished { id: record.id, total: record.total, rake: record.rake });
+            events.extend(self.rabbit(record));
+            self.finished = true;
+        }
+        events
+    }
+
+    // cards turned up since the last update, at showdown or once the hand is over
+    fn shows(&mut self, record: &Record) -> Vec<Event> {
+        let shown: Vec<record::Shown> = record.shown.iter().filter(|shown| !self.shown.contains(shown)).cloned().collect();
+        self.shown.extend(shown.iter().cloned());
+        shown.into_iter().map(|shown| Event::CardsShown { position: shown.position, cards: shown.cards }).collect()
+    }
+
+    fn rabbit(&mut self, record: &Record) -> Option<Event> {
+        if self.rabbit || record.rabbit.is_empty() {
+            return None;
+        }
+        self.rabbit = true;
+        Some(Event::Rabbit { cards: record.rabbit.clone() })
+    }
+}
+
+// replays a recorded hand as the events viewer would have received
+pub fn events(record: &Record, viewer: Viewer) -> Vec<Event> {
+    Feed::default().update(record, true).iter().map(|event| event.masked(viewer)).collect()
+}
+
+fn invalid(message: String) -> thrift::Error {
+    new_protocol_error(ProtocolErrorKind::InvalidData, message)
+}
+
+fn required<T>(value: Option<T>, name: &str) -> thrift::Result<T> {
+    value.ok_or_else(|| invalid(format!("missing required field {}", name)))
+}
+
+// a value that can sit in a struct field or a list
+trait Field: Sized {
+    const TYPE: TType;
+
+    fn write(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()>;
+
+    fn read(i: &mut dyn TInputProtocol) -> thrift::Result<Self>;
+}
+
+impl Field for bool {
+    const TYPE: TType = TType::Bool;
+
+    fn write(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        o.write_bool(*self)
+    }
+
+    fn read(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        i.read_bool()
+    }
+}
+
+impl Field for u8 {
+    const TYPE: TType = TType::I08;
+
+    fn write(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        o.write_i8(*self as i8)
+    }
+
+    fn read(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        Ok(i.read_i8()? as u8)
+    }
+}
+
+impl Field for i32 {
+    const TYPE: TType = TType::I32;
+
+    fn write(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        o.write_i32(*self)
+    }
+
+    fn read(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        i.read_i32()
+    }
+}
+
+impl Field for u64 {
+    const TYPE: TType = TType::I64;
+
+    fn write(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        o.write_i64(*self as i64)
+    }
+
+    fn read(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        Ok(i.read_i64()? as u64)
+    }
+}
+
+impl Field for Chips {
+    const TYPE: TType = TType::I64;
+
+    fn write(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        self.0.write(o)
+    }
+
+    fn read(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        Ok(Chips(u64::read(i)?))
+    }
+}
+
+impl Field for String {
+    const TYPE: TType = TType::String;
+
+    fn write(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        o.write_string(self)
+    }
+
+    fn read(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        i.read_string()
+    }
+}
+
+// hidden cards go over the wire with only the hidden bit set
+impl Field for Option<Card> {
+    const TYPE: TType = TType::I08;
+
+    fn write(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        self.map_or(bits::hide(0), |card| card.to_u8()).write(o)
+    }
+
+    fn read(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        let card = u8::read(i)?;
+        if bits::is_hidden(card) {
+            return Ok(None);
+        }
+        Card::from_u8(card).map(Some).ok_or_else(|| invalid(format!("invalid card {}", card)))
+    }
+}
+
+impl Field for Card {
+    const TYPE: TType = TType::I08;
+
+    fn write(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        Some(*self).write(o)
+    }
+
+    fn read(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        Option::<Card>::read(i)?.ok_or_else(|| invalid("hidden card where a known one is required".to_string()))
+    }
+}
+
+impl<T: Field> Field for Vec<T> {
+    const TYPE: TType = TType::List;
+
+    fn write(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        o.write_list_begin(&TListIdentifier::new(T::TYPE, self.len() as i32))?;
+        for value in self.iter() {
+            value.write(o)?;
+        }
+        o.write_list_end()
+    }
+
+    fn read(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        let list = i.read_list_begin()?;
+        let values = (0..list.size).map(|_| T::read(i)).collect::<thrift::Result<_>>()?;
+        i.read_list_end()?;
+        Ok(values)
+    }
+}
+
+macro_rules! enums {
+    ($($ty:ty { $($variant:path = $value:literal,)* })*) => {$(
+        impl Field for $ty {
+            const TYPE: TType = TType::I32;
+
+            fn write(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+                o.write_i32(match *self { $($variant => $value,)* })
+            }
+
+            fn read(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+                match i.read_i32()? {
+                    $($value => Ok($variant),)*
+                    value => Err(invalid(format!("unknown {} {}", stringify!($ty), value))),
+                }
+            }
+        }
+    )*};
+}
+
+enums! {
+    Name {
+        Name::Texas = 1, Name::Omaha = 2, Name::Omaha8 = 3, Name::Stud = 4, Name::Stud8 = 5, Name::Razz = 6,
+        Name::London = 7, Name::FiveCard = 8, Name::Single27 = 9, Name::Triple27 = 10, Name::Badugi = 11,
+    }
+    Limit {
+        Limit::FixedLimit = 1, Limit::PotLimit = 2, Limit::NoLimit = 3,
+    }
+    street::Holdem {
+        street::Holdem::Preflop = 1, street::Holdem::Flop = 2, street::Holdem::Turn = 3, street::Holdem::River = 4,
+    }
+    Status {
+        Status::Empty = 1, Status::Reserved = 2, Status::Ready = 3, Status::WaitBigBlind = 4, Status::PostBigBlind = 5,
+        Status::Playing = 6, Status::AutoPlaying = 7, Status::AllIn = 8, Status::Betting = 9, Status::Folding = 10,
+        Status::SittingOut = 11, Status::Idle = 12, Status::Away = 13, Status::Gone = 14,
+    }
+}
+
+macro_rules! structs {
+    ($($ty:ty),*) => {$(
+        impl Field for $ty {
+            const TYPE: TType = TType::Struct;
+
+            fn write(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+                self.write_to_out_protocol(o)
+            }
+
+            fn read(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+                Self::read_from_in_protocol(i)
+            }
+        }
+    )*};
+}
+
+structs!(Bet, Stake, User, Game, Seat, Table, Event, record::Seat, record::Action, record::Winner, record::Pot);
+
+fn write_struct(
+    o: &mut dyn TOutputProtocol,
+    name: &str,
+    fields: impl FnOnce(&mut dyn TOutputProtocol) -> thrift::Result<()>,
+) -> thrift::Result<()> {
+    o.write_struct_begin(&TStructIdentifier::new(name))?;
+    fields(o)?;
+    o.write_field_stop()?;
+    o.write_struct_end()
+}
+
+fn field<T: Field>(o: &mut dyn TOutputProtocol, name: &str, id: i16, value: &T) -> thrift::Result<()> {
+    o.write_field_begin(&TFieldIdentifier::new(name, T::TYPE, id))?;
+    value.write(o)?;
+    o.write_field_end()
+}
+
+fn optional<T: Field>(o: &mut dyn TOutputProtocol, name: &str, id: i16, value: Option<&T>) -> thrift::Result<()> {
+    value.map_or(Ok(()), |value| field(o, name, id, value))
+}
+
+// a struct field written in place, for the union members that have no rust type of their own
+fn nested(
+    o: &mut dyn TOutputProtocol,
+    name: &str,
+    id: i16,
+    struct_name: &str,
+    fields: impl FnOnce(&mut dyn TOutputProtocol) -> thrift::Result<()>,
+) -> thrift::Result<()> {
+    o.write_field_begin(&TFieldIdentifier::new(name, TType::Struct, id))?;
+    write_struct(o, struct_name, fields)?;
+    o.write_field_end()
+}
+
+// hands every field id to read, which returns false for the ones it doesn't know;
+// those are skipped so older readers accept what newer writers add
+fn read_struct(
+    i: &mut dyn TInputProtocol,
+    mut read: impl FnMut(&mut dyn TInputProtocol, i16) -> thrift::Result<bool>,
+) -> thrift::Result<()> {
+    i.read_struct_begin()?;
+    loop {
+        let ident = i.read_field_begin()?;
+        if ident.field_type == TType::Stop {
+            break;
+        }
+        let known = match ident.id {
+            Some(id) => read(i, id)?,
+            None => false,
+        };
+        if !known {
+            i.skip(ident.field_type)?;
+        }
+        i.read_field_end()?;
+    }
+    i.read_struct_end()
+}
+
+impl TSerializable for Bet {
+    fn write_to_out_protocol(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        let kind: i32 = match self {
+            Bet::Check => 1,
+            Bet::Fold => 2,
+            Bet::Raise(_) => 3,
+            Bet::Call(_) => 4,
+            Bet::SmallBlind(_) => 5,
+            Bet::BigBlind(_) => 6,
+            Bet::Straddle(_) => 7,
+            Bet::Ante(_) => 8,
+            Bet::BringIn(_) => 9,
+            Bet::GuestBlind(_) => 10,
+        };
+        write_struct(o, "Bet", |o| {
+            field(o, "type", 1, &kind)?;
+            optional(o, "amount", 2, Some(&self.amount()).filter(|_| !matches!(self, Bet::Check | Bet::Fold)))
+        })
+    }
+
+    fn read_from_in_protocol(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        let (mut kind, mut amount) = (None, None);
+        read_struct(i, |i, id| {
+            match id {
+                1 => kind = Some(i32::read(i)?),
+                2 => amount = Some(Chips::read(i)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        let amount = || required(amount, "Bet.amount");
+        Ok(match required(kind, "Bet.type")? {
+            1 => Bet::Check,
+            2 => Bet::Fold,
+            3 => Bet::Raise(amount()?),
+            4 => Bet::Call(amount()?),
+            5 => Bet::SmallBlind(amount()?),
+            6 => Bet::BigBlind(amount()?),
+            7 => Bet::Straddle(amount()?),
+            8 => Bet::Ante(amount()?),
+            9 => Bet::BringIn(amount()?),
+            10 => Bet::GuestBlind(amount()?),
+            kind => return Err(invalid(format!("unknown BetType {}", kind))),
+        })
+    }
+}
+
+impl TSerializable for Stake {
+    fn write_to_out_protocol(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        let (min_buy_in, max_buy_in) = self.buy_in();
+        write_struct(o, "Stake", |o| {
+            field(o, "small_blind", 1, &self.small_blind())?;
+            field(o, "big_blind", 2, &self.big_blind())?;
+            optional(o, "ante", 3, self.ante().as_ref())?;
+            optional(o, "bring_in", 4, self.bring_in().as_ref())?;
+            optional(o, "currency", 5, self.currency().map(|currency| format!("{:?}", currency)).as_ref())?;
+            field(o, "min_buy_in", 6, &min_buy_in)?;
+            field(o, "max_buy_in", 7, &max_buy_in)
+        })
+    }
+
+    fn read_from_in_protocol(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        let (mut small_blind, mut big_blind, mut ante, mut bring_in, mut currency, mut min_buy_in, mut max_buy_in) =
+            (None, None, None, None, None, None, None);
+        read_struct(i, |i, id| {
+            match id {
+                1 => small_blind = Some(Chips::read(i)?),
+                2 => big_blind = Some(Chips::read(i)?),
+                3 => ante = Some(Chips::read(i)?),
+                4 => bring_in = Some(Chips::read(i)?),
+                5 => currency = Some(String::read(i)?),
+                6 => min_buy_in = Some(Chips::read(i)?),
+                7 => max_buy_in = Some(Chips::read(i)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        let mut stake = Stake::new(required(big_blind, "Stake.big_blind")?)
+            .with_small_blind(required(small_blind, "Stake.small_blind")?)
+            .with_buy_in(required(min_buy_in, "Stake.min_buy_in")?, required(max_buy_in, "Stake.max_buy_in")?);
+        if let Some(ante) = ante {
+            stake = stake.with_ante_of(ante);
+        }
+        if let Some(bring_in) = bring_in {
+            stake = stake.with_bring_in_of(bring_in);
+        }
+        if let Some(code) = currency {
+            stake = stake.with_currency(Currency::from_code(&code).ok_or_else(|| invalid(format!("unknown currency {}", code)))?);
+        }
+        Ok(stake)
+    }
+}
+
+impl TSerializable for User {
+    fn write_to_out_protocol(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        write_struct(o, "User", |o| field(o, "name", 1, &self.name))
+    }
+
+    fn read_from_in_protocol(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        let mut name = None;
+        read_struct(i, |i, id| {
+            match id {
+                1 => name = Some(String::read(i)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(User { name: required(name, "User.name")? })
+    }
+}
+
+impl TSerializable for Game {
+    fn write_to_out_protocol(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        write_struct(o, "Game", |o| {
+            field(o, "name", 1, &self.name)?;
+            field(o, "limit", 2, &self.limit)?;
+            field(o, "stake", 3, &self.stake)?;
+            field(o, "table_size", 4, &self.table_size)?;
+            field(o, "table", 5, &self.table)
+        })
+    }
+
+    fn read_from_in_protocol(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        let (mut name, mut limit, mut stake, mut table_size, mut table) = (None, None, None, None, None);
+        read_struct(i, |i, id| {
+            match id {
+                1 => name = Some(Name::read(i)?),
+                2 => limit = Some(Limit::read(i)?),
+                3 => stake = Some(Stake::read(i)?),
+                4 => table_size = Some(u8::read(i)?),
+                5 => table = Some(String::read(i)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(Game {
+            name: required(name, "Game.name")?,
+            limit: required(limit, "Game.limit")?,
+            stake: required(stake, "Game.stake")?,
+            table_size: required(table_size, "Game.table_size")?,
+            table: required(table, "Game.table")?,
+        })
+    }
+}
+
+impl TSerializable for Seat {
+    fn write_to_out_protocol(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        write_struct(o, "Seat", |o| {
+            field(o, "position", 1, &self.position)?;
+            field(o, "status", 2, &self.status)?;
+            optional(o, "player", 3, self.player.as_ref())?;
+            optional(o, "stack", 4, self.stack.as_ref())?;
+            field(o, "put", 5, &self.put)?;
+            field(o, "online", 6, &self.online)?;
+            field(o, "cards", 7, &self.cards)
+        })
+    }
+
+    fn read_from_in_protocol(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        let (mut position, mut status, mut player, mut stack, mut put, mut online, mut cards) =
+            (None, None, None, None, None, None, None);
+        read_struct(i, |i, id| {
+            match id {
+                1 => position = Some(u8::read(i)?),
+                2 => status = Some(Status::read(i)?),
+                3 => player = Some(User::read(i)?),
+                4 => stack = Some(Chips::read(i)?),
+                5 => put = Some(Chips::read(i)?),
+                6 => online = Some(bool::read(i)?),
+                7 => cards = Some(Vec::read(i)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(Seat {
+            position: required(position, "Seat.position")?,
+            status: required(status, "Seat.status")?,
+            player,
+            stack,
+            put: required(put, "Seat.put")?,
+            online: required(online, "Seat.online")?,
+            cards: required(cards, "Seat.cards")?,
+        })
+    }
+}
+
+impl TSerializable for Table {
+    fn write_to_out_protocol(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        write_struct(o, "Table", |o| {
+            field(o, "game", 1, &self.game)?;
+            field(o, "button", 2, &self.button)?;
+            field(o, "seats", 3, &self.seats)?;
+            field(o, "board", 4, &self.board)?;
+            field(o, "pot", 5, &self.pot)?;
+            optional(o, "street", 6, self.street.as_ref())?;
+            optional(o, "to_act", 7, self.to_act.as_ref())
+        })
+    }
+
+    fn read_from_in_protocol(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        let (mut game, mut button, mut seats, mut board, mut pot, mut street, mut to_act) =
+            (None, None, None, None, None, None, None);
+        read_struct(i, |i, id| {
+            match id {
+                1 => game = Some(Game::read(i)?),
+                2 => button = Some(u8::read(i)?),
+                3 => seats = Some(Vec::read(i)?),
+                4 => board = Some(Vec::read(i)?),
+                5 => pot = Some(Chips::read(i)?),
+                6 => street = Some(street::Holdem::read(i)?),
+                7 => to_act = Some(u8::read(i)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(Table {
+            game: required(game, "Table.game")?,
+            button: required(button, "Table.button")?,
+            seats: required(seats, "Table.seats")?,
+            board: required(board, "Table.board")?,
+            pot: required(pot, "Table.pot")?,
+            street,
+            to_act,
+        })
+    }
+}
+
+impl TSerializable for record::Seat {
+    fn write_to_out_protocol(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        write_struct(o, "SeatedPlayer", |o| {
+            field(o, "position", 1, &self.position)?;
+            field(o, "player", 2, &self.player)?;
+            field(o, "stack", 3, &self.stack)
+        })
+    }
+
+    fn read_from_in_protocol(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        let (mut position, mut player, mut stack) = (None, None, None);
+        read_struct(i, |i, id| {
+            match id {
+                1 => position = Some(u8::read(i)?),
+                2 => player = Some(String::read(i)?),
+                3 => stack = Some(Chips::read(i)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(record::Seat {
+            position: required(position, "SeatedPlayer.position")?,
+            player: required(player, "SeatedPlayer.player")?,
+            stack: required(stack, "SeatedPlayer.stack")?,
+        })
+    }
+}
+
+impl TSerializable for record::Action {
+    fn write_to_out_protocol(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        write_struct(o, "Action", |o| {
+            field(o, "street", 1, &self.street)?;
+            field(o, "position", 2, &self.position)?;
+            field(o, "bet", 3, &self.bet)?;
+            field(o, "all_in", 4, &self.all_in)?;
+            field(o, "timed_out", 5, &self.timed_out)?;
+            field(o, "dead", 6, &self.dead)?;
+            field(o, "at", 7, &self.at)?;
+            field(o, "time_used_ms", 8, &self.time_used_ms)?;
+            field(o, "player", 9, &self.player)?;
+            field(o, "forced", 10, &self.forced)
+        })
+    }
+
+    fn read_from_in_protocol(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        let (mut street, mut position, mut bet, mut all_in, mut timed_out) = (None, None, None, None, None);
+        let (mut dead, mut at, mut time_used_ms, mut player, mut forced) = (None, None, None, None, None);
+        read_struct(i, |i, id| {
+            match id {
+                1 => street = Some(street::Holdem::read(i)?),
+                2 => position = Some(u8::read(i)?),
+                3 => bet = Some(Bet::read(i)?),
+                4 => all_in = Some(bool::read(i)?),
+                5 => timed_out = Some(bool::read(i)?),
+                6 => dead = Some(bool::read(i)?),
+                7 => at = Some(u64::read(i)?),
+                8 => time_used_ms = Some(u64::read(i)?),
+                9 => player = Some(String::read(i)?),
+                10 => forced = Some(bool::read(i)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(record::Action {
+            street: required(street, "Action.street")?,
+            position: required(position, "Action.position")?,
+            player: required(player, "Action.player")?,
+            bet: required(bet, "Action.bet")?,
+            at: required(at, "Action.at")?,
+            time_used_ms: required(time_used_ms, "Action.time_used_ms")?,
+            forced: required(forced, "Action.forced")?,
+            all_in: required(all_in, "Action.all_in")?,
+            timed_out: required(timed_out, "Action.timed_out")?,
+            dead: required(dead, "Action.dead")?,
+        })
+    }
+}
+
+impl TSerializable for record::Winner {
+    fn write_to_out_protocol(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        write_struct(o, "Winner", |o| {
+            field(o, "position", 1, &self.position)?;
+            field(o, "amount", 2, &self.amount)
+        })
+    }
+
+    fn read_from_in_protocol(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        let (mut position, mut amount) = (None, None);
+        read_struct(i, |i, id| {
+            match id {
+                1 => position = Some(u8::read(i)?),
+                2 => amount = Some(Chips::read(i)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(record::Winner { position: required(position, "Winner.position")?, amount: required(amount, "Winner.amount")? })
+    }
+}
+
+impl TSerializable for record::Pot {
+    fn write_to_out_protocol(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        write_struct(o, "Pot", |o| {
+            field(o, "amount", 1, &self.amount)?;
+            field(o, "eligible", 2, &self.eligible)?;
+            field(o, "winners", 3, &self.winners)
+        })
+    }
+
+    fn read_from_in_protocol(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        let (mut amount, mut eligible, mut winners) = (None, None, None);
+        read_struct(i, |i, id| {
+            match id {
+                1 => amount = Some(Chips::read(i)?),
+                2 => eligible = Some(Vec::read(i)?),
+                3 => winners = Some(Vec::read(i)?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(record::Pot {
+            amount: required(amount, "Pot.amount")?,
+            eligible: required(eligible, "Pot.eligible")?,
+            winners: required(winners, "Pot.winners")?,
+        })
+    }
+}
+
+impl TSerializable for Event {
+    fn write_to_out_protocol(&self, o: &mut dyn TOutputProtocol) -> thrift::Result<()> {
+        write_struct(o, "Event", |o| match self {
+            Event::HandStarted { id, button, seats } => nested(o, "hand_started", 1, "HandStarted", |o| {
+                field(o, "id", 1, id)?;
+                field(o, "button", 2, button)?;
+                field(o, "seats", 3, seats)
+            }),
+            Event::CardsDealt { street, position, cards } => nested(o, "cards_dealt", 2, "CardsDealt", |o| {
+                field(o, "street", 1, street)?;
+                optional(o, "position", 2, position.as_ref())?;
+                field(o, "cards", 3, cards)
+            }),
+            Event::Action(action) => field(o, "action", 3, action),
+            Event::CardsShown { position, cards } => nested(o, "cards_shown", 4, "CardsShown", |o| {
+                field(o, "position", 1, position)?;
+                field(o, "cards", 2, cards)
+            }),
+            Event::Pot(pot) => field(o, "pot", 5, pot),
+            Event::HandFinished { id, total, rake } => nested(o, "hand_finished", 6, "HandFinished", |o| {
+                field(o, "id", 1, id)?;
+                field(o, "total", 2, total)?;
+                field(o, "rake", 3, rake)
+            }),
+            Event::Rabbit { cards } => nested(o, "rabbit", 7, "Rabbit", |o| field(o, "cards", 1, cards)),
+            Event::RunOut { run, board } => nested(o, "run_out", 8, "RunOut", |o| {
+                field(o, "run", 1, run)?;
+                field(o, "board", 2, board)
+            }),
+        })
+    }
+
+    fn read_from_in_protocol(i: &mut dyn TInputProtocol) -> thrift::Result<Self> {
+        let mut event = None;
+        read_struct(i, |i, id| {
+            event = Some(match id {
+                1 => {
+                    let (mut id, mut button, mut seats) = (None, None, None);
+                    read_struct(i, |i, field| {
+                        match field {
+                            1 => id = Some(u64::read(i)?),
+                            2 => button = Some(u8::read(i)?),
+                            3 => seats = Some(Vec::read(i)?),
+                            _ => return Ok(false),
+                        }
+                        Ok(true)
+                    })?;
+                    Event::HandStarted {
+                        id: required(id, "HandStarted.id")?,
+                        button: required(button, "HandStarted.button")?,
+                        seats: required(seats, "HandStarted.seats")?,
+                    }
+                }
+                2 => {
+                    let (mut street, mut position, mut cards) = (None, None, None);
+                    read_struct(i, |i, field| {
+                        match field {
+                            1 => street = Some(street::Holdem::read(i)?),
+                            2 => position = Some(u8::read(i)?),
+                            3 => cards = Some(Vec::read(i)?),
+                            _ => return Ok(false),
+                        }
+                        Ok(true)
+                    })?;
+                    Event::CardsDealt { street: required(street, "CardsDealt.street")?, position, cards: required(cards, "CardsDealt.cards")? }
+                }
+                3 => Event::Action(record::Action::read(i)?),
+                4 => {
+                    let (mut position, mut cards) = (None, None);
+                    read_struct(i, |i, field| {
+                        match field {
+                            1 => position = Some(u8::read(i)?),
+                            2 => cards = Some(Vec::read(i)?),
+                            _ => return Ok(false),
+                        }
+                        Ok(true)
+                    })?;
+                    Event::CardsShown { position: required(position, "CardsShown.position")?, cards: required(cards, "CardsShown.cards")? }
+                }
+                5 => Event::Pot(record::Pot::read(i)?),
+                6 => {
+                    let (mut id, mut total, mut rake) = (None, None, None);
+                    read_struct(i, |i, field| {
+                        match field {
+                            1 => id = Some(u64::read(i)?),
+                            2 => total = Some(Chips::read(i)?),
+                            3 => rake = Some(Chips::read(i)?),
+                            _ => return Ok(false),
+                        }
+                        Ok(true)
+                    })?;
+                    Event::HandFinished {
+                        id: required(id, "HandFinished.id")?,
+                        total: required(total, "HandFinished.total")?,
+                        rake: required(rake, "HandFinished.rake")?,
+                    }
+                }
+                7 => {
+                    let mut cards = None;
+                    read_struct(i, |i, field| {
+                        match field {
+                            1 => cards = Some(Vec::read(i)?),
+                            _ => return Ok(false),
+                        }
+                        Ok(true)
+                    })?;
+                    Event::Rabbit { cards: required(cards, "Rabbit.cards")? }
+                }
+                8 => {
+                    let (mut run, mut board) = (None, None);
+                    read_struct(i, |i, field| {
+                        match field {
+                            1 => run = Some(u8::read(i)?),
+                            2 => board = Some(Vec::read(i)?),
+                            _ => return Ok(false),
+                        }
+                        Ok(true)
+                    })?;
+                    Event::RunOut { run: required(run, "RunOut.run")?, board: required(board, "RunOut.board")? }
+                }
+                _ => return Ok(false),
+            });
+            Ok(true)
+        })?;
+        required(event, "Event")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Player;
+
+    fn played() -> game::Game<'static> {
+        let stake = Stake::new(Chips(10)).with_currency(Currency::EUR).with_ante();
+        let mut game = game::Game::new(Name::Texas, Limit::NoLimit, stake, 6).with_seed(7).with_name("Alpha");
+        game.join(Player::blank_player("p1"), 0, Chips(1000)).unwrap();
+        game.join(Player::blank_player("p2"), 2, Chips(300)).unwrap();
+        game.join(Player::blank_player("p3"), 4, Chips(1000)).unwrap();
+        game.start().unwrap();
+        game
+    }
+
+    fn finish(game: &mut game::Game) {
+        let first = game.to_act().unwrap();
+        let shove = game.range(first).unwrap().max_raise.unwrap();
+        game.act(first, Bet::Raise(shove)).unwrap();
+        while let Some(position) = game.to_act() {
+            let range = game.range(position).unwrap();
+            game.act(position, Bet::Call(range.call)).unwrap();
+        }
+    }
+
+    #[test]
+    pub fn bet_bytes() {
+        let bytes = encode(&Bet::Raise(Chips(30)), Encoding::Binary).unwrap();
+        assert_eq!(bytes, [0x08, 0, 1, 0, 0, 0, 3, 0x0a, 0, 2, 0, 0, 0, 0, 0, 0, 0, 30, 0]);
+        assert_eq!(encode(&Bet::Check, Encoding::Binary).unwrap(), [0x08, 0, 1, 0, 0, 0, 1, 0]);
+        assert_eq!(decode::<Bet>(&bytes, Encoding::Binary).unwrap(), Bet::Raise(Chips(30)));
+        assert!(decode::<Bet>(&[0x08, 0, 1, 0, 0, 0, 4, 0], Encoding::Binary).is_err());
+    }
+
+    #[test]
+    pub fn round_trip() {
+        let mut game = played();
+        for encoding in [Encoding::Binary, Encoding::Compact] {
+            let table = Table::view(&game, Viewer::Seat(0));
+            assert_eq!(decode::<Table>(&encode(&table, encoding).unwrap(), encoding).unwrap(), table);
+        }
+
+        finish(&mut game);
+        let record = Record::from(game.history().unwrap());
+        let events = events(&record, Viewer::Spectator);
+        for encoding in [Encoding::Binary, Encoding::Compact] {
+            for event in events.iter() {
+                assert_eq!(&decode::<Event>(&encode(event, encoding).unwrap(), encoding).unwrap(), event);
+            }
+        }
+        assert!(matches!(events.first(), Some(Event::HandStarted { seats, .. }) if seats.len() == 3));
+        assert!(matches!(events.last(), Some(Event::HandFinished { total, .. }) if *total == record.total));
+        assert_eq!(events.iter().filter(|event| matches!(event, Event::CardsDealt { position: None, .. })).count(), 3);
+    }
+
+    #[test]
+    pub fn hidden_cards() {
+        let mut game = played();
+        let table = Table::view(&game, Viewer::Seat(2));
+        let cards = |position: usize| table.seats[position].cards.clone();
+        assert_eq!(cards(2), game.pocket_cards(2).unwrap().iter().copied().map(Some).collect::<Vec<_>>());
+        assert_eq!(cards(0), vec![None, None]);
+        assert!(Table::view(&game, Viewer::Spectator).seats.iter().all(|seat| seat.cards.iter().all(Option::is_none)));
+
+        // hidden cards are sent as 64 and nothing else about them
+        let bytes = encode(&table.seats[0], Encoding::Binary).unwrap();
+        assert!(bytes.windows(2).any(|pair| pair == [64, 64]));
+
+        finish(&mut game);
+        let record = Record::from(game.history().unwrap());
+        let dealt = |events: &[Event], position: u8| events.iter().find_map(|event| match event {
+            Event::CardsDealt { position: Some(dealt), cards, .. } if *dealt == position => Some(cards.clone()),
+            _ => None,
+        });
+        let seen = events(&record, Viewer::Seat(4));
+        assert!(dealt(&seen, 4).unwrap().iter().all(Option::is_some));
+        assert!(dealt(&seen, 0).unwrap().iter().all(Option::is_none));
+        // everyone is all-in, so every hand is shown down in the open
+        assert_eq!(seen.iter().filter(|event| matches!(event, Event::CardsShown { .. })).count(), 3);
+    }
+
+    #[test]
+    pub fn showdown_view() {
+        let mut game = played();
+        let folded = game.to_act().unwrap();
+        assert!(Table::view(&game, Viewer::Admin).seats.iter().filter(|seat| seat.player.is_some()).all(|seat| seat.cards.iter().all(Option::is_some)));
+        game.act(folded, Bet::Fold).unwrap();
+        finish(&mut game);
+
+        let table = Table::view(&game, Viewer::Spectator);
+        for seat in table.seats.iter().filter(|seat| seat.player.is_some()) {
+            // the folded hand is never shown, the two all-in hands are
+            assert_eq!(seat.cards.iter().all(Option::is_some), seat.position != folded);
+            assert_eq!(seat.cards.iter().all(Option::is_none), seat.position == folded);
+        }
+        assert!(Table::view(&game, Viewer::Seat(folded)).seats[folded as usize].cards.iter().all(Option::is_some));
+        assert!(Table::view(&game, Viewer::Admin).seats[folded as usize].cards.iter().all(Option::is_some));
+    }
+
+    #[test]
+    pub fn shown_after_the_hand() {
+        let mut game = game::Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 2).with_seed(3).with_rabbit_hunting();
+        game.join(Player::blank_player("p1"), 0, Chips(1000)).unwrap();
+        game.join(Player::blank_player("p2"), 1, Chips(1000)).unwrap();
+        game.start().unwrap();
+        let folded = game.to_act().unwrap();
+        game.act(folded, Bet::Fold).unwrap();
+
+        let mut feed = Feed::default();
+        let events = feed.update(&Record::from(game.history().unwrap()), true);
+        assert!(matches!(events.last(), Some(Event::Rabbit { cards }) if cards.len() == 5));
+
+        // one card shown, the other stays face down
+        let card = game.pocket_cards(folded).unwrap()[0];
+        game.show(folded, vec![card]).unwrap();
+        assert_eq!(Table::view(&game, Viewer::Spectator).seats[folded as usize].cards, vec![Some(card), None]);
+        let record = Record::from(game.history().unwrap());
+        assert_eq!(feed.update(&record, true), vec![Event::CardsShown { position: folded, cards: vec![card] }]);
+        assert!(feed.update(&record, true).is_empty());
+    }
+
+    #[test]
+    pub fn mucked_cards_leave_with_the_player() {
+        let mut game = game::Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 2).with_seed(3);
+        game.join(Player::blank_player("p1"), 0, Chips(1000)).unwrap();
+        game.join(Player::blank_player("p2"), 1, Chips(1000)).unwrap();
+        game.start().unwrap();
+        let folded = game.to_act().unwrap();
+        game.act(folded, Bet::Fold).unwrap();
+        assert_eq!(Table::view(&game, Viewer::Seat(folded)).seats[folded as usize].cards.len(), 2);
+
+        game.leave(folded).unwrap();
+        game.join(Player::blank_player("mallory"), folded, Chips(1000)).unwrap();
+        assert!(game.pocket_cards(folded).is_some());
+        for viewer in [Viewer::Seat(folded), Viewer::Admin] {
+            assert!(Table::view(&game, viewer).seats[folded as usize].cards.is_empty());
+        }
+    }
+
+    #[test]
+    pub fn live_feed() {
+        let mut game = played();
+        let mut feed = Feed::default();
+        let mut live = vec![];
+        let mut update = |game: &game::Game| {
+            let record = Record::from(game.history().unwrap());
+            live.extend(feed.update(&record, !game.is_hand_in_progress()));
+        };
+        update(&game);
+        while let Some(position) = game.to_act() {
+            let range = game.range(position).unwrap();
+            let bet = if range.call.is_zero() { Bet::Check } else { Bet::Call(range.call) };
+            game.act(position, bet).unwrap();
+            update(&game);
+        }
+        assert_eq!(live.iter().map(|event| event.masked(Viewer::Spectator)).collect::<Vec<_>>(), events(&Record::from(game.history().unwrap()), Viewer::Spectator));
+    }
+}
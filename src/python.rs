@@ -0,0 +1,322 @@
+use pyo3::basic::CompareOp;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyList;
+use crate::equity::Calculator;
+use crate::poker::{self, card, hand, Cards, Rank};
+use crate::pokerstars;
+use crate::range;
+use crate::record::Record;
+
+// python bindings, see docs/python.md. cards go in as strings like "Ah Kd", anything that
+// deals out boards or parses a file of hands lets go of the GIL while it works.
+
+fn error(error: impl ToString) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+fn strict(cards: &str) -> PyResult<Cards> {
+    card::parse_joined(cards).map_err(error)
+}
+
+fn wrap(cards: &[poker::Card]) -> Vec<Card> {
+    cards.iter().map(|&card| Card(card)).collect()
+}
+
+#[pyclass(module = "pokernode", frozen)]
+#[derive(Clone, Copy)]
+pub struct Card(poker::Card);
+
+#[pymethods]
+impl Card {
+    #[new]
+    fn new(s: &str) -> PyResult<Self> {
+        match strict(s)?[..] {
+            [card] => Ok(Card(card)),
+            _ => Err(error(format!("not one card: {}", s))),
+        }
+    }
+
+    // Card.parse("AhKd 7c")
+    #[staticmethod]
+    fn parse(s: &str) -> PyResult<Vec<Card>> {
+        Ok(wrap(&strict(s)?))
+    }
+
+    #[getter]
+    fn rank(&self) -> String {
+        self.0.kind().short_name().to_string()
+    }
+
+    #[getter]
+    fn suit(&self) -> String {
+        self.0.suit().short_name().to_string()
+    }
+
+    fn __int__(&self) -> u8 {
+        self.0.to_u8()
+    }
+
+    fn __hash__(&self) -> u64 {
+        self.0.to_u8() as u64
+    }
+
+    fn __eq__(&self, other: &Card) -> bool {
+        self.0 == other.0
+    }
+
+    fn __str__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Card('{:?}')", self.0)
+    }
+}
+
+#[pyclass(module = "pokernode", frozen)]
+pub struct Hand(poker::Hand);
+
+#[pymethods]
+impl Hand {
+    // "two-pair", "flush" and so on
+    #[getter]
+    fn rank(&self) -> String {
+        match self.0.rank() {
+            Some(Rank::High(rank)) => rank.name().to_string(),
+            _ => String::new(),
+        }
+    }
+
+    #[getter]
+    fn description(&self) -> String {
+        self.0.description()
+    }
+
+    #[getter]
+    fn cards(&self) -> Vec<Card> {
+        wrap(self.0.cards())
+    }
+
+    // stronger hands compare greater
+    fn __richcmp__(&self, other: &Hand, op: CompareOp) -> bool {
+        self.0.partial_cmp(&other.0).is_some_and(|order| op.matches(order))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Hand('{}')", self.0.description())
+    }
+}
+
+// the best high hand, omaha plays exactly two pocket cards
+#[pyfunction]
+#[pyo3(signature = (pocket, board = "", omaha = false))]
+fn evaluate(pocket: &str, board: &str, omaha: bool) -> PyResult<Hand> {
+    let hand = hand::best_high(&strict(pocket)?, &strict(board)?, omaha).ok_or_else(|| error("not enough cards for a hand"))?;
+    Ok(Hand(hand))
+}
+
+#[pyclass(module = "pokernode", frozen, get_all)]
+pub struct Equity {
+    win: f64,
+    tie: f64,
+    equity: f64,
+}
+
+#[pymethods]
+impl Equity {
+    fn __repr__(&self) -> String {
+        format!("Equity(win={:.4}, tie={:.4}, equity={:.4})", self.win, self.tie, self.equity)
+    }
+}
+
+impl From<&crate::equity::Equity> for Equity {
+    fn from(equity: &crate::equity::Equity) -> Self {
+        Equity { win: equity.win, tie: equity.tie, equity: equity.equity }
+    }
+}
+
+// one Equity per hand, sampled from seed when there are too many boards to deal
+#[pyfunction]
+#[pyo3(signature = (hands, board = "", dead = "", omaha = false, trials = 20000, seed = 0))]
+fn equity(py: Python<'_>, hands: Vec<String>, board: &str, dead: &str, omaha: bool, trials: u32, seed: u64) -> PyResult<Vec<Equity>> {
+    let pockets = hands.iter().map(|hand| strict(hand)).collect::<PyResult<Vec<_>>>()?;
+    let mut calculator = Calculator::new(pockets).with_board(strict(board)?).with_dead(strict(dead)?).with_trials(trials).with_seed(seed);
+    if omaha {
+        calculator = calculator.with_omaha();
+    }
+    let outcome = py.allow_threads(|| calculator.calculate()).map_err(error)?;
+    Ok(outcome.equities.iter().map(Equity::from).collect())
+}
+
+// every combination in "QQ+, AKs, A5s-A2s", each as a pair of cards
+#[pyfunction]
+fn parse_range(s: &str) -> PyResult<Vec<(Card, Card)>> {
+    let combos = range::parse(s).map_err(error)?;
+    Ok(combos.iter().map(|combo| (Card(combo[0]), Card(combo[1]))).collect())
+}
+
+#[pyclass(module = "pokernode", frozen)]
+pub struct HandRecord(Record);
+
+#[pymethods]
+impl HandRecord {
+    #[staticmethod]
+    fn from_json(s: &str) -> PyResult<Self> {
+        Ok(HandRecord(Record::from_json(s).map_err(error)?))
+    }
+
+    #[getter]
+    fn id(&self) -> u64 {
+        self.0.id
+    }
+
+    #[getter]
+    fn table(&self) -> &str {
+        &self.0.table
+    }
+
+    #[getter]
+    fn game(&self) -> &'static str {
+        pokerstars::game_name(self.0.game)
+    }
+
+    #[getter]
+    fn limit(&self) -> &'static str {
+        pokerstars::limit_name(self.0.limit)
+    }
+
+    #[getter]
+    fn button(&self) -> u8 {
+        self.0.button
+    }
+
+    // (position, player, stack) for every seat dealt in
+    #[getter]
+    fn seats(&self) -> Vec<(u8, String, u64)> {
+        self.0.seats.iter().map(|seat| (seat.position, seat.player.clone(), seat.stack.amount())).collect()
+    }
+
+    #[getter]
+    fn board(&self) -> Vec<Card> {
+        wrap(&self.0.cards.iter().filter(|dealt| dealt.position.is_none()).flat_map(|dealt| dealt.cards.clone()).collect::<Vec<_>>())
+    }
+
+    #[getter]
+    fn total(&self) -> u64 {
+        self.0.total.amount()
+    }
+
+    #[getter]
+    fn rake(&self) -> u64 {
+        self.0.rake.amount()
+    }
+
+    fn to_json(&self) -> String {
+        self.0.to_json()
+    }
+
+    // the whole record as plain dicts and lists, the same shape as the json
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        py.import("json")?.call_method1("loads", (self.0.to_json(),))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("HandRecord(id={}, table='{}')", self.0.id, self.0.table)
+    }
+}
+
+// every hand in a pokerstars hand history
+#[pyfunction]
+fn parse_histories<'py>(py: Python<'py>, text: &str) -> PyResult<Bound<'py, PyList>> {
+    let records = py.allow_threads(|| {
+        pokerstars::parse(text).map(|histories| histories.iter().map(Record::from).collect::<Vec<_>>())
+    }).map_err(error)?;
+    PyList::new(py, records.into_iter().map(HandRecord))
+}
+
+#[pymodule]
+fn pokernode(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Card>()?;
+    m.add_class::<Hand>()?;
+    m.add_class::<Equity>()?;
+    m.add_class::<HandRecord>()?;
+    m.add_function(wrap_pyfunction!(evaluate, m)?)?;
+    m.add_function(wrap_pyfunction!(equity, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_range, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_histories, m)?)?;
+    Ok(())
+}
+
+// cargo test --features python
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyDict;
+
+    fn run(code: &str) {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let globals = PyDict::new(py);
+            globals.set_item("pn", pyo3::wrap_pymodule!(pokernode)(py)).unwrap();
+            let code = std::ffi::CString::new(code).unwrap();
+            if let Err(error) = py.run(&code, Some(&globals), None) {
+                error.print(py);
+                panic!("python failed");
+            }
+        });
+    }
+
+    #[test]
+    pub fn cards_and_hands() {
+        run(r#"
+assert [str(card) for card in pn.Card.parse("AhKd 7c")] == ["Ah", "Kd", "7c"]
+assert pn.Card("Ah") == pn.Card.parse("Ah")[0] and len({pn.Card("Ah"), pn.Card("Ah")}) == 1
+assert (pn.Card("Td").rank, pn.Card("Td").suit) == ("T", "d")
+pairs = pn.evaluate("Ah Ad", "7c 7d 2s Kh 9c")
+assert (pairs.rank, pairs.description) == ("two-pair", "two pairs, As and 7s")
+assert pairs < pn.evaluate("7h 7s", "7c 7d 2s Kh 9c")
+try:
+    pn.evaluate("Ah Ax")
+    assert False
+except ValueError as error:
+    assert "Ax" in str(error)
+assert len(pn.parse_range("QQ+, AKs")) == 3 * 6 + 4
+"#);
+    }
+
+    #[test]
+    pub fn equities() {
+        run(r#"
+aces, kings = pn.equity(["AhAd", "KhKd"], board="2c 7s 9d Ks")
+assert (aces.win, kings.equity) == (2 / 44, 42 / 44)
+assert [e.equity for e in pn.equity(["AhAd", "KhKd"], trials=500, seed=7)] == [e.equity for e in pn.equity(["AhAd", "KhKd"], trials=500, seed=7)]
+"#);
+    }
+
+    #[test]
+    pub fn hand_records() {
+        run(r#"
+records = pn.parse_histories("""PokerStars Hand #7: Hold'em No Limit (5/10) - 2026/10/18 12:00:00 UTC
+Table 'Alpha' 2-max Seat #1 is the button
+Seat 1: a (1000 in chips)
+Seat 2: b (1000 in chips)
+a: posts small blind 5
+b: posts big blind 10
+*** HOLE CARDS ***
+a: folds
+""")
+assert len(records) == 1
+record = records[0]
+assert (record.id, record.table, record.game, record.limit) == (7, "Alpha", "Hold'em", "No Limit")
+assert record.seats == [(0, "a", 1000), (1, "b", 1000)]
+assert pn.HandRecord.from_json(record.to_json()).id == 7
+assert record.to_dict()["table"] == "Alpha"
+try:
+    pn.parse_histories("PokerStars Hand #1")
+    assert False
+except ValueError:
+    pass
+"#);
+    }
+}
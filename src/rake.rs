@@ -0,0 +1,167 @@
+use crate::game::{Stake, Variant};
+use crate::locale::Currency;
+use crate::pot::Pot;
+use crate::chips::Chips;
+
+pub const BASIS_POINTS: u32 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cap {
+    pub big_blind: Chips,
+    pub players: u8,
+    pub amount: Chips,
+}
+
+#[derive(Debug, Clone)]
+pub struct Policy {
+    basis_points: u32,
+    caps: Vec<Cap>,
+    no_flop_no_drop: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rake {
+    pub amount: Chips,
+    pub pot: Chips,
+    pub basis_points: u32,
+    pub cap: Option<Chips>,
+    pub players: u8,
+    pub currency: Option<Currency>,
+    pub no_flop_no_drop: bool,
+}
+
+impl Rake {
+    pub fn is_capped(&self) -> bool {
+        self.cap == Some(self.amount)
+    }
+}
+
+impl Policy {
+    pub fn new(percent: f32) -> Self {
+        Policy {
+            basis_points: (percent * 100.0).round() as u32,
+            caps: vec![],
+            no_flop_no_drop: true,
+        }
+    }
+
+    pub fn with_cap(mut self, big_blind: Chips, players: u8, amount: Chips) -> Self {
+        self.caps.push(Cap { big_blind, players, amount });
+        self
+    }
+
+    pub fn with_no_flop_no_drop(mut self, enabled: bool) -> Self {
+        self.no_flop_no_drop = enabled;
+        self
+    }
+
+    pub fn basis_points(&self) -> u32 {
+        self.basis_points
+    }
+
+    // highest stake level not above the big blind, then the highest player bracket within it
+    pub fn cap(&self, big_blind: Chips, players: u8) -> Option<Chips> {
+        let level = self.caps.iter()
+            .filter(|cap| cap.big_blind <= big_blind)
+            .map(|cap| cap.big_blind)
+            .max()?;
+
+        self.caps.iter()
+            .filter(|cap| cap.big_blind == level && cap.players <= players)
+            .max_by_key(|cap| cap.players)
+            .map(|cap| cap.amount)
+    }
+
+    pub fn calculate(&self, pot: &Pot, stake: &Stake, variant: &Variant, players: u8, flop: bool) -> Rake {
+        let total = pot.total() - pot.uncalled().map_or(Chips::ZERO, |(_, amount)| amount);
+        let cap = self.cap(stake.big_blind(), players);
+        let no_flop_no_drop = self.no_flop_no_drop && !flop && matches!(variant, Variant::Holdem);
+
+        let amount = if no_flop_no_drop {
+            Chips::ZERO
+        } else {
+            let amount = total.basis_points(self.basis_points);
+            cap.map_or(amount, |cap| amount.min(cap))
+        };
+
+        Rake {
+            amount,
+            pot: total,
+            basis_points: self.basis_points,
+            cap,
+            players,
+            currency: stake.currency(),
+            no_flop_no_drop,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> Policy {
+        Policy::new(5.0)
+            .with_cap(Chips(0), 2, Chips(50))
+            .with_cap(Chips(0), 5, Chips(100))
+            .with_cap(Chips(200), 2, Chips(100))
+            .with_cap(Chips(200), 5, Chips(300))
+    }
+
+    #[test]
+    pub fn caps() {
+        let policy = policy();
+        assert_eq!(policy.cap(Chips(10), 2), Some(Chips(50)));
+        assert_eq!(policy.cap(Chips(10), 4), Some(Chips(50)));
+        assert_eq!(policy.cap(Chips(10), 9), Some(Chips(100)));
+        assert_eq!(policy.cap(Chips(200), 3), Some(Chips(100)));
+        assert_eq!(policy.cap(Chips(400), 6), Some(Chips(300)));
+        assert_eq!(Policy::new(5.0).cap(Chips(10), 2), None);
+    }
+
+    #[test]
+    pub fn capped_rake() {
+        let stake = Stake::new(Chips(10)).with_currency(Currency::USD);
+        let mut pot = Pot::new();
+        pot.add(0, Chips(1500)).unwrap();
+        pot.add(1, Chips(1500)).unwrap();
+
+        let rake = policy().calculate(&pot, &stake, &Variant::Holdem, 2, true);
+        assert_eq!(rake.pot, Chips(3000));
+        assert_eq!(rake.amount, Chips(50));
+        assert!(rake.is_capped());
+        assert_eq!(rake.currency, Some(Currency::USD));
+    }
+
+    #[test]
+    pub fn uncalled_bet_is_not_raked() {
+        let stake = Stake::new(Chips(10));
+        let mut pot = Pot::new();
+        pot.add(0, Chips(100)).unwrap();
+        pot.add(1, Chips(300)).unwrap();
+
+        let rake = Policy::new(5.0).calculate(&pot, &stake, &Variant::Holdem, 2, true);
+        assert_eq!(rake.pot, Chips(200));
+        assert_eq!(rake.amount, Chips(10));
+    }
+
+    #[test]
+    pub fn no_flop_no_drop() {
+        let stake = Stake::new(Chips(10));
+        let mut pot = Pot::new();
+        pot.add(0, Chips(5)).unwrap();
+        pot.add(1, Chips(30)).unwrap();
+        pot.add(2, Chips(30)).unwrap();
+
+        let rake = policy().calculate(&pot, &stake, &Variant::Holdem, 3, false);
+        assert_eq!(rake.amount, Chips(0));
+        assert!(rake.no_flop_no_drop);
+
+        let rake = policy().calculate(&pot, &stake, &Variant::SevenCard, 3, false);
+        assert_eq!(rake.amount, Chips(3));
+        assert!(!rake.no_flop_no_drop);
+
+        let rake = policy().with_no_flop_no_drop(false).calculate(&pot, &stake, &Variant::Holdem, 3, false);
+        assert_eq!(rake.amount, Chips(3));
+    }
+}
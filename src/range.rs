@@ -0,0 +1,151 @@
+use std::fmt;
+use crate::poker::{card, kind, Card, Cards, Kind, Suit};
+
+// hold'em starting hand ranges in the usual shorthand: "QQ+, AKs, A5s-A2s, KQo, AhKh"
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Invalid(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(token) => write!(f, "invalid range: {}", token),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Suitedness {
+    Any,
+    Suited,
+    Offsuit,
+}
+
+// both kinds of a two card shorthand, the higher one first
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Shorthand {
+    high: Kind,
+    low: Kind,
+    suited: Suitedness,
+}
+
+impl Shorthand {
+    fn parse(s: &str) -> Option<Self> {
+        let (high, low) = (kind::parse(s.get(..1)?)?, kind::parse(s.get(1..2)?)?);
+        let suited = match s.get(2..)? {
+            "" => Suitedness::Any,
+            "s" => Suitedness::Suited,
+            "o" => Suitedness::Offsuit,
+            _ => return None,
+        };
+        if high == low && suited != Suitedness::Any {
+            return None;
+        }
+        let (high, low) = if (high as u8) < (low as u8) { (low, high) } else { (high, low) };
+        Some(Shorthand { high, low, suited })
+    }
+
+    fn is_pair(&self) -> bool {
+        self.high == self.low
+    }
+
+    fn combos(&self, combos: &mut Vec<Cards>) {
+        for (i, &first) in Suit::ALL.iter().enumerate() {
+            for (j, &second) in Suit::ALL.iter().enumerate() {
+                let wanted = match self.suited {
+                    _ if self.is_pair() => i < j,
+                    Suitedness::Any => true,
+                    Suitedness::Suited => i == j,
+                    Suitedness::Offsuit => i != j,
+                };
+                if wanted {
+                    push(combos, vec![Card::new(self.high, first), Card::new(self.low, second)]);
+                }
+            }
+        }
+    }
+}
+
+fn push(combos: &mut Vec<Cards>, combo: Cards) {
+    if !combos.iter().any(|known| known.iter().all(|card| combo.contains(card))) {
+        combos.push(combo);
+    }
+}
+
+// "22+" climbs to aces, "A2s+" climbs the low card up to just under the high one
+fn plus(from: Shorthand) -> Vec<Shorthand> {
+    let top = if from.is_pair() { Kind::Ace as u8 } else { from.high as u8 - 1 };
+    (from.low as u8..=top)
+        .map(|low| {
+            let low = Kind::ALL[low as usize];
+            Shorthand { high: if from.is_pair() { low } else { from.high }, low, suited: from.suited }
+        })
+        .collect()
+}
+
+// "A2s-A5s" or "22-55", in either order
+fn between(a: Shorthand, b: Shorthand) -> Option<Vec<Shorthand>> {
+    if a.is_pair() != b.is_pair() || a.suited != b.suited || (!a.is_pair() && a.high != b.high) {
+        return None;
+    }
+    let (from, to) = if (a.low as u8) <= (b.low as u8) { (a, b) } else { (b, a) };
+    Some(plus(from).into_iter().filter(|hand| (hand.low as u8) <= (to.low as u8)).collect())
+}
+
+// every two card combination in the range, each once
+pub fn parse(s: &str) -> Result<Vec<Cards>, Error> {
+    let mut combos = vec![];
+    for token in s.split(|c: char| c.is_whitespace() || c == ',').filter(|token| !token.is_empty()) {
+        let invalid = || Error::Invalid(token.to_string());
+        let hands = if let Some(from) = token.strip_suffix('+') {
+            plus(Shorthand::parse(from).ok_or_else(invalid)?)
+        } else if let Some((a, b)) = token.split_once('-') {
+            let (a, b) = (Shorthand::parse(a).ok_or_else(invalid)?, Shorthand::parse(b).ok_or_else(invalid)?);
+            between(a, b).ok_or_else(invalid)?
+        } else if let Some(hand) = Shorthand::parse(token) {
+            vec![hand]
+        } else {
+            match card::parse_joined(token) {
+                Ok(cards) if cards.len() == 2 => push(&mut combos, cards),
+                _ => return Err(invalid()),
+            }
+            continue;
+        };
+        for hand in hands {
+            hand.combos(&mut combos);
+        }
+    }
+    Ok(combos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn parse_ranges() {
+        assert_eq!(parse("AA").unwrap().len(), 6);
+        assert_eq!(parse("AKs").unwrap().len(), 4);
+        assert_eq!(parse("KAo").unwrap().len(), 12);
+        assert_eq!(parse("AK").unwrap().len(), 16);
+        assert_eq!(parse("22+").unwrap().len(), 13 * 6);
+        assert_eq!(parse("A2s+").unwrap().len(), 12 * 4);
+        assert_eq!(parse("A5s-A2s, QQ-KK").unwrap().len(), 4 * 4 + 2 * 6);
+        // overlaps count once
+        assert_eq!(parse("AKs AK AhKh").unwrap().len(), 16);
+        assert_eq!(parse("AhKh").unwrap(), vec![card::parse("Ah Kh")]);
+        assert!(parse("JJ+").unwrap().iter().all(|combo| combo[0].kind() == combo[1].kind() && combo[0].kind() as u8 >= Kind::Jack as u8));
+    }
+
+    #[test]
+    pub fn bad_ranges() {
+        assert_eq!(parse("AAs"), Err(Error::Invalid("AAs".to_string())));
+        assert_eq!(parse("AK, A2s-K5s"), Err(Error::Invalid("A2s-K5s".to_string())));
+        assert_eq!(parse("AhAhKd"), Err(Error::Invalid("AhAhKd".to_string())));
+        assert_eq!(parse("XY"), Err(Error::Invalid("XY".to_string())));
+    }
+}
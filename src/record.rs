@@ -0,0 +1,315 @@
+use std::fmt;
+use std::time;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use crate::bet::Bet;
+use crate::chips::Chips;
+use crate::game::{Limit, Name, Stake, street};
+use crate::history::{self, History};
+use crate::poker::Cards;
+
+// bumped on any change that old readers can't ignore
+pub const VERSION: u32 = 1;
+
+pub const SCHEMA: &str = include_str!("../schema/hand-record.v1.json");
+
+#[derive(Debug)]
+pub enum Error {
+    Json(serde_json::Error),
+    Version(u32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(error) => write!(f, "invalid hand record: {}", error),
+            Self::Version(version) => write!(f, "unsupported hand record version {}", version),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Seat {
+    pub position: u8,
+    pub player: String,
+    pub stack: Chips,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Action {
+    pub street: street::Holdem,
+    pub position: u8,
+    pub player: String,
+    pub bet: Bet,
+    // unix time in milliseconds
+    pub at: u64,
+    pub time_used_ms: u64,
+    pub forced: bool,
+    pub all_in: bool,
+    pub timed_out: bool,
+    pub dead: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    // board cards and hands shown in full
+    Public,
+    // hole cards only their owner saw
+    Owner,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Dealt {
+    pub street: street::Holdem,
+    // none for the board
+    pub position: Option<u8>,
+    pub cards: Cards,
+    pub visibility: Visibility,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Shown {
+    pub position: u8,
+    // the whole pocket or only some of it
+    pub cards: Cards,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Winner {
+    pub position: u8,
+    pub amount: Chips,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Pot {
+    pub amount: Chips,
+    pub eligible: Vec<u8>,
+    pub winners: Vec<Winner>,
+}
+
+// all-in insurance bought from the house
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Insurance {
+    pub position: u8,
+    pub equity: f64,
+    pub covered: Chips,
+    pub premium: Chips,
+    pub payout: Chips,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Uncalled {
+    pub position: u8,
+    pub amount: Chips,
+}
+
+// the machine-readable counterpart of the text hand history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Record {
+    pub version: u32,
+    pub id: u64,
+    pub seed: Option<u64>,
+    // unix time in milliseconds
+    pub started_at: u64,
+    pub game: Name,
+    pub limit: Limit,
+    pub stake: Stake,
+    pub table: String,
+    pub table_size: u8,
+    pub button: u8,
+    pub seats: Vec<Seat>,
+    pub actions: Vec<Action>,
+    pub cards: Vec<Dealt>,
+    // in the order they were turned up, records written before there were shows only mark the pockets public
+    #[serde(default)]
+    pub shown: Vec<Shown>,
+    // the rest of the board dealt after the hand for a rabbit hunt
+    #[serde(default)]
+    pub rabbit: Cards,
+    // every board when the hand was run more than once, pots list the winners of every run
+    #[serde(default)]
+    pub runs: Vec<Cards>,
+    #[serde(default)]
+    pub insurance: Vec<Insurance>,
+    pub uncalled: Option<Uncalled>,
+    pub pots: Vec<Pot>,
+    pub total: Chips,
+    pub rake: Chips,
+}
+
+fn millis(time: time::SystemTime) -> u64 {
+    time.duration_since(time::UNIX_EPOCH).map_or(0, |duration| duration.as_millis() as u64)
+}
+
+impl From<&History> for Record {
+    fn from(history: &History) -> Self {
+        let player = |position: u8| history.player(position).unwrap_or_default().to_string();
+        let action = |street: street::Holdem, entry: &history::Entry| Action {
+            street,
+            position: entry.position,
+            player: player(entry.position),
+            bet: entry.bet,
+            at: millis(entry.time),
+            time_used_ms: entry.time_used.as_millis() as u64,
+            forced: entry.bet.is_forced(),
+            all_in: entry.all_in,
+            timed_out: entry.timed_out,
+            dead: entry.dead,
+        };
+        let mut actions: Vec<Action> = history.posts.iter().map(|entry| action(street::Holdem::Preflop, entry)).collect();
+        for street in history.streets.iter() {
+            actions.extend(street.entries.iter().map(|entry| action(street.street, entry)));
+        }
+
+        let mut cards: Vec<Dealt> = history.pockets.iter()
+            .map(|(position, pocket)| {
+                let shown = history.shown.iter().any(|shown| shown.position == *position && shown.cards.len() == pocket.len());
+                Dealt {
+                    street: street::Holdem::Preflop,
+                    position: Some(*position),
+                    cards: pocket.clone(),
+                    visibility: if shown { Visibility::Public } else { Visibility::Owner },
+                }
+            })
+            .collect();
+        cards.extend(history.streets.iter()
+            .filter(|street| !street.cards.is_empty())
+            .map(|street| Dealt { street: street.street, position: None, cards: street.cards.clone(), visibility: Visibility::Public }));
+
+        let mut pots: Vec<Pot> = history.side_pots.iter()
+            .map(|side_pot| Pot {
+                amount: side_pot.amount,
+                eligible: side_pot.eligible.iter().map(|&position| position as u8).collect(),
+                winners: vec![],
+            })
+            .collect();
+        for award in history.awards.iter() {
+            // imported hands may only say who collected what
+            while pots.len() <= award.side_pot {
+                pots.push(Pot { amount: Chips::ZERO, eligible: vec![], winners: vec![] });
+            }
+            let pot = &mut pots[award.side_pot];
+            if history.side_pots.len() <= award.side_pot {
+                pot.amount += award.amount;
+            }
+            pot.winners.push(Winner { position: award.position as u8, amount: award.amount });
+        }
+
+        Record {
+            version: VERSION,
+            id: history.id,
+            seed: history.seed,
+            started_at: millis(history.time),
+            game: history.game,
+            limit: history.limit,
+            stake: history.stake.clone(),
+            table: history.table.clone(),
+            table_size: history.table_size,
+            button: history.button,
+            seats: history.seats.iter()
+                .map(|seat| Seat { position: seat.position, player: seat.player.clone(), stack: seat.stack })
+                .collect(),
+            actions,
+            cards,
+            shown: history.shown.iter().map(|shown| Shown { position: shown.position, cards: shown.cards.clone() }).collect(),
+            rabbit: history.rabbit.clone(),
+            runs: history.runs.clone(),
+            insurance: history.insurance.iter()
+                .map(|cover| Insurance { position: cover.position, equity: cover.equity, covered: cover.covered, premium: cover.premium, payout: cover.payout })
+                .collect(),
+            uncalled: history.uncalled.map(|(position, amount)| Uncalled { position, amount }),
+            pots,
+            total: history.total,
+            rake: history.rake,
+        }
+    }
+}
+
+impl Record {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("hand records always serialize")
+    }
+
+    // refuses records written by a newer version
+    pub fn from_json(s: &str) -> Result<Self, Error> {
+        #[derive(Deserialize)]
+        struct Versioned {
+            version: u32,
+        }
+        let Versioned { version } = serde_json::from_str(s)?;
+        if version > VERSION {
+            return Err(Error::Version(version));
+        }
+        Ok(serde_json::from_str(s)?)
+    }
+
+    pub fn schema() -> String {
+        let schema = schemars::schema_for!(Record);
+        serde_json::to_string_pretty(&schema).expect("schemas always serialize") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::locale::Currency;
+    use crate::player::Player;
+
+    fn played() -> Game<'static> {
+        let stake = Stake::new(Chips(10)).with_currency(Currency::USD);
+        let mut game = Game::new(Name::Texas, Limit::NoLimit, stake, 6).with_seed(42);
+        game.join(Player::blank_player("p1"), 0, Chips(1000)).unwrap();
+        game.join(Player::blank_player("p2"), 2, Chips(400)).unwrap();
+        game.join(Player::blank_player("p3"), 4, Chips(1000)).unwrap();
+        game.start().unwrap();
+        // the first player shoves, the short stack calls all-in for less and the rest call
+        let first = game.to_act().unwrap();
+        let shove = game.range(first).unwrap().max_raise.unwrap();
+        game.act(first, Bet::Raise(shove)).unwrap();
+        while let Some(position) = game.to_act() {
+            let range = game.range(position).unwrap();
+            game.act(position, Bet::Call(range.call)).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    pub fn round_trip() {
+        let game = played();
+        let record = Record::from(game.history().unwrap());
+        assert_eq!(record.seed, game.history().unwrap().seed);
+        // the same seed deals the same cards
+        assert_eq!(played().history().unwrap().pockets, game.history().unwrap().pockets);
+        assert_eq!(record.actions.iter().filter(|action| action.forced).count(), 2);
+        assert!(record.cards.iter().any(|dealt| dealt.position.is_none() && dealt.street == street::Holdem::River));
+        assert_eq!(record.pots.iter().flat_map(|pot| pot.winners.iter()).map(|winner| winner.amount).sum::<Chips>(), record.total);
+
+        let json = record.to_json();
+        assert_eq!(Record::from_json(&json).unwrap(), record);
+        assert!(json.contains(r#""bet":{"type":"small_blind","amount":5}"#));
+        assert!(json.contains(r#""game":"texas","limit":"no_limit""#));
+    }
+
+    #[test]
+    pub fn versions() {
+        let mut record = Record::from(played().history().unwrap());
+        record.version = VERSION + 1;
+        assert!(matches!(Record::from_json(&record.to_json()), Err(Error::Version(2))));
+        assert!(matches!(Record::from_json("{\"version\":1}"), Err(Error::Json(_))));
+    }
+
+    #[test]
+    pub fn published_schema() {
+        assert_eq!(Record::schema(), SCHEMA, "regenerate schema/hand-record.v1.json from Record::schema()");
+    }
+}
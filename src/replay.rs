@@ -0,0 +1,214 @@
+use std::fmt;
+use vec_map::VecMap;
+use crate::bet::Bet;
+use crate::chips::Chips;
+use crate::game::{self, Game};
+use crate::history::History;
+use crate::player::Player;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Game(game::Error),
+    Illegal { position: u8, bet: Bet, error: game::Error },
+    Mismatch { what: String, expected: String, actual: String },
+}
+
+impl From<game::Error> for Error {
+    fn from(error: game::Error) -> Self {
+        Error::Game(error)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Game(error) => write!(f, "{}", error),
+            Self::Illegal { position, bet, error } => write!(f, "seat {} can't {:?}: {}", position + 1, bet, error),
+            Self::Mismatch { what, expected, actual } => write!(f, "{}: expected {}, got {}", what, expected, actual),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+fn check<T: PartialEq + fmt::Debug>(what: impl Into<String>, expected: T, actual: T) -> Result<(), Error> {
+    if expected != actual {
+        return Err(Error::Mismatch { what: what.into(), expected: format!("{:?}", expected), actual: format!("{:?}", actual) });
+    }
+    Ok(())
+}
+
+// plays a recorded hand through the engine with the same seats, button and cards,
+// then checks that the engine collected and paid out the same amounts
+pub fn replay(history: &History) -> Result<Game<'_>, Error> {
+    let mut stake = history.stake.clone().with_buy_in(Chips::ZERO, Chips(u64::MAX));
+    if let Some(ante) = history.posts.iter().find_map(|entry| match entry.bet {
+        Bet::Ante(amount) => Some(amount),
+        _ => None,
+    }) {
+        stake = stake.with_ante_of(ante);
+    }
+    let mut game = Game::new(history.game, history.limit, stake, history.table_size).with_name(&history.table);
+    for seat in history.seats.iter() {
+        game.join(Player::blank_player(&seat.player), seat.position, seat.stack)?;
+    }
+    game.set_button(history.button)?;
+
+    let mut pockets = VecMap::new();
+    for (position, cards) in history.pockets.iter() {
+        pockets.insert(*position as usize, cards.clone());
+    }
+    game.deal_next(pockets, history.board.clone());
+    game.start()?;
+
+    let posts = |history: &History| history.posts.iter().map(|entry| (entry.position, entry.bet)).collect::<Vec<_>>();
+    check("forced bets", posts(history), game.history().map(posts).unwrap_or_default())?;
+
+    for street in history.streets.iter() {
+        for entry in street.entries.iter() {
+            check(format!("street of seat {} acting", entry.position + 1), Some(street.street), game.street())?;
+            game.act(entry.position, entry.bet)
+                .map_err(|error| Error::Illegal { position: entry.position, bet: entry.bet, error })?;
+        }
+    }
+    if game.is_hand_in_progress() {
+        return Err(Error::Mismatch { what: "hand".to_string(), expected: "finished".to_string(), actual: format!("{:?}", game.street()) });
+    }
+    let actual = game.history().ok_or(game::Error::NoHandInProgress)?;
+
+    check("board", &history.board, &actual.board)?;
+    check("total pot", history.total, actual.total)?;
+    check("uncalled bet", history.uncalled, actual.uncalled)?;
+
+    // the engine runs without rake, so every winner may get a little more than the file says
+    let mut raked = Chips::ZERO;
+    for seat in history.seats.iter() {
+        let (expected, won) = (history.won(seat.position), actual.won(seat.position));
+        if won < expected {
+            check(format!("winnings of seat {}", seat.position + 1), expected, won)?;
+        }
+        raked += won - expected;
+
+        let put = history.contributed(seat.position);
+        let returned = history.uncalled.filter(|&(position, _)| position == seat.position).map_or(Chips::ZERO, |(_, amount)| amount);
+        let stack = game.table().seat(seat.position).and_then(|seat| seat.stack()).unwrap_or_default();
+        check(format!("stack of seat {}", seat.position + 1), seat.stack + returned + expected - put, stack - (won - expected))?;
+    }
+    check("rake", history.rake, raked)?;
+
+    Ok(game)
+}
+
+// hand ids with the first problem replaying each of them
+pub fn replay_all(histories: &[History]) -> Vec<(u64, Result<(), Error>)> {
+    histories.iter().map(|history| (history.id, replay(history).map(|_| ()))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pokerstars;
+
+    const CORPUS: &str = "\
+PokerStars Hand #230001: Hold'em No Limit ($0.05/$0.10 USD) - 2026/10/18 12:00:00 UTC
+Table 'Alpha' 6-max Seat #3 is the button
+Seat 1: Alice ($10 in chips)
+Seat 2: Bob ($4 in chips)
+Seat 3: Carol ($12 in chips)
+Alice: posts small blind $0.05
+Bob: posts big blind $0.10
+*** HOLE CARDS ***
+Dealt to Alice [9s 8s]
+Carol: raises $0.20 to $0.30
+Alice: calls $0.25
+Bob: raises $3.70 to $4 and is all-in
+Carol: calls $3.70
+Alice: folds
+*** FLOP *** [Ah 7c 2d]
+*** TURN *** [Ah 7c 2d] [Ks]
+*** RIVER *** [Ah 7c 2d Ks] [3h]
+*** SHOW DOWN ***
+Bob: shows [Kd Kh] (three of a kind, Kings)
+Carol: shows [Ac Qd] (a pair of Aces)
+Bob collected $7.90 from pot
+*** SUMMARY ***
+Total pot $8.30 | Rake $0.40
+Board [Ah 7c 2d Ks 3h]
+Seat 1: Alice (small blind) folded before Flop
+Seat 2: Bob (big blind) showed [Kd Kh] and won ($7.90) with three of a kind, Kings
+Seat 3: Carol (button) showed [Ac Qd] and lost with a pair of Aces
+
+
+
+PokerStars Hand #230002: Hold'em No Limit ($0.05/$0.10 USD) - 2026/10/18 12:01:10 UTC
+Table 'Alpha' 6-max Seat #3 is the button
+Seat 1: Alice ($9.70 in chips)
+Seat 3: Carol ($8 in chips)
+Carol: posts small blind $0.05
+Alice: posts big blind $0.10
+*** HOLE CARDS ***
+Dealt to Alice [Qc Jc]
+Carol: calls $0.05
+Alice: checks
+*** FLOP *** [Tc 9c 2h]
+Alice has timed out
+Alice: checks
+Carol said, \"gl\"
+Carol: bets $0.20
+Alice: raises $0.40 to $0.60
+Carol: folds
+Uncalled bet ($0.40) returned to Alice
+Alice collected $0.60 from pot
+Alice: doesn't show hand
+*** SUMMARY ***
+Total pot $0.60 | Rake $0
+Board [Tc 9c 2h]
+Seat 1: Alice (big blind) collected ($0.60)
+Seat 3: Carol (button) (small blind) folded on the Flop
+";
+
+    #[test]
+    pub fn replay_corpus() {
+        let histories = pokerstars::parse(CORPUS).unwrap();
+        assert_eq!(histories.len(), 2);
+        assert_eq!(replay_all(&histories), vec![(230001, Ok(())), (230002, Ok(()))]);
+
+        let game = replay(&histories[0]).unwrap();
+        let stack = |position: u8| game.table().seat(position).and_then(|seat| seat.stack());
+        assert_eq!((stack(0), stack(1), stack(2)), (Some(Chips(970)), Some(Chips(830)), Some(Chips(800))));
+    }
+
+    #[test]
+    pub fn mismatches() {
+        let mut histories = pokerstars::parse(CORPUS).unwrap();
+        histories[0].awards[0].amount = Chips(800);
+        assert_eq!(replay(&histories[0]).err(), Some(Error::Mismatch {
+            what: "rake".to_string(),
+            expected: "Chips(40)".to_string(),
+            actual: "Chips(30)".to_string(),
+        }));
+
+        // without alice's raise carol folds out of turn
+        histories[1].streets[1].entries.remove(2);
+        assert_eq!(replay(&histories[1]).err(), Some(Error::Illegal { position: 2, bet: Bet::Fold, error: game::Error::NotYourTurn(2) }));
+    }
+
+    #[test]
+    pub fn replay_exported_hand() {
+        let mut game = crate::game::Game::new(game::Name::Texas, game::Limit::NoLimit, game::Stake::new(Chips(10)), 6);
+        for (position, name) in [(0, "p1"), (2, "p2"), (4, "p3")] {
+            game.join(Player::blank_player(name), position, Chips(1000)).unwrap();
+        }
+        game.start().unwrap();
+        while let Some(position) = game.to_act() {
+            let range = game.range(position).unwrap();
+            let bet = if range.call.is_zero() { Bet::Check } else { Bet::Call(range.call) };
+            game.act(position, bet).unwrap();
+        }
+
+        let text = pokerstars::write(game.history().unwrap(), None);
+        let imported = pokerstars::parse(&text).unwrap();
+        assert_eq!(imported.len(), 1);
+        replay(&imported[0]).unwrap();
+    }
+}
@@ -1,7 +1,11 @@
-use std::time;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::clock::Clock;
+use crate::chips::{self, Chips};
 use crate::player::Player;
 use crate::bet::Bet;
 use crate::bet::AutoPlay;
+use crate::button::Presence;
 
 #[derive(Debug)]
 enum State<'a> {
@@ -9,11 +13,11 @@ enum State<'a> {
     Reserved(Player<'a>),
     Ready(Slot<'a>),
     WaitBigBlind(Slot<'a>),
-    PostBigBlind(Slot<'a>, u16),
-    Playing(Slot<'a>, Bet),
+    PostBigBlind(Slot<'a>, Chips),
+    Playing(Slot<'a>),
     AutoPlaying(Slot<'a>, AutoPlay),
-    AllIn(Slot<'a>, Bet),
-    Betting(Slot<'a>, Bet),
+    AllIn(Slot<'a>),
+    Betting(Slot<'a>),
     Folding(Slot<'a>),
     SittingOut(Slot<'a>),
     Idle(Slot<'a>),
@@ -21,32 +25,69 @@ enum State<'a> {
     Gone(Slot<'a>),
 }
 
-impl State<'_> {
-    pub fn check(self) -> Self {
+impl<'a> State<'a> {
+    fn can_act(&self) -> bool {
+        matches!(self, Self::Playing(_) | Self::Betting(_) | Self::AutoPlaying(..))
+    }
+
+    fn acting(self) -> Result<Slot<'a>, Box<Self>> {
         match self {
-            Self::Playing(slot, _bet) => Self::Betting(slot,  Bet::Check),
-            _ => self,
+            Self::Playing(slot) | Self::Betting(slot) | Self::AutoPlaying(slot, _) => Ok(slot),
+            state => Err(Box::new(state)),
+        }
+    }
+
+    fn placed(slot: Slot<'a>) -> Self {
+        if slot.current_stack_amount.is_zero() { Self::AllIn(slot) } else { Self::Betting(slot) }
+    }
+
+    pub fn check(self) -> Self {
+        match self.acting() {
+            Ok(slot) => Self::Betting(slot),
+            Err(state) => *state,
         }
     }
 
     pub fn fold(self) -> Self {
-        match self {
-            Self::Playing(slot, _bet) => Self::Folding(slot),
-            _ => self,
+        match self.acting() {
+            Ok(slot) => Self::Folding(slot),
+            Err(state) => *state,
         }
     }
 
-    pub fn raise(self, amount: u32) -> Self {
-        match self {
-            Self::Playing(slot, bet) => Self::Betting(slot,  Bet::Raise(amount)),
-            _ => self,
+    pub fn raise(self, amount: Chips) -> Self {
+        match self.acting() {
+            Ok(mut slot) => {
+                slot.put(amount.saturating_sub(slot.amount_put));
+                Self::placed(slot)
+            },
+            Err(state) => *state,
+        }
+    }
+
+    pub fn call(self, amount: Chips) -> Self {
+        match self.acting() {
+            Ok(mut slot) => {
+                slot.put(amount);
+                Self::placed(slot)
+            },
+            Err(state) => *state,
         }
     }
 
-    pub fn call(self, amount: u32) -> Self {
+    // forced bets leave the seat to act voluntarily later; antes are dead and don't count as a bet
+    pub fn post(self, bet: Bet) -> Self {
         match self {
-            Self::Playing(slot, bet) => Self::Betting(slot,  Bet::Call(amount)),
-            _ => self,
+            Self::Playing(mut slot) => {
+                match bet {
+                    Bet::Ante(amount) => { slot.put_dead(amount); },
+                    Bet::SmallBlind(amount) | Bet::BigBlind(amount) | Bet::Straddle(amount)
+                    | Bet::BringIn(amount) | Bet::GuestBlind(amount) => { slot.put(amount); },
+                    _ => {},
+                }
+                if slot.current_stack_amount.is_zero() { Self::AllIn(slot) } else { Self::Playing(slot) }
+            },
+            state => state,
         }
     }
 }
@@ -57,18 +98,75 @@ pub enum NetworkStatus {
     Online,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MissedBlinds {
+    pub small_blind: bool,
+    pub big_blind: bool,
+}
+
+// tops the stack back up between hands once it drops below the threshold
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoRebuy {
+    pub threshold: Chips,
+    pub to: Chips,
+}
+
 #[derive(Debug)]
 struct Slot<'a> {
     player: Player<'a>,
     network_status: NetworkStatus,
-    current_stack_amount: u16,
-    amount_put: u16,
-    rebuy_amount: u16,
-    clock: time::Duration,
+    current_stack_amount: Chips,
+    amount_put: Chips,
+    rebuy_amount: Chips,
+    clock: Clock,
     auto_play: AutoPlay,
+    missed_blinds: MissedBlinds,
+    sit_out_next_hand: bool,
+    offline_hands: u8,
+    all_in_protections: u8,
+    auto_rebuy: Option<AutoRebuy>,
+    // losing hands go in the muck at showdown instead of being turned up
+    auto_muck: bool,
+    // how many times the player agrees to run the board after an all-in
+    runs: u8,
+    // buys all-in insurance whenever it's offered
+    insured: bool,
 }
 
-impl Slot<'_> {
+impl<'a> Slot<'a> {
+    fn new(player: Player<'a>, amount: Chips) -> Self {
+        Slot {
+            player,
+            network_status: NetworkStatus::Online,
+            current_stack_amount: amount,
+            amount_put: Chips::ZERO,
+            rebuy_amount: Chips::ZERO,
+            clock: Clock::default(),
+            auto_play: AutoPlay::CheckFold,
+            missed_blinds: MissedBlinds::default(),
+            sit_out_next_hand: false,
+            offline_hands: 0,
+            all_in_protections: 0,
+            auto_rebuy: None,
+            auto_muck: false,
+            runs: 1,
+            insured: false,
+        }
+    }
+
+    fn put(&mut self, amount: Chips) -> Chips {
+        let amount = amount.min(self.current_stack_amount);
+        self.current_stack_amount -= amount;
+        self.amount_put += amount;
+        amount
+    }
+
+    fn put_dead(&mut self, amount: Chips) -> Chips {
+        let amount = amount.min(self.current_stack_amount);
+        self.current_stack_amount -= amount;
+        amount
+    }
+
     pub fn offline(&mut self) {
         self.network_status = NetworkStatus::Offline;
     }
@@ -87,7 +185,670 @@ impl Slot<'_> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Empty,
+    Reserved,
+    Ready,
+    WaitBigBlind,
+    PostBigBlind,
+    Playing,
+    AutoPlaying,
+    AllIn,
+    Betting,
+    Folding,
+    SittingOut,
+    Idle,
+    Away,
+    Gone,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transition {
+    Reserve,
+    BuyIn,
+    SitOut,
+    ComeBack,
+    GoAway,
+    Leave,
+    Timeout,
+    DealIn,
+    StartHand,
+    Bet,
+    PreAction,
+    Protect,
+    FinishHand,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub position: u8,
+    pub transition: Transition,
+    pub from: Status,
+    pub to: Status,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    IllegalTransition { position: u8, from: Status, transition: Transition },
+    Chips(chips::Error),
+}
+
+impl From<chips::Error> for Error {
+    fn from(error: chips::Error) -> Self {
+        Error::Chips(error)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IllegalTransition { position, from, transition } =>
+                write!(f, "seat {}: can't {:?} from {:?}", position, transition, from),
+            Self::Chips(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl<'a> State<'a> {
+    fn slot(&self) -> Option<&Slot<'a>> {
+        match self {
+            State::Empty | State::Reserved(_) => None,
+            State::Ready(slot)
+            | State::WaitBigBlind(slot)
+            | State::PostBigBlind(slot, _)
+            | State::Playing(slot)
+            | State::AutoPlaying(slot, _)
+            | State::AllIn(slot)
+            | State::Betting(slot)
+            | State::Folding(slot)
+            | State::SittingOut(slot)
+            | State::Idle(slot)
+            | State::Away(slot)
+            | State::Gone(slot) => Some(slot),
+        }
+    }
+
+    fn slot_mut(&mut self) -> Option<&mut Slot<'a>> {
+        match self {
+            State::Empty | State::Reserved(_) => None,
+            State::Ready(slot)
+            | State::WaitBigBlind(slot)
+            | State::PostBigBlind(slot, _)
+            | State::Playing(slot)
+            | State::AutoPlaying(slot, _)
+            | State::AllIn(slot)
+            | State::Betting(slot)
+            | State::Folding(slot)
+            | State::SittingOut(slot)
+            | State::Idle(slot)
+            | State::Away(slot)
+            | State::Gone(slot) => Some(slot),
+        }
+    }
+
+    fn status(&self) -> Status {
+        match self {
+            Self::Empty => Status::Empty,
+            Self::Reserved(_) => Status::Reserved,
+            Self::Ready(_) => Status::Ready,
+            Self::WaitBigBlind(_) => Status::WaitBigBlind,
+            Self::PostBigBlind(..) => Status::PostBigBlind,
+            Self::Playing(_) => Status::Playing,
+            Self::AutoPlaying(..) => Status::AutoPlaying,
+            Self::AllIn(_) => Status::AllIn,
+            Self::Betting(_) => Status::Betting,
+            Self::Folding(_) => Status::Folding,
+            Self::SittingOut(_) => Status::SittingOut,
+            Self::Idle(_) => Status::Idle,
+            Self::Away(_) => Status::Away,
+            Self::Gone(_) => Status::Gone,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Seat<'a> {
+    position: u8,
     state: State<'a>,
 }
+
+impl<'a> Seat<'a> {
+    pub fn new(position: u8) -> Self {
+        Seat { position, state: State::Empty }
+    }
+
+    pub fn position(&self) -> u8 {
+        self.position
+    }
+
+    pub fn status(&self) -> Status {
+        self.state.status()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self.state, State::Empty)
+    }
+
+    pub fn is_in_hand(&self) -> bool {
+        matches!(self.state,
+            State::Playing(_) | State::AutoPlaying(..) | State::AllIn(_) | State::Betting(_) | State::Folding(_))
+    }
+
+    pub fn player(&self) -> Option<&Player<'a>> {
+        match &self.state {
+            State::Empty => None,
+            State::Reserved(player) => Some(player),
+            _ => self.slot().map(|slot| &slot.player),
+        }
+    }
+
+    pub fn stack(&self) -> Option<Chips> {
+        self.slot().map(|slot| slot.current_stack_amount)
+    }
+
+    pub fn presence(&self) -> Presence {
+        match self.state {
+            State::Empty | State::Reserved(_) => Presence::Empty,
+            State::SittingOut(_) | State::Idle(_) | State::Away(_) | State::Gone(_) => Presence::SittingOut,
+            State::WaitBigBlind(_) => Presence::WaitBigBlind,
+            State::PostBigBlind(_, amount) => Presence::PostBigBlind(amount),
+            _ => Presence::Active,
+        }
+    }
+
+    fn slot(&self) -> Option<&Slot<'a>> {
+        self.state.slot()
+    }
+
+    fn slot_mut(&mut self) -> Option<&mut Slot<'a>> {
+        self.state.slot_mut()
+    }
+
+    pub fn missed_blinds(&self) -> MissedBlinds {
+        self.slot().map_or(MissedBlinds::default(), |slot| slot.missed_blinds)
+    }
+
+    pub fn miss_small_blind(&mut self) {
+        if let Some(slot) = self.slot_mut() {
+            slot.missed_blinds.small_blind = true;
+        }
+    }
+
+    pub fn miss_big_blind(&mut self) {
+        if let Some(slot) = self.slot_mut() {
+            slot.missed_blinds.big_blind = true;
+        }
+    }
+
+    pub fn is_sitting_out_next_hand(&self) -> bool {
+        self.slot().is_some_and(|slot| slot.sit_out_next_hand)
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.slot().is_some_and(|slot| slot.network_status == NetworkStatus::Online)
+    }
+
+    pub fn disconnect(&mut self) {
+        if let Some(slot) = self.slot_mut() {
+            slot.offline();
+        }
+    }
+
+    // hands missed while offline are forgotten once the player is back
+    pub fn reconnect(&mut self) -> u8 {
+        match self.slot_mut() {
+            Some(slot) => {
+                slot.online();
+                std::mem::take(&mut slot.offline_hands)
+            },
+            None => 0,
+        }
+    }
+
+    pub fn offline_hands(&self) -> u8 {
+        self.slot().map_or(0, |slot| slot.offline_hands)
+    }
+
+    pub fn miss_hand_offline(&mut self) -> u8 {
+        match self.slot_mut() {
+            Some(slot) => {
+                slot.offline_hands = slot.offline_hands.saturating_add(1);
+                slot.offline_hands
+            },
+            None => 0,
+        }
+    }
+
+    pub fn all_in_protections(&self) -> u8 {
+        self.slot().map_or(0, |slot| slot.all_in_protections)
+    }
+
+    // the state is handed back untouched when the transition is illegal
+    fn transition<F>(&mut self, transition: Transition, f: F) -> Result<Event, Error>
+    where F: FnOnce(State<'a>) -> Result<State<'a>, Box<State<'a>>>
+    {
+        let from = self.status();
+        match f(std::mem::replace(&mut self.state, State::Empty)) {
+            Ok(state) => {
+                self.state = state;
+                Ok(Event { position: self.position, transition, from, to: self.status() })
+            },
+            Err(state) => {
+                self.state = *state;
+                Err(Error::IllegalTransition { position: self.position, from, transition })
+            },
+        }
+    }
+
+    pub fn reserve(&mut self, player: Player<'a>) -> Result<Event, Error> {
+        self.transition(Transition::Reserve, |state| match state {
+            State::Empty => Ok(State::Reserved(player)),
+            state => Err(Box::new(state)),
+        })
+    }
+
+    pub fn buy_in(&mut self, amount: Chips, wait_big_blind: bool) -> Result<Event, Error> {
+        self.transition(Transition::BuyIn, |state| match state {
+            State::Reserved(player) => {
+                let slot = Slot::new(player, amount);
+                Ok(if wait_big_blind { State::WaitBigBlind(slot) } else { State::Ready(slot) })
+            },
+            state => Err(Box::new(state)),
+        })
+    }
+
+    // during a hand the seat is only flagged and sits out once the hand is over
+    pub fn sit_out(&mut self) -> Result<Event, Error> {
+        self.transition(Transition::SitOut, |state| match state {
+            State::Ready(slot)
+            | State::WaitBigBlind(slot)
+            | State::PostBigBlind(slot, _)
+            | State::Idle(slot)
+            | State::Away(slot) => Ok(State::SittingOut(slot)),
+            mut state if matches!(state.status(), Status::Playing | Status::AutoPlaying | Status::AllIn | Status::Betting | Status::Folding) => {
+                if let Some(slot) = state.slot_mut() {
+                    slot.sit_out_next_hand = true;
+                }
+                Ok(state)
+            },
+            state => Err(Box::new(state)),
+        })
+    }
+
+    // a player who missed the big blind either waits for it or posts it live with the small blind dead
+    pub fn come_back(&mut self, post: bool, small_blind: Chips, big_blind: Chips) -> Result<Event, Error> {
+        self.transition(Transition::ComeBack, |state| match state {
+            State::SittingOut(slot) | State::Idle(slot) | State::Away(slot) => {
+                // a missed small blind is owed like a missed big blind, dead on top of the big blind
+                let missed = slot.missed_blinds;
+                Ok(if !missed.small_blind && !missed.big_blind {
+                    State::Ready(slot)
+                } else if post {
+                    let dead = if missed.small_blind { small_blind } else { Chips::ZERO };
+                    State::PostBigBlind(slot, big_blind + dead)
+                } else {
+                    State::WaitBigBlind(slot)
+                })
+            },
+            mut state if state.slot().is_some_and(|slot| slot.sit_out_next_hand) => {
+                if let Some(slot) = state.slot_mut() {
+                    slot.sit_out_next_hand = false;
+                }
+                Ok(state)
+            },
+            state => Err(Box::new(state)),
+        })
+    }
+
+    pub fn go_away(&mut self) -> Result<Event, Error> {
+        self.transition(Transition::GoAway, |state| match state {
+            State::Ready(slot)
+            | State::WaitBigBlind(slot)
+            | State::PostBigBlind(slot, _)
+            | State::SittingOut(slot)
+            | State::Idle(slot) => Ok(State::Away(slot)),
+            state => Err(Box::new(state)),
+        })
+    }
+
+    // leaving in the middle of a hand keeps the seat until the hand is finished
+    pub fn leave(&mut self) -> Result<Event, Error> {
+        self.transition(Transition::Leave, |state| match state {
+            State::Reserved(_)
+            | State::Ready(_)
+            | State::WaitBigBlind(_)
+            | State::PostBigBlind(..)
+            | State::SittingOut(_)
+            | State::Idle(_)
+            | State::Away(_) => Ok(State::Empty),
+            State::Playing(slot)
+            | State::AutoPlaying(slot, _)
+            | State::AllIn(slot)
+            | State::Betting(slot)
+            | State::Folding(slot) => Ok(State::Gone(slot)),
+            state => Err(Box::new(state)),
+        })
+    }
+
+    // only between hands, a seat in a hand has to fold first
+    pub fn timeout(&mut self) -> Result<Event, Error> {
+        self.transition(Transition::Timeout, |state| match state {
+            State::Ready(slot)
+            | State::WaitBigBlind(slot)
+            | State::PostBigBlind(slot, _) => Ok(State::Idle(slot)),
+            state => Err(Box::new(state)),
+        })
+    }
+
+    pub fn deal_in(&mut self) -> Result<Event, Error> {
+        self.transition(Transition::DealIn, |state| match state {
+            State::Ready(mut slot) | State::WaitBigBlind(mut slot) | State::PostBigBlind(mut slot, _) => {
+                slot.missed_blinds = MissedBlinds::default();
+                Ok(State::Ready(slot))
+            },
+            state => Err(Box::new(state)),
+        })
+    }
+
+    pub fn start_hand(&mut self) -> Result<Event, Error> {
+        self.transition(Transition::StartHand, |state| match state {
+            State::Ready(mut slot) => {
+                slot.amount_put = Chips::ZERO;
+                Ok(State::Playing(slot))
+            },
+            state => Err(Box::new(state)),
+        })
+    }
+
+    pub fn is_folded(&self) -> bool {
+        matches!(self.state, State::Folding(_))
+    }
+
+    pub fn is_all_in(&self) -> bool {
+        matches!(self.state, State::AllIn(_))
+    }
+
+    pub fn can_act(&self) -> bool {
+        self.state.can_act()
+    }
+
+    pub fn put(&self) -> Chips {
+        self.slot().map_or(Chips::ZERO, |slot| slot.amount_put)
+    }
+
+    // chips moved from the stack into the pot
+    pub fn bet(&mut self, bet: Bet) -> Result<Chips, Error> {
+        let before = self.stack().unwrap_or_default();
+        self.transition(Transition::Bet, |state| match bet {
+            _ if bet.is_forced() && matches!(state, State::Playing(_)) => Ok(state.post(bet)),
+            Bet::Check if state.can_act() => Ok(state.check()),
+            Bet::Fold if state.can_act() => Ok(state.fold()),
+            Bet::Call(amount) if state.can_act() => Ok(state.call(amount)),
+            Bet::Raise(amount) if state.can_act() => Ok(state.raise(amount)),
+            _ => Err(Box::new(state)),
+        })?;
+        Ok(before.checked_sub(self.stack().unwrap_or_default())?)
+    }
+
+    pub fn new_street(&mut self) {
+        self.state = match std::mem::replace(&mut self.state, State::Empty) {
+            State::Betting(slot) => State::Playing(slot),
+            state => state,
+        };
+        if let Some(slot) = self.slot_mut() {
+            slot.amount_put = Chips::ZERO;
+        }
+    }
+
+    pub fn pre_action(&self) -> Option<AutoPlay> {
+        match self.state {
+            State::AutoPlaying(_, auto_play) => Some(auto_play),
+            _ => None,
+        }
+    }
+
+    pub fn queue(&mut self, auto_play: AutoPlay) -> Result<Event, Error> {
+        self.transition(Transition::PreAction, |state| match state {
+            State::Playing(slot) | State::Betting(slot) | State::AutoPlaying(slot, _) => Ok(State::AutoPlaying(slot, auto_play)),
+            state => Err(Box::new(state)),
+        })
+    }
+
+    pub fn cancel_pre_action(&mut self) -> Result<Event, Error> {
+        self.transition(Transition::PreAction, |state| match state {
+            State::AutoPlaying(slot, _) => Ok(State::Playing(slot)),
+            state => Err(Box::new(state)),
+        })
+    }
+
+    pub fn win(&mut self, amount: Chips) -> Result<(), chips::Error> {
+        if let Some(slot) = self.slot_mut() {
+            slot.current_stack_amount = slot.current_stack_amount.checked_add(amount)?;
+        }
+        Ok(())
+    }
+
+    pub fn clock(&self) -> Option<&Clock> {
+        self.slot().map(|slot| &slot.clock)
+    }
+
+    pub fn clock_mut(&mut self) -> Option<&mut Clock> {
+        self.slot_mut().map(|slot| &mut slot.clock)
+    }
+
+    // what the dealer does for the player once the clock runs out
+    pub fn auto_play(&self) -> Option<AutoPlay> {
+        self.slot().map(|slot| slot.auto_play)
+    }
+
+    pub fn set_auto_play(&mut self, auto_play: AutoPlay) {
+        if let Some(slot) = self.slot_mut() {
+            slot.auto_play = auto_play;
+        }
+    }
+
+    pub fn pending_rebuy(&self) -> Chips {
+        self.slot().map_or(Chips::ZERO, |slot| slot.rebuy_amount)
+    }
+
+    pub fn request_rebuy(&mut self, amount: Chips) {
+        if let Some(slot) = self.slot_mut() {
+            slot.rebuy_amount += amount;
+        }
+    }
+
+    // stacks never change in the middle of a hand
+    pub fn apply_rebuy(&mut self) -> Chips {
+        if self.is_in_hand() {
+            return Chips::ZERO;
+        }
+        match self.slot_mut() {
+            Some(slot) => {
+                let amount = std::mem::take(&mut slot.rebuy_amount);
+                slot.current_stack_amount += amount;
+                amount
+            },
+            None => Chips::ZERO,
+        }
+    }
+
+    pub fn auto_rebuy(&self) -> Option<AutoRebuy> {
+        self.slot().and_then(|slot| slot.auto_rebuy)
+    }
+
+    pub fn set_auto_rebuy(&mut self, auto_rebuy: Option<AutoRebuy>) {
+        if let Some(slot) = self.slot_mut() {
+            slot.auto_rebuy = auto_rebuy;
+        }
+    }
+
+    pub fn auto_muck(&self) -> bool {
+        self.slot().is_some_and(|slot| slot.auto_muck)
+    }
+
+    pub fn set_auto_muck(&mut self, auto_muck: bool) {
+        if let Some(slot) = self.slot_mut() {
+            slot.auto_muck = auto_muck;
+        }
+    }
+
+    pub fn runs(&self) -> u8 {
+        self.slot().map_or(1, |slot| slot.runs)
+    }
+
+    pub fn set_runs(&mut self, runs: u8) {
+        if let Some(slot) = self.slot_mut() {
+            slot.runs = runs;
+        }
+    }
+
+    pub fn is_insured(&self) -> bool {
+        self.slot().is_some_and(|slot| slot.insured)
+    }
+
+    pub fn set_insured(&mut self, insured: bool) {
+        if let Some(slot) = self.slot_mut() {
+            slot.insured = insured;
+        }
+    }
+
+    // takes chips off the stack outside the pot, as many as there are
+    pub fn pay(&mut self, amount: Chips) -> Chips {
+        match self.slot_mut() {
+            Some(slot) => {
+                let amount = amount.min(slot.current_stack_amount);
+                slot.current_stack_amount -= amount;
+                amount
+            },
+            None => Chips::ZERO,
+        }
+    }
+
+    // disconnected all-in protection: the player stays in for what is already in the pot
+    pub fn protect(&mut self) -> Result<Event, Error> {
+        self.transition(Transition::Protect, |state| match state.acting() {
+            Ok(mut slot) => {
+                slot.all_in_protections = slot.all_in_protections.saturating_add(1);
+                Ok(State::AllIn(slot))
+            },
+            Err(state) => Err(state),
+        })
+    }
+
+    pub fn finish_hand(&mut self) -> Result<Event, Error> {
+        self.transition(Transition::FinishHand, |state| match state {
+            State::Gone(_) => Ok(State::Empty),
+            State::Playing(mut slot)
+            | State::AutoPlaying(mut slot, _)
+            | State::AllIn(mut slot)
+            | State::Betting(mut slot)
+            | State::Folding(mut slot) => {
+                slot.amount_put = Chips::ZERO;
+                if slot.sit_out_next_hand || (slot.current_stack_amount + slot.rebuy_amount).is_zero() {
+                    slot.sit_out_next_hand = false;
+                    Ok(State::SittingOut(slot))
+                } else {
+                    Ok(State::Ready(slot))
+                }
+            },
+            state => Err(Box::new(state)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seated() -> Seat<'static> {
+        let mut seat = Seat::new(3);
+        seat.reserve(Player::blank_player("player-1")).unwrap();
+        seat.buy_in(Chips(1000), false).unwrap();
+        seat
+    }
+
+    #[test]
+    pub fn reserve_and_buy_in() {
+        let mut seat = Seat::new(3);
+        assert_eq!(seat.buy_in(Chips(1000), false), Err(Error::IllegalTransition { position: 3, from: Status::Empty, transition: Transition::BuyIn }));
+        assert_eq!(seat.reserve(Player::blank_player("player-1")),
+            Ok(Event { position: 3, transition: Transition::Reserve, from: Status::Empty, to: Status::Reserved }));
+        assert!(seat.reserve(Player::blank_player("player-2")).is_err());
+        assert_eq!(seat.buy_in(Chips(1000), true).map(|event| event.to), Ok(Status::WaitBigBlind));
+        assert_eq!(seat.stack(), Some(Chips(1000)));
+        assert_eq!(seat.player().map(|player| &*player.name), Some("player-1"));
+    }
+
+    #[test]
+    pub fn sit_out_and_come_back() {
+        let mut seat = seated();
+        assert_eq!(seat.sit_out().map(|event| event.to), Ok(Status::SittingOut));
+        assert!(seat.sit_out().is_err());
+        assert_eq!(seat.come_back(false, Chips(5), Chips(10)).map(|event| event.to), Ok(Status::Ready));
+        assert!(seat.come_back(false, Chips(5), Chips(10)).is_err());
+    }
+
+    #[test]
+    pub fn missed_blinds_on_come_back() {
+        let mut seat = seated();
+        seat.sit_out().unwrap();
+        seat.miss_small_blind();
+        seat.miss_big_blind();
+        assert_eq!(seat.come_back(false, Chips(5), Chips(10)).map(|event| event.to), Ok(Status::WaitBigBlind));
+        seat.sit_out().unwrap();
+        assert_eq!(seat.come_back(true, Chips(5), Chips(10)).map(|event| event.to), Ok(Status::PostBigBlind));
+        assert_eq!(seat.presence(), Presence::PostBigBlind(Chips(15)));
+        seat.deal_in().unwrap();
+        assert_eq!(seat.missed_blinds(), MissedBlinds::default());
+        assert_eq!(seat.status(), Status::Ready);
+    }
+
+    #[test]
+    pub fn missed_small_blind_on_come_back() {
+        let mut seat = seated();
+        seat.sit_out().unwrap();
+        seat.miss_small_blind();
+        assert_eq!(seat.come_back(false, Chips(5), Chips(10)).map(|event| event.to), Ok(Status::WaitBigBlind));
+        seat.sit_out().unwrap();
+        assert_eq!(seat.come_back(true, Chips(5), Chips(10)).map(|event| event.to), Ok(Status::PostBigBlind));
+        assert_eq!(seat.presence(), Presence::PostBigBlind(Chips(15)));
+    }
+
+    #[test]
+    pub fn away_timeout_and_leave() {
+        let mut seat = seated();
+        assert_eq!(seat.timeout().map(|event| event.to), Ok(Status::Idle));
+        assert!(seat.timeout().is_err());
+        assert_eq!(seat.go_away().map(|event| event.to), Ok(Status::Away));
+        assert!(seat.go_away().is_err());
+        assert_eq!(seat.leave().map(|event| event.to), Ok(Status::Empty));
+        assert!(seat.leave().is_err());
+        assert!(seat.finish_hand().is_err());
+    }
+
+    #[test]
+    pub fn no_timeout_in_a_hand() {
+        let mut seat = seated();
+        seat.start_hand().unwrap();
+        seat.bet(Bet::BigBlind(Chips(10))).unwrap();
+        assert_eq!(seat.timeout(), Err(Error::IllegalTransition { position: 3, from: Status::Playing, transition: Transition::Timeout }));
+        seat.bet(Bet::Fold).unwrap();
+        assert!(seat.timeout().is_err());
+        assert_eq!(seat.finish_hand().map(|event| event.to), Ok(Status::Ready));
+        assert_eq!(seat.timeout().map(|event| event.to), Ok(Status::Idle));
+    }
+
+    #[test]
+    pub fn offline_hands_saturate() {
+        let mut seat = seated();
+        for _ in 0..300 {
+            seat.miss_hand_offline();
+        }
+        assert_eq!(seat.offline_hands(), u8::MAX);
+    }
+}
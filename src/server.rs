@@ -0,0 +1,911 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinSet;
+use crate::bet::{AutoPlay, Bet};
+use crate::chips::Chips;
+use crate::clock::Alert;
+use crate::events;
+use crate::game::{self, Game};
+use crate::player::Player;
+use crate::protocol::{self, Event, Feed, Viewer};
+use crate::record::Record;
+use crate::storage::{self, Storage};
+use crate::table;
+
+// clients talk json, over tcp one request or message per line, see docs/websocket.md
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    Login { name: String, token: String },
+    // picks up a dropped connection's player and tables
+    Resume { token: String },
+    Heartbeat,
+    Tables,
+    Subscribe { table: String },
+    Unsubscribe { table: String },
+    Join { table: String, position: u8, amount: Chips },
+    Act { table: String, bet: Bet },
+    PreAct { table: String, auto_play: AutoPlay },
+    CancelPreAct { table: String },
+    // losing hands go in the muck at showdown instead of being turned up
+    AutoMuck { table: String, auto_muck: bool },
+    // how many times to run the board after an all-in, if everyone else agrees
+    RunIt { table: String, times: u8 },
+    Insure { table: String, insured: bool },
+    Leave { table: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Listing {
+    pub game: protocol::Game,
+    pub players: u8,
+    pub hand_in_progress: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    // resume picks the session up again if the connection drops, within the server's resume window
+    LoggedIn { player: String, resume: String },
+    Heartbeat,
+    Tables { tables: Vec<Listing> },
+    // the whole table as the client's seat sees it, after every change
+    Table { table: protocol::Table },
+    Event { table: String, event: Event },
+    // the player to act is running out of time, seconds left on the clock
+    Warning { table: String, position: u8, remaining: u64 },
+    TimeBank { table: String, position: u8, remaining: u64 },
+    Left { table: String, cash_out: Option<Chips> },
+    Error { reason: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    NotLoggedIn,
+    AlreadyLoggedIn,
+    Unauthorized,
+    InvalidResumeToken,
+    UnknownTable(String),
+    NotSeated(String),
+    Game(game::Error),
+    // no new hands at the table until it can be saved again
+    Storage { table: String, reason: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotLoggedIn => write!(f, "log in first"),
+            Self::AlreadyLoggedIn => write!(f, "already logged in"),
+            Self::Unauthorized => write!(f, "wrong name or token"),
+            Self::InvalidResumeToken => write!(f, "nothing to resume"),
+            Self::UnknownTable(table) => write!(f, "no table {}", table),
+            Self::NotSeated(table) => write!(f, "not seated at {}", table),
+            Self::Game(error) => write!(f, "{}", error),
+            Self::Storage { table, reason } => write!(f, "table {} can't be saved, no new hands until it can: {}", table, reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<game::Error> for Error {
+    fn from(error: game::Error) -> Self {
+        Error::Game(error)
+    }
+}
+
+impl From<table::Error> for Error {
+    fn from(error: table::Error) -> Self {
+        Error::Game(game::Error::Table(error))
+    }
+}
+
+struct Watcher {
+    session: u64,
+    player: Option<Arc<str>>,
+    sender: mpsc::UnboundedSender<Message>,
+}
+
+enum Write {
+    Setup(String, storage::Setup),
+    Log(String, Box<events::Log>),
+}
+
+// why each table's last write didn't make it into storage
+type Failures = Arc<Mutex<HashMap<String, String>>>;
+
+#[derive(Clone)]
+struct Queue {
+    sender: mpsc::UnboundedSender<Write>,
+    failures: Failures,
+}
+
+impl Queue {
+    fn send(&self, write: Write) {
+        let _ = self.sender.send(write);
+    }
+
+    fn failure(&self, table: &str) -> Option<String> {
+        self.failures.lock().unwrap().get(table).cloned()
+    }
+}
+
+// storage gets written in order on a thread of its own, no table waits on the disk while the
+// tables are locked. dropping the writer waits for whatever is still queued.
+struct Writer {
+    queue: Option<Queue>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Writer {
+    fn new(mut storage: Box<dyn Storage>) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let failures = Failures::default();
+        let failed = failures.clone();
+        let thread = thread::spawn(move || {
+            // the last event in storage and setups still to save, both tried again with the next write
+            let mut saved: HashMap<String, u64> = HashMap::new();
+            let mut setups: HashMap<String, storage::Setup> = HashMap::new();
+            while let Some(write) = receiver.blocking_recv() {
+                let (table, log) = match write {
+                    Write::Setup(table, setup) => {
+                        saved.insert(table.clone(), 0);
+                        setups.insert(table.clone(), setup);
+                        (table, None)
+                    },
+                    Write::Log(table, log) => (table, Some(log)),
+                };
+                let mut result = Ok(());
+                if let Some(setup) = setups.get(&table) {
+                    result = storage.save_setup(&table, setup);
+                    if result.is_ok() {
+                        setups.remove(&table);
+                    }
+                }
+                if let (Ok(()), Some(log)) = (&result, log) {
+                    let saved = saved.entry(table.clone()).or_default();
+                    result = storage::persist(storage.as_mut(), &table, &log, *saved).map(|now| *saved = now);
+                }
+                match result {
+                    Ok(()) => failed.lock().unwrap().remove(&table),
+                    Err(error) => failed.lock().unwrap().insert(table, error.to_string()),
+                };
+            }
+        });
+        Writer { queue: Some(Queue { sender, failures }), thread: Some(thread) }
+    }
+
+    fn queue(&self) -> Option<Queue> {
+        self.queue.clone()
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        self.queue = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct Hosted {
+    game: Game<'static>,
+    feed: Feed,
+    watchers: Vec<Watcher>,
+    writer: Option<Queue>,
+    // the last event handed to the writer
+    queued: u64,
+    // no new hands while storage is failing
+    stalled: bool,
+}
+
+impl Hosted {
+    fn position(&self, player: Option<&str>) -> Option<u8> {
+        self.game.table().position_of(player?)
+    }
+
+    fn broadcast(&mut self) {
+        let finished = !self.game.is_hand_in_progress();
+        let events = self.game.history().map(|history| self.feed.update(&Record::from(history), finished)).unwrap_or_default();
+        let (game, name) = (&self.game, self.game.name());
+        // a closed channel means the session is gone
+        self.watchers.retain(|watcher| {
+            let position = watcher.player.as_deref().and_then(|player| game.table().position_of(player));
+            events.iter().all(|event| watcher.sender.send(Message::Event { table: name.to_string(), event: event.masked(position.into()) }).is_ok())
+                && watcher.sender.send(Message::Table { table: protocol::Table::view(game, position.into()) }).is_ok()
+        });
+    }
+
+    fn alert(&mut self, position: u8, alert: Alert) {
+        let table = self.game.name().to_string();
+        let message = match alert {
+            Alert::Warning(remaining) => Message::Warning { table, position, remaining: remaining.as_secs() },
+            Alert::TimeBank(remaining) => Message::TimeBank { table, position, remaining: remaining.as_secs() },
+            Alert::Expired => return,
+        };
+        self.watchers.retain(|watcher| watcher.sender.send(message.clone()).is_ok());
+    }
+
+    fn failure(&self) -> Option<String> {
+        self.writer.as_ref().and_then(|writer| writer.failure(self.game.name()))
+    }
+
+    // tells everyone what changed and deals the next hand when there are enough players. the hand
+    // being played is finished but none dealt while the table can't be saved.
+    fn publish(&mut self) {
+        self.broadcast();
+        if !self.game.is_hand_in_progress() {
+            let failure = self.failure();
+            self.stalled = failure.is_some();
+            match failure {
+                Some(reason) => {
+                    let error = Error::Storage { table: self.game.name().to_string(), reason };
+                    self.watchers.retain(|watcher| watcher.sender.send(Message::Error { reason: error.to_string() }).is_ok());
+                },
+                None => if self.game.start().is_ok() {
+                    self.broadcast();
+                },
+            }
+        }
+        self.save();
+    }
+
+    // the log only goes back to the last snapshot, the writer does the disk work
+    fn save(&mut self) {
+        let sequence = self.game.events().state().sequence;
+        if let Some(writer) = self.writer.as_ref().filter(|_| sequence != self.queued) {
+            writer.send(Write::Log(self.game.name().to_string(), Box::new(self.game.events().clone())));
+            self.queued = sequence;
+        }
+    }
+
+    // a stalled table tries its last write again and deals once it went through
+    fn retry(&mut self) {
+        match self.writer.as_ref().filter(|_| self.failure().is_some()) {
+            Some(writer) => writer.send(Write::Log(self.game.name().to_string(), Box::new(self.game.events().clone()))),
+            None => self.publish(),
+        }
+    }
+
+    fn attach(&mut self, writer: Queue) {
+        self.writer = Some(writer);
+        self.queued = 0;
+        self.save();
+    }
+}
+
+type Authenticate = dyn Fn(&str, &str) -> bool + Send + Sync;
+
+// a dropped session waiting to be resumed
+struct Parked {
+    player: Arc<str>,
+    watching: Vec<String>,
+    since: time::Instant,
+}
+
+pub struct Server {
+    tables: Mutex<BTreeMap<String, Hosted>>,
+    // dropped after the tables, so it has every write there is to wait for
+    storage: Option<Writer>,
+    authenticate: Box<Authenticate>,
+    online: Mutex<HashSet<String>>,
+    parked: Mutex<HashMap<String, Parked>>,
+    sessions: AtomicU64,
+    heartbeat: time::Duration,
+    resume_window: time::Duration,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Server {
+    // lets anyone in under any name until an authenticator is set
+    pub fn new() -> Self {
+        Server {
+            tables: Mutex::new(BTreeMap::new()),
+            storage: None,
+            authenticate: Box::new(|_, _| true),
+            online: Mutex::new(HashSet::new()),
+            parked: Mutex::new(HashMap::new()),
+            sessions: AtomicU64::new(0),
+            heartbeat: time::Duration::from_secs(30),
+            resume_window: time::Duration::from_secs(120),
+        }
+    }
+
+    // clients that say nothing for this long are shown offline at their seats
+    pub fn with_heartbeat(mut self, heartbeat: time::Duration) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    pub fn with_resume_window(mut self, resume_window: time::Duration) -> Self {
+        self.resume_window = resume_window;
+        self
+    }
+
+    pub fn heartbeat(&self) -> time::Duration {
+        self.heartbeat
+    }
+
+    pub fn with_authenticator(mut self, authenticate: impl Fn(&str, &str) -> bool + Send + Sync + 'static) -> Self {
+        self.authenticate = Box::new(authenticate);
+        self
+    }
+
+    // tables are found by the game's name
+    pub fn with_table(self, game: Game<'static>) -> Self {
+        let name = game.name().to_string();
+        let mut hosted = Hosted { game, feed: Feed::default(), watchers: vec![], writer: None, queued: 0, stalled: false };
+        if let Some(writer) = self.storage.as_ref().and_then(Writer::queue) {
+            writer.send(Write::Setup(name.clone(), storage::Setup::of(&hosted.game)));
+            hosted.attach(writer);
+        }
+        self.tables.lock().unwrap().insert(name, hosted);
+        self
+    }
+
+    // every table is saved as it plays, tables restored from storage are added with with_table
+    pub fn with_storage(mut self, mut storage: Box<dyn Storage>) -> Result<Self, storage::Error> {
+        let tables = self.tables.get_mut().unwrap();
+        for hosted in tables.values() {
+            storage.save_setup(hosted.game.name(), &storage::Setup::of(&hosted.game))?;
+        }
+        let writer = Writer::new(storage);
+        for hosted in tables.values_mut() {
+            hosted.attach(writer.queue().unwrap());
+        }
+        self.storage = Some(writer);
+        Ok(self)
+    }
+
+    pub fn with_game<R>(&self, table: &str, f: impl FnOnce(&Game<'static>) -> R) -> Option<R> {
+        self.tables.lock().unwrap().get(table).map(|hosted| f(&hosted.game))
+    }
+
+    fn lobby(&self) -> Vec<Listing> {
+        self.tables.lock().unwrap().values()
+            .map(|hosted| {
+                let table = protocol::Table::view(&hosted.game, Viewer::Spectator);
+                Listing {
+                    players: table.seats.iter().filter(|seat| seat.player.is_some()).count() as u8,
+                    hand_in_progress: table.street.is_some(),
+                    game: table.game,
+                }
+            })
+            .collect()
+    }
+
+    fn park(&self, token: String, player: Arc<str>, watching: Vec<String>) {
+        let mut parked = self.parked.lock().unwrap();
+        parked.retain(|_, parked| parked.since.elapsed() < self.resume_window);
+        parked.insert(token, Parked { player, watching, since: time::Instant::now() });
+    }
+
+    // warns whoever is running out of clock, times out whoever ran out and tries failed writes again
+    pub fn tick(&self) {
+        for hosted in self.tables.lock().unwrap().values_mut() {
+            if !hosted.game.is_hand_in_progress() {
+                if hosted.stalled {
+                    hosted.retry();
+                }
+                continue;
+            }
+            match hosted.game.poll() {
+                Ok(Some((_, Alert::Expired))) => hosted.publish(),
+                Ok(Some((position, alert))) => hosted.alert(position, alert),
+                _ => {},
+            }
+        }
+    }
+
+    // serves clients until shutdown resolves, then closes every connection and waits for them
+    pub async fn serve(self: Arc<Self>, listener: TcpListener, shutdown: impl Future<Output = ()>) -> io::Result<()> {
+        self.listen(listener, shutdown, connection).await
+    }
+
+    pub(crate) async fn listen<F, C>(self: Arc<Self>, listener: TcpListener, shutdown: impl Future<Output = ()>, connection: C) -> io::Result<()>
+    where
+        C: Fn(Arc<Server>, TcpStream, watch::Receiver<bool>) -> F,
+        F: Future<Output = io::Result<()>> + Send + 'static,
+    {
+        let (stop, stopped) = watch::channel(false);
+        let mut connections = JoinSet::new();
+        let mut clock = tokio::time::interval(time::Duration::from_secs(1));
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => break,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        connections.spawn(connection(self.clone(), stream, stopped.clone()));
+                    }
+                    // the client hung up before we got to it
+                    Err(error) if error.kind() == io::ErrorKind::ConnectionAborted => {}
+                    Err(error) => return Err(error),
+                },
+                _ = clock.tick() => self.tick(),
+                Some(_) = connections.join_next(), if !connections.is_empty() => {}
+            }
+        }
+        stop.send_replace(true);
+        while connections.join_next().await.is_some() {}
+        Ok(())
+    }
+}
+
+async fn connection(server: Arc<Server>, stream: TcpStream, mut stopped: watch::Receiver<bool>) -> io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut session = Session::new(server, sender);
+    loop {
+        tokio::select! {
+            line = lines.next_line() => match line? {
+                Some(line) => match serde_json::from_str(&line) {
+                    Ok(request) => session.handle(request),
+                    Err(error) => session.send(Message::Error { reason: format!("invalid request: {}", error) }),
+                },
+                None => break,
+            },
+            Some(message) = receiver.recv() => {
+                let line = serde_json::to_string(&message).expect("messages always serialize") + "\n";
+                writer.write_all(line.as_bytes()).await?;
+            }
+            _ = stopped.changed() => break,
+        }
+    }
+    drop(session);
+    writer.shutdown().await
+}
+
+// one client's login and tables, whatever transport it came in on
+pub struct Session {
+    server: Arc<Server>,
+    id: u64,
+    player: Option<Arc<str>>,
+    resume: Option<String>,
+    sender: mpsc::UnboundedSender<Message>,
+    watching: Vec<String>,
+    last_seen: time::Instant,
+    online: bool,
+}
+
+impl Session {
+    pub fn new(server: Arc<Server>, sender: mpsc::UnboundedSender<Message>) -> Self {
+        let id = server.sessions.fetch_add(1, Ordering::Relaxed);
+        Session { server, id, player: None, resume: None, sender, watching: vec![], last_seen: time::Instant::now(), online: true }
+    }
+
+    pub fn player(&self) -> Option<&str> {
+        self.player.as_deref()
+    }
+
+    pub fn send(&self, message: Message) {
+        let _ = self.sender.send(message);
+    }
+
+    pub fn handle(&mut self, request: Request) {
+        self.last_seen = time::Instant::now();
+        if !self.online {
+            self.set_online(true);
+        }
+        if let Err(error) = self.dispatch(request) {
+            self.send(Message::Error { reason: error.to_string() });
+        }
+    }
+
+    fn dispatch(&mut self, request: Request) -> Result<(), Error> {
+        match request {
+            Request::Login { name, token } => self.login(&name, &token),
+            Request::Resume { token } => self.resume(token),
+            Request::Heartbeat => {
+                self.send(Message::Heartbeat);
+                Ok(())
+            }
+            Request::Tables => {
+                self.send(Message::Tables { tables: self.server.lobby() });
+                Ok(())
+            }
+            Request::Subscribe { table } => self.table(&table, |_, _| Ok(())),
+            Request::Unsubscribe { table } => {
+                self.watching.retain(|watching| *watching != table);
+                let mut tables = self.server.tables.lock().unwrap();
+                let hosted = tables.get_mut(&table).ok_or(Error::UnknownTable(table))?;
+                hosted.watchers.retain(|watcher| watcher.session != self.id);
+                Ok(())
+            }
+            Request::Join { table, position, amount } => {
+                let player = self.player.clone().ok_or(Error::NotLoggedIn)?;
+                self.table(&table, |game, _| Ok(game.join(Player::blank_player(&player), position, amount)?))
+            }
+            Request::Act { table, bet } => self.table(&table, |game, position| {
+                Ok(game.act(position.ok_or_else(|| Error::NotSeated(game.name().to_string()))?, bet)?)
+            }),
+            Request::PreAct { table, auto_play } => self.table(&table, |game, position| {
+                Ok(game.pre_act(position.ok_or_else(|| Error::NotSeated(game.name().to_string()))?, auto_play)?)
+            }),
+            Request::CancelPreAct { table } => self.table(&table, |game, position| {
+                Ok(game.cancel_pre_act(position.ok_or_else(|| Error::NotSeated(game.name().to_string()))?)?)
+            }),
+            Request::AutoMuck { table, auto_muck } => self.table(&table, |game, position| {
+                Ok(game.set_auto_muck(position.ok_or_else(|| Error::NotSeated(game.name().to_string()))?, auto_muck)?)
+            }),
+            Request::RunIt { table, times } => self.table(&table, |game, position| {
+                Ok(game.set_run_it(position.ok_or_else(|| Error::NotSeated(game.name().to_string()))?, times)?)
+            }),
+            Request::Insure { table, insured } => self.table(&table, |game, position| {
+                Ok(game.set_insurance(position.ok_or_else(|| Error::NotSeated(game.name().to_string()))?, insured)?)
+            }),
+            Request::Leave { table } => {
+                let mut cash_out = None;
+                self.table(&table, |game, position| {
+                    cash_out = game.leave(position.ok_or_else(|| Error::NotSeated(game.name().to_string()))?)?;
+                    Ok(())
+                })?;
+                self.send(Message::Left { table, cash_out });
+                Ok(())
+            }
+        }
+    }
+
+    fn login(&mut self, name: &str, token: &str) -> Result<(), Error> {
+        if self.player.is_some() {
+            return Err(Error::AlreadyLoggedIn);
+        }
+        if !(self.server.authenticate)(name, token) {
+            return Err(Error::Unauthorized);
+        }
+        // one connection per player
+        if !self.server.online.lock().unwrap().insert(name.to_string()) {
+            return Err(Error::AlreadyLoggedIn);
+        }
+        self.player = Some(Arc::from(name));
+        let resume = format!("{:032x}", rand::random::<u128>());
+        self.resume = Some(resume.clone());
+        self.send(Message::LoggedIn { player: name.to_string(), resume });
+        Ok(())
+    }
+
+    fn resume(&mut self, token: String) -> Result<(), Error> {
+        if self.player.is_some() {
+            return Err(Error::AlreadyLoggedIn);
+        }
+        let parked = self.server.parked.lock().unwrap().remove(&token)
+            .filter(|parked| parked.since.elapsed() < self.server.resume_window)
+            .ok_or(Error::InvalidResumeToken)?;
+        if !self.server.online.lock().unwrap().insert(parked.player.to_string()) {
+            return Err(Error::AlreadyLoggedIn);
+        }
+        self.player = Some(parked.player.clone());
+        self.resume = Some(token.clone());
+        self.send(Message::LoggedIn { player: parked.player.to_string(), resume: token });
+        // every table sends its current view, events missed in between are not replayed
+        for table in parked.watching {
+            let _ = self.table(&table, |_, _| Ok(()));
+        }
+        Ok(())
+    }
+
+    // shows the session's seats offline once heartbeats stop, any request brings them back
+    pub fn check_heartbeat(&mut self) {
+        if self.online && self.last_seen.elapsed() > self.server.heartbeat {
+            self.set_online(false);
+        }
+    }
+
+    fn set_online(&mut self, online: bool) {
+        self.online = online;
+        let mut tables = self.server.tables.lock().unwrap();
+        for table in self.watching.iter() {
+            if let Some(hosted) = tables.get_mut(table) {
+                if let Some(position) = hosted.position(self.player.as_deref()) {
+                    let changed = if online { hosted.game.reconnect(position) } else { hosted.game.disconnect(position) };
+                    if changed.is_ok() {
+                        hosted.publish();
+                    }
+                }
+            }
+        }
+    }
+
+    // runs change against the table with the player's seat, if any,
+    // watching the table from then on and telling every watcher about it
+    fn table(&mut self, table: &str, change: impl FnOnce(&mut Game<'static>, Option<u8>) -> Result<(), Error>) -> Result<(), Error> {
+        let mut tables = self.server.tables.lock().unwrap();
+        let hosted = tables.get_mut(table).ok_or_else(|| Error::UnknownTable(table.to_string()))?;
+        let position = hosted.position(self.player.as_deref());
+        // coming back on a new connection
+        if let Some(position) = position.filter(|&position| hosted.game.table().seat(position).is_some_and(|seat| !seat.is_online())) {
+            hosted.game.reconnect(position)?;
+        }
+        let result = change(&mut hosted.game, position);
+
+        if !self.watching.iter().any(|watching| watching == table) {
+            self.watching.push(table.to_string());
+            hosted.watchers.push(Watcher { session: self.id, player: self.player.clone(), sender: self.sender.clone() });
+        }
+        if result.is_ok() {
+            hosted.publish();
+        } else if let Some(watcher) = hosted.watchers.iter().find(|watcher| watcher.session == self.id) {
+            let _ = watcher.sender.send(Message::Table { table: protocol::Table::view(&hosted.game, position.into()) });
+        }
+        result
+    }
+}
+
+impl Drop for Session {
+    // seats stay taken, their players just go offline until they come back
+    fn drop(&mut self) {
+        let mut tables = self.server.tables.lock().unwrap();
+        for table in self.watching.iter() {
+            if let Some(hosted) = tables.get_mut(table) {
+                hosted.watchers.retain(|watcher| watcher.session != self.id);
+                if let Some(position) = hosted.position(self.player.as_deref()) {
+                    if hosted.game.disconnect(position).is_ok() {
+                        hosted.publish();
+                    }
+                }
+            }
+        }
+        if let Some(player) = self.player.take() {
+            self.server.online.lock().unwrap().remove(&*player);
+            if let Some(resume) = self.resume.take() {
+                self.server.park(resume, player, std::mem::take(&mut self.watching));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::Lines;
+    use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+    use tokio::sync::oneshot;
+    use std::sync::atomic::AtomicBool;
+    use crate::clock;
+    use crate::game::{Limit, Name, Stake};
+
+    struct Client {
+        lines: Lines<BufReader<OwnedReadHalf>>,
+        writer: OwnedWriteHalf,
+    }
+
+    impl Client {
+        async fn connect(address: std::net::SocketAddr) -> Self {
+            let (reader, writer) = TcpStream::connect(address).await.unwrap().into_split();
+            Client { lines: BufReader::new(reader).lines(), writer }
+        }
+
+        async fn send(&mut self, request: Request) {
+            let line = serde_json::to_string(&request).unwrap() + "\n";
+            self.writer.write_all(line.as_bytes()).await.unwrap();
+        }
+
+        async fn next(&mut self) -> Option<Message> {
+            let line = tokio::time::timeout(time::Duration::from_secs(5), self.lines.next_line()).await.unwrap().unwrap()?;
+            Some(serde_json::from_str(&line).unwrap())
+        }
+
+        // skips messages until one matches
+        async fn until<T>(&mut self, mut f: impl FnMut(Message) -> Option<T>) -> T {
+            loop {
+                if let Some(found) = f(self.next().await.expect("connection closed")) {
+                    return found;
+                }
+            }
+        }
+
+        async fn login(address: std::net::SocketAddr, name: &str) -> Self {
+            let mut client = Client::connect(address).await;
+            client.send(Request::Login { name: name.to_string(), token: format!("secret-{}", name) }).await;
+            client.until(|message| matches!(message, Message::LoggedIn { .. }).then_some(())).await;
+            client
+        }
+    }
+
+    async fn start() -> (std::net::SocketAddr, oneshot::Sender<()>, tokio::task::JoinHandle<io::Result<()>>) {
+        host(Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 6).with_name("Alpha").with_seed(1)).await
+    }
+
+    async fn host(game: Game<'static>) -> (std::net::SocketAddr, oneshot::Sender<()>, tokio::task::JoinHandle<io::Result<()>>) {
+        let server = Server::new()
+            .with_authenticator(|name, token| token == format!("secret-{}", name))
+            .with_table(game);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (shutdown, stopped) = oneshot::channel();
+        let serving = tokio::spawn(Arc::new(server).serve(listener, async { stopped.await.ok(); }));
+        (address, shutdown, serving)
+    }
+
+    fn hand_started(message: Message) -> Option<protocol::Table> {
+        match message {
+            Message::Table { table } if table.street.is_some() => Some(table),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    pub async fn play_a_hand() {
+        let (address, shutdown, serving) = start().await;
+        let mut alice = Client::login(address, "alice").await;
+        let mut bob = Client::login(address, "bob").await;
+
+        alice.send(Request::Join { table: "Alpha".to_string(), position: 0, amount: Chips(1000) }).await;
+        bob.send(Request::Join { table: "Alpha".to_string(), position: 3, amount: Chips(1000) }).await;
+
+        // everyone only sees their own cards
+        let table = alice.until(hand_started).await;
+        assert!(table.seats[0].cards.iter().all(Option::is_some));
+        assert_eq!(table.seats[3].cards, vec![None, None]);
+        let table = bob.until(hand_started).await;
+        assert!(table.seats[3].cards.iter().all(Option::is_some));
+        assert_eq!(table.seats[0].cards, vec![None, None]);
+
+        let to_act = table.to_act.unwrap();
+        let (acting, waiting) = if to_act == 0 { (&mut alice, &mut bob) } else { (&mut bob, &mut alice) };
+        acting.send(Request::Act { table: "Alpha".to_string(), bet: Bet::Fold }).await;
+        for client in [acting, waiting] {
+            let action = client.until(|message| match message {
+                Message::Event { event: Event::Action(action), .. } if !action.forced => Some(action),
+                _ => None,
+            }).await;
+            assert_eq!((action.position, action.bet), (to_act, Bet::Fold));
+            client.until(|message| matches!(message, Message::Event { event: Event::HandFinished { .. }, .. }).then_some(())).await;
+        }
+
+        shutdown.send(()).unwrap();
+        serving.await.unwrap().unwrap();
+        // the server hangs up on everyone
+        while alice.next().await.is_some() {}
+        while bob.next().await.is_some() {}
+    }
+
+    #[tokio::test]
+    pub async fn clock_alerts() {
+        let clock = clock::Settings::new(time::Duration::ZERO)
+            .with_time_bank(time::Duration::from_secs(30), time::Duration::from_secs(30))
+            .with_warning(time::Duration::from_secs(60));
+        let game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 6).with_name("Alpha").with_seed(1).with_clock(clock);
+        let (address, shutdown, serving) = host(game).await;
+        let mut alice = Client::login(address, "alice").await;
+        let mut bob = Client::login(address, "bob").await;
+
+        alice.send(Request::Join { table: "Alpha".to_string(), position: 0, amount: Chips(1000) }).await;
+        bob.send(Request::Join { table: "Alpha".to_string(), position: 3, amount: Chips(1000) }).await;
+        let to_act = alice.until(hand_started).await.to_act.unwrap();
+
+        // straight into the time bank, which is already under the warning
+        let time_bank = alice.until(|message| match message {
+            Message::TimeBank { position, remaining, .. } => Some((position, remaining)),
+            _ => None,
+        }).await;
+        assert_eq!(time_bank.0, to_act);
+        assert!(time_bank.1 <= 30);
+        let warning = bob.until(|message| match message {
+            Message::Warning { position, .. } => Some(position),
+            _ => None,
+        }).await;
+        assert_eq!(warning, to_act);
+
+        shutdown.send(()).unwrap();
+        serving.await.unwrap().unwrap();
+    }
+
+    // every write fails while it's broken
+    struct Broken(Arc<AtomicBool>);
+
+    impl Broken {
+        fn write(&self) -> Result<(), storage::Error> {
+            match self.0.load(Ordering::Relaxed) {
+                true => Err(storage::Error::Io(io::Error::other("disk full"))),
+                false => Ok(()),
+            }
+        }
+    }
+
+    impl Storage for Broken {
+        fn save_setup(&mut self, _: &str, _: &storage::Setup) -> Result<(), storage::Error> {
+            self.write()
+        }
+
+        fn save_snapshot(&mut self, _: &str, _: &events::State) -> Result<(), storage::Error> {
+            self.write()
+        }
+
+        fn append(&mut self, _: &str, _: u64, _: &[events::Event]) -> Result<(), storage::Error> {
+            self.write()
+        }
+
+        fn load(&mut self) -> Result<Vec<storage::Saved>, storage::Error> {
+            Ok(vec![])
+        }
+    }
+
+    #[test]
+    pub fn no_hands_while_storage_fails() {
+        let broken = Arc::new(AtomicBool::new(true));
+        let game = Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 6).with_name("Alpha").with_seed(1);
+        let server = Arc::new(Server::new().with_storage(Box::new(Broken(broken.clone()))).unwrap().with_table(game));
+        let failure = || server.storage.as_ref().and_then(Writer::queue).and_then(|queue| queue.failure("Alpha"));
+        while failure().is_none() {
+            thread::sleep(time::Duration::from_millis(1));
+        }
+
+        let (sender, mut messages) = mpsc::unbounded_channel();
+        let mut sessions = vec![];
+        for name in ["alice", "bob"] {
+            let mut session = Session::new(server.clone(), sender.clone());
+            session.handle(Request::Login { name: name.to_string(), token: String::new() });
+            session.handle(Request::Join { table: "Alpha".to_string(), position: sessions.len() as u8, amount: Chips(1000) });
+            sessions.push(session);
+        }
+        assert_eq!(server.with_game("Alpha", Game::is_hand_in_progress), Some(false));
+        let reason = storage::Error::Io(io::Error::other("disk full")).to_string();
+        let error = Error::Storage { table: "Alpha".to_string(), reason };
+        assert!(std::iter::from_fn(|| messages.try_recv().ok()).any(|message| message == Message::Error { reason: error.to_string() }));
+
+        // dealt again once a write goes through
+        broken.store(false, Ordering::Relaxed);
+        while server.with_game("Alpha", Game::is_hand_in_progress) == Some(false) {
+            server.tick();
+            thread::sleep(time::Duration::from_millis(1));
+        }
+        assert!(failure().is_none());
+    }
+
+    #[tokio::test]
+    pub async fn requests_are_checked() {
+        let (address, shutdown, serving) = start().await;
+        let error = |message| match message {
+            Message::Error { reason } => Some(reason),
+            _ => None,
+        };
+
+        let mut client = Client::connect(address).await;
+        client.send(Request::Join { table: "Alpha".to_string(), position: 0, amount: Chips(1000) }).await;
+        assert_eq!(client.until(error).await, Error::NotLoggedIn.to_string());
+        client.send(Request::Login { name: "alice".to_string(), token: "guess".to_string() }).await;
+        assert_eq!(client.until(error).await, Error::Unauthorized.to_string());
+        client.writer.write_all(b"{\"type\":\"shuffle\"}\n").await.unwrap();
+        assert!(client.until(error).await.starts_with("invalid request"));
+
+        let mut alice = Client::login(address, "alice").await;
+        alice.send(Request::Act { table: "Beta".to_string(), bet: Bet::Check }).await;
+        assert_eq!(alice.until(error).await, Error::UnknownTable("Beta".to_string()).to_string());
+        alice.send(Request::Act { table: "Alpha".to_string(), bet: Bet::Check }).await;
+        assert_eq!(alice.until(error).await, Error::NotSeated("Alpha".to_string()).to_string());
+        alice.send(Request::Tables).await;
+        let tables = alice.until(|message| match message {
+            Message::Tables { tables } => Some(tables),
+            _ => None,
+        }).await;
+        assert_eq!(tables.iter().map(|listing| listing.game.table.as_str()).collect::<Vec<_>>(), vec!["Alpha"]);
+
+        // a second connection can't take over the same player
+        let mut again = Client::connect(address).await;
+        again.send(Request::Login { name: "alice".to_string(), token: "secret-alice".to_string() }).await;
+        assert_eq!(again.until(error).await, Error::AlreadyLoggedIn.to_string());
+
+        shutdown.send(()).unwrap();
+        serving.await.unwrap().unwrap();
+    }
+}
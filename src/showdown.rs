@@ -0,0 +1,109 @@
+use std::fmt;
+use crate::poker::Hand;
+use crate::pot::SidePot;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    NotDealtIn(u8),
+    // cards that aren't in the seat's pocket, or none at all
+    NotInPocket,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotDealtIn(position) => write!(f, "seat {} wasn't dealt in", position),
+            Self::NotInPocket => write!(f, "those cards aren't in the pocket"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// the order hands are turned up in: the last aggressor first, otherwise the first seat
+// left of the button, then around the table
+pub fn order(in_hand: &[usize], aggressor: Option<usize>, button: usize, size: usize) -> Vec<usize> {
+    let first = aggressor.filter(|aggressor| in_hand.contains(aggressor)).unwrap_or((button + 1) % size);
+    let mut order = in_hand.to_vec();
+    order.sort_by_key(|position| (position + size - first) % size);
+    order
+}
+
+// the best hands shown so far in every pot
+#[derive(Debug)]
+pub struct Contest<'p> {
+    side_pots: &'p [SidePot],
+    high: Vec<Option<Hand>>,
+    low: Vec<Option<Hand>>,
+}
+
+impl<'p> Contest<'p> {
+    pub fn new(side_pots: &'p [SidePot]) -> Self {
+        Contest { side_pots, high: vec![None; side_pots.len()], low: vec![None; side_pots.len()] }
+    }
+
+    // whether the hand still wins or ties part of a contested pot against what's been shown,
+    // a hand that doesn't may be mucked
+    pub fn contends(&self, position: usize, high: Option<&Hand>, low: Option<&Hand>) -> bool {
+        let beats = |hand: Option<&Hand>, best: &Option<Hand>| match (hand, best) {
+            (Some(hand), Some(best)) => hand >= best,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        self.contested(position).any(|index| beats(high, &self.high[index]) || beats(low, &self.low[index]))
+    }
+
+    pub fn show(&mut self, position: usize, high: Option<&Hand>, low: Option<&Hand>) {
+        let better = |hand: Option<&Hand>, best: &mut Option<Hand>| {
+            if let Some(hand) = hand.filter(|hand| best.as_ref().is_none_or(|best| *hand > best)) {
+                *best = Some(hand.clone());
+            }
+        };
+        for index in self.contested(position).collect::<Vec<_>>() {
+            better(high, &mut self.high[index]);
+            better(low, &mut self.low[index]);
+        }
+    }
+
+    fn contested(&self, position: usize) -> impl Iterator<Item = usize> + '_ {
+        self.side_pots.iter().enumerate()
+            .filter(move |(_, side_pot)| side_pot.eligible.len() > 1 && side_pot.eligible.contains(&position))
+            .map(|(index, _)| index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chips::Chips;
+    use crate::poker::{card, hand};
+
+    #[test]
+    pub fn showing_order() {
+        assert_eq!(order(&[0, 2, 4], None, 2, 6), vec![4, 0, 2]);
+        assert_eq!(order(&[0, 2, 4], Some(2), 2, 6), vec![2, 4, 0]);
+        // an aggressor who is no longer in doesn't count
+        assert_eq!(order(&[0, 4], Some(2), 4, 6), vec![0, 4]);
+    }
+
+    #[test]
+    pub fn contending() {
+        let board = card::parse("2c7h9dJsQc");
+        let high = |pocket: &str| hand::best_high(&card::parse(pocket), &board, false);
+        let side_pots = [
+            SidePot { amount: Chips(300), eligible: vec![0, 1, 2] },
+            SidePot { amount: Chips(200), eligible: vec![1, 2] },
+            SidePot { amount: Chips(50), eligible: vec![2] },
+        ];
+        let mut contest = Contest::new(&side_pots);
+        assert!(contest.contends(0, high("AsAd").as_ref(), None));
+        contest.show(0, high("AsAd").as_ref(), None);
+
+        // kings lose the main pot but the side pot is still open
+        assert!(contest.contends(1, high("KsKd").as_ref(), None));
+        contest.show(1, high("KsKd").as_ref(), None);
+        assert!(!contest.contends(2, high("3s4d").as_ref(), None));
+        // a tie has to be shown
+        assert!(contest.contends(2, high("KhKc").as_ref(), None));
+    }
+}
@@ -0,0 +1,524 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::events::{self, Event, Log, State};
+use crate::game::{self, Game, Limit, Name, Stake};
+
+// running tables saved as they play: how each table was set up, its last snapshot and the events
+// since. a table comes back with its seats and stacks, a hand in progress is either dealt again
+// and resumed or voided and refunded.
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+    #[cfg(feature = "sqlite")]
+    Sqlite(rusqlite::Error),
+    // a broken journal or a gap in a table's events
+    Corrupt(String),
+    Game(game::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for Error {
+    fn from(error: rusqlite::Error) -> Self {
+        Error::Sqlite(error)
+    }
+}
+
+impl From<game::Error> for Error {
+    fn from(error: game::Error) -> Self {
+        Error::Game(error)
+    }
+}
+
+impl From<events::Error> for Error {
+    fn from(error: events::Error) -> Self {
+        Error::Game(game::Error::Events(error))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "storage: {}", error),
+            Self::Json(error) => write!(f, "storage: {}", error),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(error) => write!(f, "storage: {}", error),
+            Self::Corrupt(reason) => write!(f, "storage is corrupt: {}", reason),
+            Self::Game(error) => write!(f, "can't restore: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// what it takes to open the table again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Setup {
+    pub game: Name,
+    pub limit: Limit,
+    pub stake: Stake,
+    pub table_size: u8,
+}
+
+impl Setup {
+    pub fn of(game: &Game) -> Self {
+        Setup { game: game.game(), limit: game.limit(), stake: game.stake().clone(), table_size: game.table().size() }
+    }
+
+    pub fn game(&self, table: &str) -> Game<'static> {
+        Game::new(self.game, self.limit, self.stake.clone(), self.table_size).with_name(table)
+    }
+}
+
+// one table as it was stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct Saved {
+    pub table: String,
+    pub setup: Setup,
+    pub snapshot: State,
+    pub tail: Vec<Event>,
+}
+
+impl Saved {
+    pub fn log(&self) -> Result<Log, events::Error> {
+        Log::restore(self.snapshot.clone(), self.tail.clone())
+    }
+
+    // the sequence of the last event stored
+    pub fn sequence(&self) -> u64 {
+        self.snapshot.sequence + self.tail.len() as u64
+    }
+
+    // events come in numbered, anything already covered is dropped and a gap is an error
+    fn add(&mut self, sequence: u64, event: Event) -> Result<(), Error> {
+        if sequence > self.sequence() + 1 {
+            return Err(Error::Corrupt(format!("table {} is missing event {}", self.table, self.sequence() + 1)));
+        }
+        if sequence == self.sequence() + 1 {
+            self.tail.push(event);
+        }
+        Ok(())
+    }
+
+    fn set_snapshot(&mut self, snapshot: State) {
+        if snapshot.sequence >= self.snapshot.sequence {
+            let covered = (snapshot.sequence - self.snapshot.sequence) as usize;
+            self.tail.drain(..covered.min(self.tail.len()));
+            self.snapshot = snapshot;
+        }
+    }
+}
+
+pub trait Storage: Send {
+    // saving a table again replaces how it was set up
+    fn save_setup(&mut self, table: &str, setup: &Setup) -> Result<(), Error>;
+    // every event up to the snapshot's sequence can go
+    fn save_snapshot(&mut self, table: &str, snapshot: &State) -> Result<(), Error>;
+    // events numbered on from first, events stored before are left as they are
+    fn append(&mut self, table: &str, first: u64, events: &[Event]) -> Result<(), Error>;
+    fn load(&mut self) -> Result<Vec<Saved>, Error>;
+}
+
+// writes whatever the log gained since the saved sequence and returns the sequence saved now
+pub fn persist(storage: &mut dyn Storage, table: &str, log: &Log, saved: u64) -> Result<u64, Error> {
+    let snapshot = log.snapshot();
+    if snapshot.sequence > saved || saved == 0 {
+        storage.save_snapshot(table, snapshot)?;
+    }
+    let skip = (saved.saturating_sub(snapshot.sequence) as usize).min(log.tail().len());
+    if skip < log.tail().len() {
+        storage.append(table, snapshot.sequence + skip as u64 + 1, &log.tail()[skip..])?;
+    }
+    Ok(log.state().sequence)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Recovery {
+    // deal the unfinished hand again and play on, voiding it only when it comes out differently
+    #[default]
+    Resume,
+    // unfinished hands are called off and everyone gets back what they put in
+    Void,
+}
+
+// the table the way it was saved, on a game fresh from game() so clocks, rake and seeds come from
+// the caller. a voided hand shows up in the events as hand_voided with its refunds.
+pub fn restore(game: impl Fn() -> Game<'static>, saved: &Saved, recovery: Recovery) -> Result<Game<'static>, Error> {
+    let log = saved.log()?;
+    if recovery == Recovery::Resume || log.state().hand.is_none() {
+        match game().restore(log.clone()) {
+            Err(game::Error::Diverged) => {},
+            restored => return Ok(restored?),
+        }
+    }
+    let mut log = log;
+    log.push(Event::HandVoided { refunds: log.state().refunds() })?;
+    Ok(game().restore(log)?)
+}
+
+// one json object per line, appended and synced as the table plays. load rewrites the file with
+// just what's still needed.
+//
+// {"setup": {"table": "Alpha", "setup": {...}}}
+// {"snapshot": {"table": "Alpha", "state": {...}}}
+// {"event": {"table": "Alpha", "sequence": 12, "event": {"type": "hand_finished"}}}
+//
+// not internally tagged, serde can't read the states' numbered seats back through a tag
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Line {
+    Setup { table: String, setup: Setup },
+    Snapshot { table: String, state: State },
+    Event { table: String, sequence: u64, event: Event },
+}
+
+fn known<'a>(tables: &'a mut BTreeMap<String, Saved>, table: &str) -> Result<&'a mut Saved, Error> {
+    tables.get_mut(table).ok_or_else(|| Error::Corrupt(format!("table {} was never set up", table)))
+}
+
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+}
+
+impl Journal {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Journal { path, file })
+    }
+
+    fn write(&mut self, lines: &[Line]) -> Result<(), Error> {
+        let mut buffer = vec![];
+        for line in lines {
+            serde_json::to_writer(&mut buffer, line)?;
+            buffer.push(b'\n');
+        }
+        self.file.write_all(&buffer)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    fn read(&self) -> Result<Vec<Saved>, Error> {
+        let lines = BufReader::new(File::open(&self.path)?).lines().collect::<Result<Vec<_>, _>>()?;
+        let mut tables: BTreeMap<String, Saved> = BTreeMap::new();
+        for (number, line) in lines.iter().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let line = match serde_json::from_str(line) {
+                Ok(line) => line,
+                // a crash in the middle of a write leaves half a line at the end
+                Err(_) if number + 1 == lines.len() => break,
+                Err(error) => return Err(Error::Corrupt(format!("line {}: {}", number + 1, error))),
+            };
+            match line {
+                Line::Setup { table, setup } => {
+                    let saved = tables.entry(table.clone())
+                        .or_insert_with(|| Saved { table, setup: setup.clone(), snapshot: State::default(), tail: vec![] });
+                    saved.setup = setup;
+                },
+                Line::Snapshot { table, state } => known(&mut tables, &table)?.set_snapshot(state),
+                Line::Event { table, sequence, event } => known(&mut tables, &table)?.add(sequence, event)?,
+            }
+        }
+        Ok(tables.into_values().collect())
+    }
+
+    // swaps the journal for one with only the snapshots and tails
+    fn compact(&mut self, tables: &[Saved]) -> Result<(), Error> {
+        let compacted = self.path.with_extension("compacting");
+        let mut journal = Journal { path: compacted.clone(), file: File::create(&compacted)? };
+        for saved in tables {
+            let table = || saved.table.clone();
+            let mut lines = vec![
+                Line::Setup { table: table(), setup: saved.setup.clone() },
+                Line::Snapshot { table: table(), state: saved.snapshot.clone() },
+            ];
+            for (index, event) in saved.tail.iter().enumerate() {
+                lines.push(Line::Event { table: table(), sequence: saved.snapshot.sequence + index as u64 + 1, event: event.clone() });
+            }
+            journal.write(&lines)?;
+        }
+        fs::rename(&compacted, &self.path)?;
+        *self = Journal::open(self.path.clone())?;
+        Ok(())
+    }
+}
+
+impl Storage for Journal {
+    fn save_setup(&mut self, table: &str, setup: &Setup) -> Result<(), Error> {
+        self.write(&[Line::Setup { table: table.to_string(), setup: setup.clone() }])
+    }
+
+    fn save_snapshot(&mut self, table: &str, snapshot: &State) -> Result<(), Error> {
+        self.write(&[Line::Snapshot { table: table.to_string(), state: snapshot.clone() }])
+    }
+
+    fn append(&mut self, table: &str, first: u64, events: &[Event]) -> Result<(), Error> {
+        let lines: Vec<Line> = events.iter().enumerate()
+            .map(|(index, event)| Line::Event { table: table.to_string(), sequence: first + index as u64, event: event.clone() })
+            .collect();
+        self.write(&lines)
+    }
+
+    fn load(&mut self) -> Result<Vec<Saved>, Error> {
+        let tables = self.read()?;
+        self.compact(&tables)?;
+        Ok(tables)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::Sqlite;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use rusqlite::{params, Connection, OptionalExtension};
+    use super::*;
+
+    // every write is one transaction, a snapshot deletes the events it covers
+    #[derive(Debug)]
+    pub struct Sqlite {
+        connection: Connection,
+    }
+
+    impl Sqlite {
+        pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+            Self::new(Connection::open(path)?)
+        }
+
+        pub fn in_memory() -> Result<Self, Error> {
+            Self::new(Connection::open_in_memory()?)
+        }
+
+        fn new(connection: Connection) -> Result<Self, Error> {
+            connection.execute_batch("
+                PRAGMA journal_mode = WAL;
+                PRAGMA synchronous = FULL;
+                CREATE TABLE IF NOT EXISTS tables (name TEXT PRIMARY KEY, setup TEXT NOT NULL);
+                CREATE TABLE IF NOT EXISTS snapshots (name TEXT PRIMARY KEY, sequence INTEGER NOT NULL, state TEXT NOT NULL);
+                CREATE TABLE IF NOT EXISTS events (name TEXT NOT NULL, sequence INTEGER NOT NULL, event TEXT NOT NULL, PRIMARY KEY (name, sequence));
+            ")?;
+            Ok(Sqlite { connection })
+        }
+    }
+
+    impl Storage for Sqlite {
+        fn save_setup(&mut self, table: &str, setup: &Setup) -> Result<(), Error> {
+            self.connection.execute(
+                "INSERT INTO tables (name, setup) VALUES (?1, ?2) ON CONFLICT (name) DO UPDATE SET setup = excluded.setup",
+                params![table, serde_json::to_string(setup)?],
+            )?;
+            Ok(())
+        }
+
+        fn save_snapshot(&mut self, table: &str, snapshot: &State) -> Result<(), Error> {
+            let transaction = self.connection.transaction()?;
+            transaction.execute(
+                "INSERT INTO snapshots (name, sequence, state) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (name) DO UPDATE SET sequence = excluded.sequence, state = excluded.state WHERE excluded.sequence >= sequence",
+                params![table, snapshot.sequence as i64, serde_json::to_string(snapshot)?],
+            )?;
+            transaction.execute("DELETE FROM events WHERE name = ?1 AND sequence <= ?2", params![table, snapshot.sequence as i64])?;
+            transaction.commit()?;
+            Ok(())
+        }
+
+        fn append(&mut self, table: &str, first: u64, events: &[Event]) -> Result<(), Error> {
+            let transaction = self.connection.transaction()?;
+            {
+                let mut insert = transaction.prepare("INSERT OR IGNORE INTO events (name, sequence, event) VALUES (?1, ?2, ?3)")?;
+                for (index, event) in events.iter().enumerate() {
+                    insert.execute(params![table, (first + index as u64) as i64, serde_json::to_string(event)?])?;
+                }
+            }
+            transaction.commit()?;
+            Ok(())
+        }
+
+        fn load(&mut self) -> Result<Vec<Saved>, Error> {
+            let tables = self.connection.prepare("SELECT name, setup FROM tables ORDER BY name")?
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut saved = vec![];
+            for (table, setup) in tables {
+                let snapshot = self.connection
+                    .query_row("SELECT state FROM snapshots WHERE name = ?1", params![table], |row| row.get::<_, String>(0))
+                    .optional()?;
+                let snapshot = match snapshot {
+                    Some(snapshot) => serde_json::from_str(&snapshot)?,
+                    None => State::default(),
+                };
+                let mut table = Saved { table, setup: serde_json::from_str(&setup)?, snapshot, tail: vec![] };
+                let events = self.connection.prepare("SELECT sequence, event FROM events WHERE name = ?1 AND sequence > ?2 ORDER BY sequence")?
+                    .query_map(params![table.table, table.snapshot.sequence as i64], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                for (sequence, event) in events {
+                    table.add(sequence as u64, serde_json::from_str(&event)?)?;
+                }
+                saved.push(table);
+            }
+            Ok(saved)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bet::Bet;
+    use crate::chips::Chips;
+    use crate::player::Player;
+    use crate::seat;
+
+    fn table() -> Game<'static> {
+        Game::new(Name::Texas, Limit::NoLimit, Stake::new(Chips(10)), 6).with_name("Alpha").with_seed(5).with_snapshots_every(4)
+    }
+
+    // two hands played, the second one left on the flop
+    fn crashed(storage: &mut dyn Storage) -> Game<'static> {
+        let mut game = table();
+        storage.save_setup("Alpha", &Setup::of(&game)).unwrap();
+        let mut saved = 0;
+        game.join(Player::blank_player("a"), 0, Chips(1000)).unwrap();
+        game.join(Player::blank_player("b"), 2, Chips(1000)).unwrap();
+        game.join(Player::blank_player("c"), 4, Chips(1000)).unwrap();
+        saved = persist(storage, "Alpha", game.events(), saved).unwrap();
+        for hand in 0..2 {
+            game.start().unwrap();
+            saved = persist(storage, "Alpha", game.events(), saved).unwrap();
+            while let Some(position) = game.to_act() {
+                if hand == 1 && game.board().len() == 3 {
+                    break;
+                }
+                let range = game.range(position).unwrap();
+                game.act(position, if range.can_check() { Bet::Check } else { Bet::Call(range.call) }).unwrap();
+                saved = persist(storage, "Alpha", game.events(), saved).unwrap();
+            }
+        }
+        game
+    }
+
+    fn stacks(game: &Game) -> Vec<Option<Chips>> {
+        game.table().seats().iter().map(|seat| seat.stack()).collect()
+    }
+
+    fn check(storage: &mut dyn Storage) {
+        let before = crashed(storage);
+        let saved = storage.load().unwrap().remove(0);
+        assert_eq!((saved.table.as_str(), &saved.setup), ("Alpha", &Setup::of(&before)));
+        assert_eq!(saved.sequence(), before.events().state().sequence);
+
+        let resumed = restore(table, &saved, Recovery::Resume).unwrap();
+        assert_eq!((resumed.history().map(|history| history.id), resumed.to_act()), (Some(2), before.to_act()));
+        assert_eq!((resumed.board(), resumed.pocket_cards(2)), (before.board(), before.pocket_cards(2)));
+        assert_eq!(stacks(&resumed), stacks(&before));
+        assert_eq!(resumed.events().state(), before.events().state());
+
+        let voided = restore(table, &saved, Recovery::Void).unwrap();
+        assert!(!voided.is_hand_in_progress());
+        let refunds = match voided.events().tail().last() {
+            Some(Event::HandVoided { refunds }) => refunds.clone(),
+            event => panic!("{:?}", event),
+        };
+        assert_eq!(refunds.iter().map(|&(_, amount)| amount).sum::<Chips>(), before.table().pot().total());
+        assert_eq!(voided.table().seats().iter().filter_map(|seat| seat.stack()).sum::<Chips>(), Chips(3000));
+    }
+
+    #[test]
+    pub fn journal() {
+        let path = std::env::temp_dir().join(format!("pokernode-journal-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        check(&mut Journal::open(&path).unwrap());
+        // compacted on load and the same after
+        let mut journal = Journal::open(&path).unwrap();
+        let saved = journal.load().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2 + saved[0].tail.len());
+        assert_eq!(journal.load().unwrap(), saved);
+        // half a line from a crash is left out, anything else broken is not
+        fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{\"event\": {\"ta").unwrap();
+        assert_eq!(Journal::open(&path).unwrap().load().unwrap(), saved);
+        fs::write(&path, "{}\n{}\n").unwrap();
+        assert!(matches!(Journal::open(&path).unwrap().load(), Err(Error::Corrupt(_))));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    pub fn sqlite() {
+        check(&mut Sqlite::in_memory().unwrap());
+    }
+
+    #[test]
+    pub fn resume_between_hands() {
+        let mut game = table();
+        game.join(Player::blank_player("a"), 1, Chips(1000)).unwrap();
+        game.join(Player::blank_player("b"), 3, Chips(1000)).unwrap();
+        game.start().unwrap();
+        game.act(game.to_act().unwrap(), Bet::Fold).unwrap();
+        let saved = Saved { table: "Alpha".to_string(), setup: Setup::of(&game), snapshot: game.events().snapshot().clone(), tail: game.events().tail().to_vec() };
+        let mut restored = restore(table, &saved, Recovery::Void).unwrap();
+        assert_eq!(stacks(&restored), stacks(&game));
+        restored.start().unwrap();
+        assert_eq!(restored.history().map(|history| (history.id, history.button)), Some((2, 3)));
+    }
+
+    fn seats(game: &Game) -> Vec<(seat::Status, seat::MissedBlinds, Chips, bool)> {
+        game.table().seats().iter()
+            .map(|seat| (seat.status(), seat.missed_blinds(), seat.pending_rebuy(), seat.is_sitting_out_next_hand()))
+            .collect()
+    }
+
+    #[test]
+    pub fn seats_as_they_were() {
+        let mut game = table();
+        for (name, position) in [("a", 0), ("b", 2), ("c", 4), ("d", 5)] {
+            game.join(Player::blank_player(name), position, Chips(1000)).unwrap();
+        }
+        game.sit_out(4).unwrap();
+        game.sit_out(5).unwrap();
+        for _ in 0..3 {
+            game.start().unwrap();
+            while let Some(position) = game.to_act() {
+                game.act(position, Bet::Fold).unwrap();
+            }
+        }
+        game.come_back(5, false).unwrap();
+        let short = if game.table().seat(0).and_then(|seat| seat.stack()) < Some(Chips(1000)) { 0 } else { 2 };
+        game.rebuy(short, Chips(1)).unwrap();
+        assert_eq!(game.table().seat(4).map(|seat| seat.missed_blinds().big_blind), Some(true));
+        assert_eq!(game.table().seat(5).map(|seat| seat.status()), Some(seat::Status::WaitBigBlind));
+
+        let saved = Saved { table: "Alpha".to_string(), setup: Setup::of(&game), snapshot: game.events().snapshot().clone(), tail: game.events().tail().to_vec() };
+        let restored = restore(table, &saved, Recovery::Resume).unwrap();
+        assert_eq!(seats(&restored), seats(&game));
+        assert_eq!(restored.events().state(), game.events().state());
+
+        // sitting out once the hand is over
+        game.start().unwrap();
+        game.sit_out(short).unwrap();
+        let saved = Saved { table: "Alpha".to_string(), setup: Setup::of(&game), snapshot: game.events().snapshot().clone(), tail: game.events().tail().to_vec() };
+        let restored = restore(table, &saved, Recovery::Resume).unwrap();
+        assert_eq!(restored.history().map(|history| history.id), game.history().map(|history| history.id));
+        assert_eq!(seats(&restored), seats(&game));
+        let voided = restore(table, &saved, Recovery::Void).unwrap();
+        assert_eq!(voided.table().seat(short).map(|seat| seat.status()), Some(seat::Status::SittingOut));
+    }
+}
@@ -1,28 +1,285 @@
 use crate::player::Player;
-use crate::seat::Seat;
+use crate::chips::{self, Chips};
+use crate::seat::{self, Seat};
 use crate::pot::Pot;
+use crate::button::{self, Blinds, Presence, Rotation};
+use rand::seq::IteratorRandom;
 use std::fmt;
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    TableFull,
+    InvalidSeat(u8),
+    SeatTaken(u8),
+    SeatEmpty(u8),
+    AlreadySeated,
+    BuyInTooSmall { min: Chips },
+    BuyInTooLarge { max: Chips },
+    // how much more the stack may take before it goes over the maximum buy-in
+    RebuyTooLarge { headroom: Chips },
+    HandInProgress,
+    Seat(seat::Error),
+    Chips(chips::Error),
+}
+
+impl From<seat::Error> for Error {
+    fn from(error: seat::Error) -> Self {
+        Error::Seat(error)
+    }
+}
+
+impl From<chips::Error> for Error {
+    fn from(error: chips::Error) -> Self {
+        Error::Chips(error)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TableFull => write!(f, "table is full"),
+            Self::InvalidSeat(position) => write!(f, "seat {} does not exist", position),
+            Self::SeatTaken(position) => write!(f, "seat {} is taken", position),
+            Self::SeatEmpty(position) => write!(f, "seat {} is empty", position),
+            Self::AlreadySeated => write!(f, "player is already seated"),
+            Self::BuyInTooSmall { min } => write!(f, "buy-in is below the minimum of {}", min),
+            Self::BuyInTooLarge { max } => write!(f, "buy-in is above the maximum of {}", max),
+            Self::RebuyTooLarge { headroom } => write!(f, "rebuy is above the {} left under the maximum buy-in", headroom),
+            Self::HandInProgress => write!(f, "hand is in progress"),
+            Self::Seat(error) => write!(f, "{}", error),
+            Self::Chips(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 #[derive(Debug)]
 pub struct Table<'a> {
     size: u8,
     button: u8,
     seats: Vec<Seat<'a>>,
     pot: Pot,
+    blinds: Option<Blinds>,
+    // seat transitions the game hasn't taken into its log yet
+    events: Vec<seat::Event>,
 }
 
-impl Table<'_> {
+impl<'a> Table<'a> {
     pub fn new(size: u8) -> Self {
         Table {
             size,
             button: 0,
-            seats: vec![],
-            pot: Pot{},
+            seats: (0..size).map(Seat::new).collect(),
+            pot: Pot::new(),
+            blinds: None,
+            events: vec![],
+        }
+    }
+
+    pub fn button(&self) -> u8 {
+        self.button
+    }
+
+    pub fn set_button(&mut self, position: u8) -> Result<(), Error> {
+        if position >= self.size {
+            return Err(Error::InvalidSeat(position));
+        }
+        self.button = position;
+        self.blinds = None;
+        Ok(())
+    }
+
+    pub fn blinds(&self) -> Option<&Blinds> {
+        self.blinds.as_ref()
+    }
+
+    pub fn presence(&self) -> Vec<Presence> {
+        self.seats.iter().map(|seat| seat.presence()).collect()
+    }
+
+    pub fn move_button(&mut self) -> Option<Rotation> {
+        let rotation = button::rotate(&self.presence(), self.blinds.as_ref(), self.button as usize)?;
+
+        for &position in rotation.missed_small_blind.iter() {
+            self.seats[position].miss_small_blind();
+        }
+        for &position in rotation.missed_big_blind.iter() {
+            self.seats[position].miss_big_blind();
+        }
+        let dealt_in = rotation.posts.iter().map(|&(position, _)| position).chain([rotation.blinds.big_blind]);
+        for position in dealt_in {
+            if let Ok(event) = self.seats[position].deal_in() {
+                self.events.push(event);
+            }
+        }
+
+        self.button = rotation.blinds.button as u8;
+        self.blinds = Some(rotation.blinds);
+        Some(rotation)
+    }
+
+    pub fn pot(&self) -> &Pot {
+        &self.pot
+    }
+
+    pub fn pot_mut(&mut self) -> &mut Pot {
+        &mut self.pot
+    }
+
+    pub fn size(&self) -> u8 {
+        self.size
+    }
+
+    pub fn seat(&self, position: u8) -> Option<&Seat<'a>> {
+        self.seats.get(position as usize)
+    }
+
+    pub fn seats(&self) -> &Vec<Seat<'a>> {
+        &self.seats
+    }
+
+    pub fn position_of(&self, name: &str) -> Option<u8> {
+        self.seats.iter()
+            .position(|seat| seat.player().is_some_and(|player| &*player.name == name))
+            .map(|position| position as u8)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.seats.iter().all(|seat| !seat.is_empty())
+    }
+
+    pub fn is_hand_in_progress(&self) -> bool {
+        self.seats.iter().any(|seat| seat.is_in_hand())
+    }
+
+    pub(crate) fn take_events(&mut self) -> Vec<seat::Event> {
+        std::mem::take(&mut self.events)
+    }
+
+    // every chip at the table, which has to fit in Chips for nothing won or bet later to overflow
+    pub fn chips(&self) -> Result<Chips, chips::Error> {
+        // the last hand's pot has been paid out already
+        let in_pot = if self.is_hand_in_progress() { self.pot.total() } else { Chips::ZERO };
+        self.seats.iter().try_fold(in_pot, |total, seat| {
+            total.checked_add(seat.stack().unwrap_or_default())?.checked_add(seat.pending_rebuy())
+        })
+    }
+
+    pub fn seat_mut(&mut self, position: u8) -> Result<&mut Seat<'a>, Error> {
+        self.seats.get_mut(position as usize).ok_or(Error::InvalidSeat(position))
+    }
+
+    fn record(&mut self, event: Result<seat::Event, seat::Error>) -> Result<(), Error> {
+        self.events.push(event?);
+        Ok(())
+    }
+
+    pub fn reserve(&mut self, player: Player<'a>, position: u8) -> Result<(), Error> {
+        if self.position_of(&player.name).is_some() {
+            return Err(Error::AlreadySeated);
+        }
+        if self.is_full() {
+            return Err(Error::TableFull);
+        }
+        let seat = self.seat_mut(position)?;
+        if !seat.is_empty() {
+            return Err(Error::SeatTaken(position));
         }
+        let event = seat.reserve(player);
+        self.record(event)
     }
 
-    pub fn join(&self, player: Player, position: u8, amount: u16) {
+    pub fn buy_in(&mut self, position: u8, amount: Chips) -> Result<(), Error> {
+        self.chips()?.checked_add(amount)?;
+        // once hands are running a new player waits for the big blind instead of getting a free hand
+        let wait_big_blind = self.blinds.is_some();
+        let event = self.seat_mut(position)?.buy_in(amount, wait_big_blind);
+        self.record(event)
+    }
+
+    pub fn join(&mut self, player: Player<'a>, position: u8, amount: Chips) -> Result<(), Error> {
+        self.chips()?.checked_add(amount)?;
+        self.reserve(player, position)?;
+        self.buy_in(position, amount)
+    }
 
+    // a seat picked up from storage keeps waiting for the big blind whether hands ran yet or not
+    pub(crate) fn rejoin(&mut self, player: Player<'a>, position: u8, amount: Chips, wait_big_blind: bool) -> Result<(), Error> {
+        self.chips()?.checked_add(amount)?;
+        self.reserve(player, position)?;
+        let event = self.seat_mut(position)?.buy_in(amount, wait_big_blind);
+        self.record(event)
+    }
+
+    pub fn join_random(&mut self, player: Player<'a>, amount: Chips) -> Result<u8, Error> {
+        let position = self.seats.iter()
+            .enumerate()
+            .filter(|(_, seat)| seat.is_empty())
+            .map(|(position, _)| position as u8)
+            .choose(&mut rand::thread_rng());
+        match position {
+            Some(position) => self.join(player, position, amount).map(|_| position),
+            None if self.position_of(&player.name).is_some() => Err(Error::AlreadySeated),
+            None => Err(Error::TableFull),
+        }
+    }
+
+    pub fn sit_out(&mut self, position: u8) -> Result<(), Error> {
+        let event = self.seat_mut(position)?.sit_out();
+        self.record(event)
+    }
+
+    pub fn come_back(&mut self, position: u8, post: bool, small_blind: Chips, big_blind: Chips) -> Result<(), Error> {
+        let event = self.seat_mut(position)?.come_back(post, small_blind, big_blind);
+        self.record(event)
+    }
+
+    pub fn go_away(&mut self, position: u8) -> Result<(), Error> {
+        let event = self.seat_mut(position)?.go_away();
+        self.record(event)
+    }
+
+    pub fn timeout(&mut self, position: u8) -> Result<(), Error> {
+        let event = self.seat_mut(position)?.timeout();
+        self.record(event)
+    }
+
+    // the stack is returned right away between hands, or by finish_hand when leaving mid-hand
+    pub fn leave(&mut self, position: u8) -> Result<Option<Chips>, Error> {
+        let seat = self.seat_mut(position)?;
+        if seat.is_empty() {
+            return Err(Error::SeatEmpty(position));
+        }
+        let stack = seat.stack();
+        let event = seat.leave();
+        let left = event.as_ref().is_ok_and(|event| event.to == seat::Status::Empty);
+        self.record(event)?;
+        Ok(if left { Some(stack.unwrap_or_default()) } else { None })
+    }
+
+    pub fn protect(&mut self, position: u8) -> Result<(), Error> {
+        let event = self.seat_mut(position)?.protect();
+        self.record(event)
+    }
+
+    pub fn start_hand(&mut self, position: u8) -> Result<(), Error> {
+        let event = self.seat_mut(position)?.start_hand();
+        self.record(event)
+    }
+
+    pub fn finish_hand(&mut self) -> Vec<(u8, Chips)> {
+        let mut cash_outs = vec![];
+        for seat in self.seats.iter_mut() {
+            let stack = seat.stack();
+            if let Ok(event) = seat.finish_hand() {
+                if event.from == seat::Status::Gone {
+                    cash_outs.push((event.position, stack.unwrap_or_default()));
+                }
+                self.events.push(event);
+            }
+        }
+        cash_outs
     }
 }
 
@@ -38,3 +295,76 @@ pub type SixMax<'a> = [Seat<'a>; 6];
 pub type EightMax<'a> = [Seat<'a>; 8];
 pub type NineMax<'a> = [Seat<'a>; 9];
 pub type TenMax<'a> = [Seat<'a>; 10];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn join_and_leave() {
+        let mut table = Table::new(3);
+        assert_eq!(table.join(Player::blank_player("player-1"), 1, Chips(1000)), Ok(()));
+        assert_eq!(table.join(Player::blank_player("player-2"), 1, Chips(1000)), Err(Error::SeatTaken(1)));
+        assert_eq!(table.join(Player::blank_player("player-1"), 2, Chips(1000)), Err(Error::AlreadySeated));
+        assert_eq!(table.join(Player::blank_player("player-2"), 3, Chips(1000)), Err(Error::InvalidSeat(3)));
+        assert_eq!(table.position_of("player-1"), Some(1));
+
+        assert_eq!(table.leave(1), Ok(Some(Chips(1000))));
+        assert_eq!(table.leave(1), Err(Error::SeatEmpty(1)));
+        assert_eq!(table.position_of("player-1"), None);
+    }
+
+    #[test]
+    pub fn chips_overflow() {
+        let mut table = Table::new(3);
+        table.join(Player::blank_player("player-1"), 0, Chips(u64::MAX - 10)).unwrap();
+        assert_eq!(table.join(Player::blank_player("player-2"), 1, Chips(11)), Err(Error::Chips(chips::Error::Overflow)));
+        assert!(table.seat(1).is_some_and(|seat| seat.is_empty()));
+        assert_eq!(table.join(Player::blank_player("player-2"), 1, Chips(10)), Ok(()));
+        assert_eq!(table.chips(), Ok(Chips(u64::MAX)));
+    }
+
+    #[test]
+    pub fn seat_events() {
+        let mut table = Table::new(3);
+        table.join(Player::blank_player("player-1"), 0, Chips(1000)).unwrap();
+        table.sit_out(0).unwrap();
+        assert!(matches!(table.sit_out(0), Err(Error::Seat(_))));
+        table.come_back(0, false, Chips(5), Chips(10)).unwrap();
+
+        let transitions: Vec<seat::Transition> = table.take_events().iter().map(|event| event.transition).collect();
+        assert_eq!(transitions, vec![
+            seat::Transition::Reserve,
+            seat::Transition::BuyIn,
+            seat::Transition::SitOut,
+            seat::Transition::ComeBack,
+        ]);
+        assert!(table.take_events().is_empty());
+    }
+
+    #[test]
+    pub fn new_players_wait_for_big_blind() {
+        let mut table = Table::new(4);
+        table.join(Player::blank_player("player-1"), 0, Chips(1000)).unwrap();
+        table.join(Player::blank_player("player-2"), 1, Chips(1000)).unwrap();
+        table.join(Player::blank_player("player-3"), 2, Chips(1000)).unwrap();
+        table.move_button().unwrap();
+
+        table.join(Player::blank_player("player-4"), 3, Chips(1000)).unwrap();
+        assert_eq!(table.seat(3).map(|seat| seat.status()), Some(seat::Status::WaitBigBlind));
+        let rotation = table.move_button().unwrap();
+        assert_eq!(rotation.blinds.big_blind, 3);
+        assert_eq!(table.seat(3).map(|seat| seat.status()), Some(seat::Status::Ready));
+    }
+
+    #[test]
+    pub fn join_random_until_full() {
+        let mut table = Table::new(2);
+        let first = table.join_random(Player::blank_player("player-1"), Chips(1000)).unwrap();
+        let second = table.join_random(Player::blank_player("player-2"), Chips(1000)).unwrap();
+        assert_ne!(first, second);
+        assert!(table.is_full());
+        assert_eq!(table.join_random(Player::blank_player("player-3"), Chips(1000)), Err(Error::TableFull));
+        assert_eq!(table.join_random(Player::blank_player("player-1"), Chips(1000)), Err(Error::AlreadySeated));
+    }
+}
@@ -1,11 +1,193 @@
+namespace rs pokernode.protocol
+namespace java com.pokernode.protocol
+
+// amounts are in the smallest unit of the stake's currency
+typedef i64 Chips
+
+// a card is (kind << 2) | suit with kinds from deuce = 0 to ace = 12 and suits
+// spade = 0, heart = 1, diamond = 2, club = 3; a card someone else may not see is sent as 64
+typedef byte Card
+
+enum Name {
+  TEXAS = 1,
+  OMAHA = 2,
+  OMAHA8 = 3,
+  STUD = 4,
+  STUD8 = 5,
+  RAZZ = 6,
+  LONDON = 7,
+  FIVE_CARD = 8,
+  SINGLE27 = 9,
+  TRIPLE27 = 10,
+  BADUGI = 11,
+}
+
+enum Limit {
+  FIXED_LIMIT = 1,
+  POT_LIMIT = 2,
+  NO_LIMIT = 3,
+}
+
+enum Street {
+  PREFLOP = 1,
+  FLOP = 2,
+  TURN = 3,
+  RIVER = 4,
+}
+
+enum BetType {
+  CHECK = 1,
+  FOLD = 2,
+  RAISE = 3,
+  CALL = 4,
+  SMALL_BLIND = 5,
+  BIG_BLIND = 6,
+  STRADDLE = 7,
+  ANTE = 8,
+  BRING_IN = 9,
+  GUEST_BLIND = 10,
+}
+
+enum SeatStatus {
+  EMPTY = 1,
+  RESERVED = 2,
+  READY = 3,
+  WAIT_BIG_BLIND = 4,
+  POST_BIG_BLIND = 5,
+  PLAYING = 6,
+  AUTO_PLAYING = 7,
+  ALL_IN = 8,
+  BETTING = 9,
+  FOLDING = 10,
+  SITTING_OUT = 11,
+  IDLE = 12,
+  AWAY = 13,
+  GONE = 14,
+}
+
 struct User {
+  1: required string name,
+}
 
+struct Stake {
+  1: required Chips small_blind,
+  2: required Chips big_blind,
+  3: optional Chips ante,
+  4: optional Chips bring_in,
+  // ISO 4217 code, play money has none
+  5: optional string currency,
+  6: required Chips min_buy_in,
+  7: required Chips max_buy_in,
 }
 
 struct Game {
-
+  1: required Name name,
+  2: required Limit limit,
+  3: required Stake stake,
+  4: required byte table_size,
+  5: required string table,
 }
 
 struct Bet {
+  1: required BetType type,
+  // raises are to an amount for the street, calls and forced bets are what goes in
+  2: optional Chips amount,
+}
+
+struct Seat {
+  1: required byte position,
+  2: required SeatStatus status,
+  3: optional User player,
+  4: optional Chips stack,
+  5: required Chips put,
+  6: required bool online,
+  7: required list<Card> cards,
+}
+
+struct Table {
+  1: required Game game,
+  2: required byte button,
+  3: required list<Seat> seats,
+  4: required list<Card> board,
+  5: required Chips pot,
+  6: optional Street street,
+  7: optional byte to_act,
+}
+
+struct Action {
+  1: required Street street,
+  2: required byte position,
+  3: required Bet bet,
+  4: required bool all_in,
+  5: required bool timed_out,
+  6: required bool dead,
+  // unix time in milliseconds
+  7: required i64 at,
+  8: required i64 time_used_ms,
+  9: required string player,
+  10: required bool forced,
+}
+
+struct Winner {
+  1: required byte position,
+  2: required Chips amount,
+}
+
+struct Pot {
+  1: required Chips amount,
+  2: required list<byte> eligible,
+  3: required list<Winner> winners,
+}
+
+// who was dealt in and with how much
+struct SeatedPlayer {
+  1: required byte position,
+  2: required string player,
+  3: required Chips stack,
+}
+
+struct HandStarted {
+  1: required i64 id,
+  2: required byte button,
+  3: required list<SeatedPlayer> seats,
+}
+
+struct CardsDealt {
+  1: required Street street,
+  // unset for the board
+  2: optional byte position,
+  3: required list<Card> cards,
+}
+
+struct CardsShown {
+  1: required byte position,
+  2: required list<Card> cards,
+}
+
+struct HandFinished {
+  1: required i64 id,
+  2: required Chips total,
+  3: required Chips rake,
+}
+
+// the rest of the board after a hand that ended early
+struct Rabbit {
+  1: required list<Card> cards,
+}
+
+// another board for a hand run more than once, the first run is the board dealt as usual
+struct RunOut {
+  1: required byte run,
+  2: required list<Card> board,
+}
 
+union Event {
+  1: HandStarted hand_started,
+  2: CardsDealt cards_dealt,
+  3: Action action,
+  4: CardsShown cards_shown,
+  5: Pot pot,
+  6: HandFinished hand_finished,
+  7: Rabbit rabbit,
+  8: RunOut run_out,
 }
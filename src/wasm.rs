@@ -0,0 +1,240 @@
+use std::collections::VecDeque;
+use std::time;
+use wasm_bindgen::prelude::*;
+use crate::bet::Bet;
+use crate::chips::Chips;
+use crate::clock::TimeSource;
+use crate::equity::Calculator;
+use crate::game::{Game, Limit, Name, Stake};
+use crate::player::Player;
+use crate::poker::{card, hand, Cards, Hand, Rank};
+use crate::protocol::{self, Event, Feed};
+use crate::record::Record;
+
+// javascript bindings, see docs/wasm.md. cards go in as strings like "Ah Kd" and come out as
+// ["Ah", "Kd"], events and tables are the json of the network protocols. nothing in here reads
+// the system clock or draws randomness from the browser, everything random takes a seed.
+
+// the browser has no std clock on wasm32-unknown-unknown
+#[derive(Debug)]
+struct BrowserClock;
+
+impl TimeSource for BrowserClock {
+    fn now(&self) -> time::Duration {
+        time::Duration::from_millis(js_sys::Date::now() as u64)
+    }
+
+    fn wall(&self) -> time::SystemTime {
+        time::UNIX_EPOCH + self.now()
+    }
+}
+
+fn error(error: impl ToString) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+fn names(cards: &[crate::poker::Card]) -> Vec<String> {
+    cards.iter().map(|card| format!("{:?}", card)).collect()
+}
+
+fn strict(cards: &str) -> Result<Cards, JsError> {
+    card::parse_joined(cards).map_err(error)
+}
+
+// the snake_case names used everywhere else, "texas" or "no_limit"
+fn parse<T: serde::de::DeserializeOwned>(s: &str) -> Result<T, JsError> {
+    serde_json::from_value(serde_json::Value::String(s.to_string())).map_err(|_| error(format!("unknown: {}", s)))
+}
+
+#[wasm_bindgen(js_name = parseCards)]
+pub fn parse_cards(s: &str) -> Vec<String> {
+    names(&card::parse(s))
+}
+
+#[wasm_bindgen]
+pub struct Evaluation {
+    hand: Hand,
+}
+
+#[wasm_bindgen]
+impl Evaluation {
+    // "two-pair", "flush" and so on
+    #[wasm_bindgen(getter)]
+    pub fn rank(&self) -> String {
+        match self.hand.rank() {
+            Some(Rank::High(rank)) => rank.name().to_string(),
+            _ => String::new(),
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn cards(&self) -> Vec<String> {
+        names(self.hand.cards())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn description(&self) -> String {
+        self.hand.description()
+    }
+
+    // 1 when this beats other, -1 when other does, 0 for a split
+    pub fn compare(&self, other: &Evaluation) -> i32 {
+        self.hand.partial_cmp(&other.hand).map_or(0, |order| order as i32)
+    }
+}
+
+// the best high hand, omaha plays exactly two pocket cards
+#[wasm_bindgen]
+pub fn evaluate(pocket: &str, board: &str, omaha: bool) -> Result<Evaluation, JsError> {
+    let hand = hand::best_high(&strict(pocket)?, &strict(board)?, omaha).ok_or_else(|| error("not enough cards for a hand"))?;
+    Ok(Evaluation { hand })
+}
+
+// shares of the pot for each hand, sampled from seed when there are too many boards to deal
+#[wasm_bindgen]
+pub fn equity(hands: Vec<String>, board: &str, dead: &str, omaha: bool, trials: u32, seed: u32) -> Result<Vec<f64>, JsError> {
+    let pockets = hands.iter().map(|hand| strict(hand)).collect::<Result<Vec<_>, _>>()?;
+    let mut calculator = Calculator::new(pockets).with_board(strict(board)?).with_dead(strict(dead)?).with_trials(trials).with_seed(seed as u64);
+    if omaha {
+        calculator = calculator.with_omaha();
+    }
+    let outcome = calculator.calculate().map_err(error)?;
+    Ok(outcome.equities.iter().map(|equity| equity.equity).collect())
+}
+
+// one local table, every seat played from the same page
+#[wasm_bindgen]
+pub struct PracticeTable {
+    game: Game<'static>,
+    feed: Feed,
+    events: VecDeque<Event>,
+}
+
+#[wasm_bindgen]
+impl PracticeTable {
+    #[wasm_bindgen(constructor)]
+    pub fn new(game: &str, limit: &str, big_blind: u32, table_size: u8, seed: u32) -> Result<PracticeTable, JsError> {
+        let name: Name = parse(game)?;
+        let limit: Limit = parse(limit)?;
+        if big_blind < 2 {
+            return Err(error("the big blind is at least 2"));
+        }
+        let game = Game::seeded(name, limit, Stake::new(Chips(big_blind as u64)), table_size, seed as u64, BrowserClock).with_name("practice");
+        Ok(PracticeTable { game, feed: Feed::default(), events: VecDeque::new() })
+    }
+
+    fn sync(&mut self) {
+        let finished = !self.game.is_hand_in_progress();
+        if let Some(history) = self.game.history() {
+            self.events.extend(self.feed.update(&Record::from(history), finished));
+        }
+    }
+
+    pub fn join(&mut self, position: u8, name: &str, stack: u32) -> Result<(), JsError> {
+        self.game.join(Player::blank_player(name), position, Chips(stack as u64)).map_err(error)
+    }
+
+    // what the seat takes away
+    pub fn leave(&mut self, position: u8) -> Result<u32, JsError> {
+        let cash_out = self.game.leave(position).map_err(error)?.unwrap_or_default();
+        self.sync();
+        Ok(cash_out.amount() as u32)
+    }
+
+    pub fn start(&mut self) -> Result<(), JsError> {
+        self.game.start().map_err(error)?;
+        self.sync();
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = toAct)]
+    pub fn to_act(&self) -> Option<u8> {
+        self.game.to_act()
+    }
+
+    // {"call": 10, "min_raise": 20, "max_raise": 1000, "all_in": 1000}, undefined when the seat can't act
+    pub fn range(&self, position: u8) -> Option<String> {
+        let range = self.game.range(position)?;
+        Some(serde_json::json!({
+            "call": range.call,
+            "min_raise": range.min_raise,
+            "max_raise": range.max_raise,
+            "all_in": range.all_in,
+        }).to_string())
+    }
+
+    // bet is check, fold, call or raise, raises are to a total for the street
+    pub fn act(&mut self, position: u8, bet: &str, amount: u32) -> Result<(), JsError> {
+        let amount = Chips(amount as u64);
+        let bet = match bet {
+            "check" => Bet::Check,
+            "fold" => Bet::Fold,
+            "call" => Bet::Call(amount),
+            "raise" => Bet::Raise(amount),
+            bet => return Err(error(format!("unknown bet: {}", bet))),
+        };
+        self.game.act(position, bet).map_err(error)?;
+        self.sync();
+        Ok(())
+    }
+
+    // everything since the last call as json, with other seats' cards hidden from viewer
+    pub fn events(&mut self, viewer: Option<u8>) -> Vec<String> {
+        self.events.drain(..)
+            .map(|event| serde_json::to_string(&event.masked(viewer.into())).expect("events always serialize"))
+            .collect()
+    }
+
+    // the table as viewer sees it, as json
+    pub fn table(&self, viewer: Option<u8>) -> String {
+        serde_json::to_string(&protocol::Table::view(&self.game, viewer.into())).expect("tables always serialize")
+    }
+}
+
+// wasm-pack test --node --no-default-features --features wasm
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    pub fn cards_and_hands() {
+        assert_eq!(parse_cards("AhKd 7c"), vec!["Ah", "Kd", "7c"]);
+        let pairs = evaluate("Ah Ad", "7c 7d 2s Kh 9c", false).unwrap();
+        assert_eq!((pairs.rank().as_str(), pairs.description().as_str()), ("two-pair", "two pairs, As and 7s"));
+        let quads = evaluate("7h 7s", "7c 7d 2s Kh 9c", false).unwrap();
+        assert_eq!(pairs.compare(&quads), -1);
+        assert!(evaluate("Ah Ax", "", false).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    pub fn seeded_equity() {
+        let hands = vec!["AhAd".to_string(), "KhKd".to_string()];
+        let equities = equity(hands.clone(), "2c 7s 9d Ks", "", false, 0, 0).unwrap();
+        assert_eq!(equities, vec![2.0 / 44.0, 42.0 / 44.0]);
+        assert_eq!(equity(hands.clone(), "", "", false, 500, 7).unwrap(), equity(hands, "", "", false, 500, 7).unwrap());
+    }
+
+    #[wasm_bindgen_test]
+    pub fn practice_hand() {
+        let mut table = PracticeTable::new("texas", "no_limit", 10, 6, 42).unwrap();
+        table.join(0, "you", 1000).unwrap();
+        table.join(3, "bot", 1000).unwrap();
+        table.start().unwrap();
+        let position = table.to_act().unwrap();
+        assert!(table.range(position).unwrap().contains(r#""call":5"#));
+        table.act(position, "fold", 0).unwrap();
+        assert_eq!(table.to_act(), None);
+
+        let events = table.events(Some(0));
+        assert!(events[0].starts_with(r#"{"type":"hand_started""#));
+        assert!(events.last().unwrap().starts_with(r#"{"type":"hand_finished""#));
+        assert!(table.events(Some(0)).is_empty());
+        // the same seed deals the same cards
+        let mut again = PracticeTable::new("texas", "no_limit", 10, 6, 42).unwrap();
+        again.join(0, "you", 1000).unwrap();
+        again.join(3, "bot", 1000).unwrap();
+        again.start().unwrap();
+        assert_eq!(again.events(Some(0))[1], events[1]);
+    }
+}